@@ -1,11 +1,13 @@
 #[cfg(not(all(
     any(feature = "x11", feature = "wayland"),
+    not(feature = "headless"),
     any(target_os = "freebsd", target_os = "linux", target_os = "openbsd")
 )))]
 fn main() {}
 
 #[cfg(all(
     any(feature = "x11", feature = "wayland"),
+    not(feature = "headless"),
     any(target_os = "freebsd", target_os = "linux", target_os = "openbsd")
 ))]
 fn main() {
@@ -184,10 +184,14 @@ impl WinHandler for WindowState {
                     inclination: info.inclination,
                     pos: event.pos,
                 });
+                // The pen draws while it touches the tablet
+                if let Some((line, _)) = self.touch_state.points.get_mut(&event.pointer_id) {
+                    Self::extend_line(line, &event);
+                }
             }
             PointerType::Touch(_) => {
                 if let Some((line, _)) = self.touch_state.points.get_mut(&event.pointer_id) {
-                    line.line_to(event.pos);
+                    Self::extend_line(line, &event);
                 } else {
                     tracing::warn!("moved an unknown finger");
                 }
@@ -196,9 +200,20 @@ impl WinHandler for WindowState {
         }
     }
 
+    /// Follow the pointer through the positions which were merged into `event`, so that fast
+    /// strokes stay smooth.
+    fn extend_line(line: &mut BezPath, event: &PointerEvent) {
+        for sample in &event.coalesced {
+            line.line_to(sample.pos);
+        }
+        line.line_to(event.pos);
+    }
+
     fn pointer_down(&mut self, event: PointerEvent) {
-        if let PointerType::Touch(_) = &event.pointer_type {
-            let color = if event.is_primary {
+        if let PointerType::Touch(_) | PointerType::Pen(_) = &event.pointer_type {
+            let color = if let PointerType::Pen(_) = &event.pointer_type {
+                Color::BLUE
+            } else if event.is_primary {
                 Color::RED
             } else {
                 self.finger_colors.next().unwrap()
@@ -212,7 +227,7 @@ impl WinHandler for WindowState {
     }
 
     fn pointer_up(&mut self, event: PointerEvent) {
-        if let PointerType::Touch(_) = &event.pointer_type {
+        if let PointerType::Touch(_) | PointerType::Pen(_) = &event.pointer_type {
             self.touch_state.points.remove(&event.pointer_id);
         }
     }
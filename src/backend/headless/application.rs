@@ -0,0 +1,329 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The headless application and its event loop.
+
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use instant::Instant;
+
+use crate::application::AppHandler;
+use crate::kurbo::Rect;
+use crate::platform::headless::ApplicationExt;
+use crate::scale::Scale;
+use crate::screen::Monitor;
+use crate::window::TimerToken;
+
+use super::clipboard::Clipboard;
+use super::error::Error;
+use super::window::WindowState;
+
+type MainThreadCb = Box<dyn FnOnce(Option<&mut dyn AppHandler>) + Send>;
+
+/// The callbacks from [`AppHandle::run_on_main`], which can be queued from any thread.
+#[derive(Default)]
+struct MainThreadQueue {
+    callbacks: Mutex<Vec<MainThreadCb>>,
+    /// Notified when a callback is queued.
+    queued: Condvar,
+}
+
+#[derive(Clone)]
+pub(crate) struct Application {
+    state: Rc<AppState>,
+}
+
+pub(super) struct AppState {
+    /// The backend's clock, which only moves when it is asked to.
+    now: Cell<Instant>,
+    scale: Cell<Scale>,
+    monitors: RefCell<Vec<Monitor>>,
+    /// The open windows, in the order they were created.
+    windows: RefCell<Vec<Rc<WindowState>>>,
+    /// The pending timers, in the order they were requested.
+    timers: RefCell<Vec<Timer>>,
+    main_thread_queue: Arc<MainThreadQueue>,
+    handler: RefCell<Option<Box<dyn AppHandler>>>,
+    clipboard: Clipboard,
+    primary: Clipboard,
+    quitting: Cell<bool>,
+}
+
+struct Timer {
+    deadline: Instant,
+    token: TimerToken,
+    window: Weak<WindowState>,
+}
+
+impl Application {
+    pub fn new() -> Result<Application, Error> {
+        let state = AppState {
+            now: Cell::new(Instant::now()),
+            scale: Cell::new(Scale::default()),
+            monitors: RefCell::new(default_monitors()),
+            windows: RefCell::new(Vec::new()),
+            timers: RefCell::new(Vec::new()),
+            main_thread_queue: Arc::default(),
+            handler: RefCell::new(None),
+            clipboard: Clipboard::default(),
+            primary: Clipboard::default(),
+            quitting: Cell::new(false),
+        };
+        Ok(Application {
+            state: Rc::new(state),
+        })
+    }
+
+    /// Step until quit is requested.
+    ///
+    /// When there is no work left, the clock jumps to the next timer. Without any timers, this
+    /// waits for a callback from [`AppHandle::run_on_main`], as nothing else can happen.
+    pub fn run(self, handler: Option<Box<dyn AppHandler>>) {
+        *self.state.handler.borrow_mut() = handler;
+        while !self.state.quitting.get() {
+            if !self.step() && !self.advance_to_next_timer() {
+                self.state.main_thread_queue.wait();
+            }
+        }
+        for window in self.state.windows.take() {
+            window.destroy();
+        }
+        self.state.timers.take();
+        self.state.handler.take();
+    }
+
+    pub fn quit(&self) {
+        self.state.quitting.set(true);
+    }
+
+    pub fn clipboard(&self) -> Clipboard {
+        self.state.clipboard.clone()
+    }
+
+    /// The locale is always the same, so that tests don't depend on the environment.
+    pub fn get_locale() -> String {
+        "en-US".into()
+    }
+
+    pub fn get_handle(&self) -> Option<AppHandle> {
+        Some(AppHandle {
+            queue: self.state.main_thread_queue.clone(),
+        })
+    }
+
+    pub(super) fn state(&self) -> &Rc<AppState> {
+        &self.state
+    }
+
+    pub(super) fn monitors(&self) -> Vec<Monitor> {
+        self.state.monitors.borrow().clone()
+    }
+
+    fn step(&self) -> bool {
+        let state = &self.state;
+        let mut did_work = false;
+
+        let callbacks = std::mem::take(&mut *state.main_thread_queue.callbacks.lock().unwrap());
+        for callback in callbacks {
+            let mut handler = state.handler.borrow_mut();
+            match handler.as_mut() {
+                Some(handler) => callback(Some(&mut **handler)),
+                None => callback(None),
+            }
+            did_work = true;
+        }
+
+        // Windows which are created during this step are handled in the next one.
+        let windows = state.windows.borrow().clone();
+        for window in &windows {
+            did_work |= window.process_events();
+        }
+        for window in &windows {
+            did_work |= window.process_idle();
+        }
+        did_work |= state.fire_timers();
+        for window in &windows {
+            did_work |= window.paint();
+        }
+        did_work
+    }
+
+    fn advance_to_next_timer(&self) -> bool {
+        let next = self
+            .state
+            .timers
+            .borrow()
+            .iter()
+            .map(|it| it.deadline)
+            .min();
+        match next {
+            Some(deadline) => {
+                if deadline > self.state.now.get() {
+                    self.state.now.set(deadline);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl AppState {
+    pub(super) fn now(&self) -> Instant {
+        self.now.get()
+    }
+
+    pub(super) fn scale(&self) -> Scale {
+        self.scale.get()
+    }
+
+    pub(super) fn add_window(&self, window: Rc<WindowState>) {
+        self.windows.borrow_mut().push(window);
+    }
+
+    pub(super) fn remove_window(&self, window: &WindowState) {
+        self.windows
+            .borrow_mut()
+            .retain(|it| !std::ptr::eq(&**it, window));
+    }
+
+    pub(super) fn windows(&self) -> Vec<Rc<WindowState>> {
+        self.windows.borrow().clone()
+    }
+
+    pub(super) fn add_timer(&self, deadline: Instant, window: &Rc<WindowState>) -> TimerToken {
+        let token = TimerToken::next();
+        self.timers.borrow_mut().push(Timer {
+            deadline,
+            token,
+            window: Rc::downgrade(window),
+        });
+        token
+    }
+
+    /// Fire the timers which are due, earliest first.
+    fn fire_timers(&self) -> bool {
+        let now = self.now.get();
+        let (mut due, pending): (Vec<_>, Vec<_>) = self
+            .timers
+            .take()
+            .into_iter()
+            .partition(|it| it.deadline <= now);
+        *self.timers.borrow_mut() = pending;
+        // The sort is stable, so timers with the same deadline fire in the order they were
+        // requested.
+        due.sort_by_key(|it| it.deadline);
+        let mut did_work = false;
+        for timer in due {
+            if let Some(window) = timer.window.upgrade() {
+                did_work |= window.timer(timer.token);
+            }
+        }
+        did_work
+    }
+}
+
+impl MainThreadQueue {
+    /// Block until a callback is queued.
+    fn wait(&self) {
+        let callbacks = self.callbacks.lock().unwrap();
+        let _callbacks = self
+            .queued
+            .wait_while(callbacks, |callbacks| callbacks.is_empty())
+            .unwrap();
+    }
+}
+
+/// A single 1920x1080 monitor.
+pub(super) fn default_monitors() -> Vec<Monitor> {
+    let rect = Rect::new(0.0, 0.0, 1920.0, 1080.0);
+    vec![Monitor::new(true, rect, rect)]
+}
+
+impl ApplicationExt for crate::Application {
+    fn step(&self) -> bool {
+        self.backend_app.step()
+    }
+
+    fn advance_time(&self, duration: Duration) {
+        let state = &self.backend_app.state;
+        state.now.set(state.now.get() + duration);
+    }
+
+    fn advance_to_next_timer(&self) -> bool {
+        self.backend_app.advance_to_next_timer()
+    }
+
+    fn now(&self) -> Instant {
+        self.backend_app.state.now.get()
+    }
+
+    fn set_monitors(&self, monitors: Vec<Monitor>) {
+        *self.backend_app.state.monitors.borrow_mut() = monitors;
+    }
+
+    fn set_scale(&self, scale: Scale) {
+        let state = &self.backend_app.state;
+        state.scale.set(scale);
+        for window in state.windows() {
+            window.set_scale(scale);
+        }
+    }
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "openbsd"))]
+impl crate::platform::linux::ApplicationExt for crate::Application {
+    fn primary_clipboard(&self) -> crate::Clipboard {
+        self.backend_app.state.primary.clone().into()
+    }
+
+    fn cursor_blink_interval(&self) -> Option<std::time::Duration> {
+        None
+    }
+
+    fn theme_name(&self) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl crate::platform::mac::ApplicationExt for crate::Application {
+    fn hide(&self) {}
+
+    fn hide_others(&self) {}
+
+    fn set_menu(&self, _menu: crate::Menu) {}
+}
+
+#[derive(Clone)]
+pub(crate) struct AppHandle {
+    queue: Arc<MainThreadQueue>,
+}
+
+impl AppHandle {
+    pub fn run_on_main<F>(&self, callback: F)
+    where
+        F: FnOnce(Option<&mut dyn AppHandler>) + Send + 'static,
+    {
+        self.queue
+            .callbacks
+            .lock()
+            .unwrap()
+            .push(Box::new(callback));
+        self.queue.queued.notify_one();
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An in-memory clipboard.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::clipboard::{ClipboardFormat, FormatId};
+
+/// A clipboard which is only shared within the application.
+#[derive(Debug, Clone, Default)]
+pub struct Clipboard(Rc<RefCell<Vec<ClipboardFormat>>>);
+
+impl Clipboard {
+    /// Put a string onto the clipboard.
+    pub fn put_string(&mut self, s: impl AsRef<str>) {
+        self.put_formats(&[ClipboardFormat::from(s.as_ref())]);
+    }
+
+    /// Put multi-format data on the clipboard.
+    pub fn put_formats(&mut self, formats: &[ClipboardFormat]) {
+        *self.0.borrow_mut() = formats.to_vec();
+    }
+
+    /// Get a string from the clipboard, if one is available.
+    pub fn get_string(&self) -> Option<String> {
+        self.get_format(ClipboardFormat::TEXT)
+            .and_then(|data| String::from_utf8(data).ok())
+    }
+
+    /// Given a list of supported clipboard types, returns the supported type which has
+    /// highest priority on the clipboard, or `None` if no types are supported.
+    pub fn preferred_format(&self, formats: &[FormatId]) -> Option<FormatId> {
+        let contents = self.0.borrow();
+        formats
+            .iter()
+            .find(|format| contents.iter().any(|it| it.identifier == **format))
+            .copied()
+    }
+
+    /// Return data in a given format, if available.
+    pub fn get_format(&self, format: FormatId) -> Option<Vec<u8>> {
+        self.0
+            .borrow()
+            .iter()
+            .find(|it| it.identifier == format)
+            .map(|it| it.data.clone())
+    }
+
+    pub fn available_type_names(&self) -> Vec<String> {
+        self.0
+            .borrow()
+            .iter()
+            .map(|it| it.identifier.to_string())
+            .collect()
+    }
+}
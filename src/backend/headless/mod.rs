@@ -0,0 +1,25 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A backend without a display, which keeps all of its state in memory.
+//!
+//! It is driven through the traits in [`crate::platform::headless`].
+
+pub mod application;
+pub mod clipboard;
+pub mod dnd;
+pub mod error;
+pub mod menu;
+pub mod screen;
+pub mod window;
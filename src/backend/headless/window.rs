@@ -0,0 +1,683 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Windows which only exist in memory.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use instant::Instant;
+use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle, WebDisplayHandle,
+    WebWindowHandle,
+};
+
+use crate::clipboard::ClipboardFormat;
+use crate::common_util::IdleCallback;
+use crate::dialog::FileDialogOptions;
+use crate::dnd::{DragIcon, DropAction};
+use crate::error::Error as ShellError;
+use crate::icon::Icon;
+use crate::kurbo::{Insets, Point, Rect, Size};
+use crate::mouse::{Cursor, CursorDesc};
+use crate::pixels::{PixelBuffer, PixelFormat};
+use crate::platform::headless::{InjectedEvent, WindowHandleExt};
+use crate::region::Region;
+use crate::scale::Scale;
+use crate::text::{simulate_input, Event, InputHandler};
+use crate::window;
+use crate::window::{
+    FileDialogToken, IdleToken, TextFieldToken, TimerToken, WinHandler, WindowLevel,
+};
+
+use super::application::{AppState, Application};
+use super::menu::Menu;
+
+pub(crate) struct WindowBuilder {
+    app: Application,
+    handler: Option<Box<dyn WinHandler>>,
+    size: Size,
+    position: Point,
+    state: window::WindowState,
+}
+
+#[derive(Clone, Default)]
+pub struct WindowHandle(Weak<WindowState>);
+
+impl PartialEq for WindowHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+}
+
+impl Eq for WindowHandle {}
+
+/// A handle that can get used to schedule an idle handler. Note that
+/// this handle is thread safe.
+#[derive(Clone)]
+pub struct IdleHandle {
+    queue: Arc<Mutex<Vec<IdleKind>>>,
+}
+
+enum IdleKind {
+    Callback(IdleCallback),
+    Token(IdleToken),
+}
+
+/// Work for a window, which is done on the next step of the application.
+enum Task {
+    Inject(InjectedEvent),
+    SetScale(Scale),
+    /// Nothing can accept a drag, so drags are cancelled straight away.
+    CancelDrag,
+    Close,
+}
+
+pub(super) struct WindowState {
+    app: Weak<AppState>,
+    handler: RefCell<Box<dyn WinHandler>>,
+    tasks: RefCell<VecDeque<Task>>,
+    idle_queue: Arc<Mutex<Vec<IdleKind>>>,
+    size: Cell<Size>,
+    position: Cell<Point>,
+    scale: Cell<Scale>,
+    state: Cell<window::WindowState>,
+    visible: Cell<bool>,
+    focused: Cell<bool>,
+    destroyed: Cell<bool>,
+    invalid: RefCell<Region>,
+    anim_frame_requested: Cell<bool>,
+    active_text_field: Cell<Option<TextFieldToken>>,
+    /// The pixels shown by `present_pixels`, in BGRA.
+    framebuffer: RefCell<Option<PixelBuffer>>,
+}
+
+/// Custom cursors are accepted, but never shown.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CustomCursor;
+
+impl WindowBuilder {
+    pub fn new(app: Application) -> WindowBuilder {
+        WindowBuilder {
+            app,
+            handler: None,
+            size: Size::new(500.0, 400.0),
+            position: Point::ZERO,
+            state: window::WindowState::Restored,
+        }
+    }
+
+    pub fn handler(mut self, handler: Box<dyn WinHandler>) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    pub fn size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn min_size(self, _size: Size) -> Self {
+        self
+    }
+
+    pub fn resizable(self, _resizable: bool) -> Self {
+        self
+    }
+
+    pub fn show_titlebar(self, _show_titlebar: bool) -> Self {
+        self
+    }
+
+    pub fn transparent(self, _transparent: bool) -> Self {
+        self
+    }
+
+    pub fn position(mut self, position: Point) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn window_state(mut self, state: window::WindowState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn level(self, _level: WindowLevel) -> Self {
+        self
+    }
+
+    pub fn title<S: Into<String>>(self, _title: S) -> Self {
+        self
+    }
+
+    pub fn icon(self, _icon: Icon) -> Self {
+        self
+    }
+
+    pub fn menu(self, _menu: Menu) -> Self {
+        self
+    }
+
+    pub fn build(self) -> Result<WindowHandle, ShellError> {
+        let handler = self
+            .handler
+            .ok_or_else(|| anyhow!("WindowBuilder::build called without a handler"))?;
+        let app = self.app.state();
+        let scale = app.scale();
+        let window = Rc::new(WindowState {
+            app: Rc::downgrade(app),
+            handler: RefCell::new(handler),
+            tasks: RefCell::new(VecDeque::new()),
+            idle_queue: Arc::new(Mutex::new(Vec::new())),
+            size: Cell::new(self.size),
+            position: Cell::new(self.position),
+            scale: Cell::new(scale),
+            state: Cell::new(self.state),
+            visible: Cell::new(false),
+            focused: Cell::new(false),
+            destroyed: Cell::new(false),
+            invalid: RefCell::new(Region::EMPTY),
+            anim_frame_requested: Cell::new(false),
+            active_text_field: Cell::new(None),
+            framebuffer: RefCell::new(None),
+        });
+        app.add_window(window.clone());
+
+        let handle = WindowHandle(Rc::downgrade(&window));
+        window.with_handler(|h| {
+            h.connect(&handle.clone().into());
+            h.scale(scale);
+            h.size(self.size);
+        });
+        Ok(handle)
+    }
+}
+
+impl WindowState {
+    fn with_handler<T>(&self, f: impl FnOnce(&mut dyn WinHandler) -> T) -> Option<T> {
+        match self.handler.try_borrow_mut() {
+            Ok(mut handler) => Some(f(&mut **handler)),
+            Err(_) => {
+                tracing::error!("failed to borrow WinHandler");
+                None
+            }
+        }
+    }
+
+    fn present_pixels(&self, pixels: &PixelBuffer, damage: Option<&Region>) {
+        let mut framebuffer = self.framebuffer.borrow_mut();
+        let (width, height) = (pixels.width(), pixels.height());
+        // Like a new buffer on a real platform, a buffer with a different size starts out empty.
+        if !matches!(&*framebuffer, Some(old) if old.width() == width && old.height() == height) {
+            *framebuffer = Some(PixelBuffer::new(width, height, PixelFormat::Bgra8));
+        }
+        let framebuffer = framebuffer.as_mut().unwrap();
+        let stride = width as usize * 4;
+        for rect in pixels.damage_rects(damage, self.scale.get()) {
+            let offset = rect.y as usize * stride + rect.x as usize * 4;
+            pixels.copy_bgra(rect, &mut framebuffer.data_mut()[offset..], stride, false);
+        }
+    }
+
+    /// Run `f` with the focused text field, if there is one.
+    fn with_input_handler(&self, f: impl FnOnce(&mut dyn InputHandler)) {
+        let Some(token) = self.active_text_field.get() else {
+            return;
+        };
+        self.with_handler(|handler| {
+            let mut input_handler = handler.acquire_input_lock(token, true);
+            f(&mut *input_handler);
+            handler.release_input_lock(token);
+        });
+    }
+
+    fn push_task(&self, task: Task) {
+        self.tasks.borrow_mut().push_back(task);
+    }
+
+    fn invalidate(&self) {
+        let rect = self.size.get().to_rect();
+        self.invalid.borrow_mut().set_rect(rect);
+    }
+
+    pub(super) fn set_scale(&self, scale: Scale) {
+        self.push_task(Task::SetScale(scale));
+    }
+
+    pub(super) fn process_events(&self) -> bool {
+        let tasks = self.tasks.take();
+        let did_work = !tasks.is_empty();
+        for task in tasks {
+            if self.destroyed.get() {
+                break;
+            }
+            match task {
+                Task::Inject(event) => self.handle_event(event),
+                Task::SetScale(scale) => {
+                    self.scale.set(scale);
+                    let size = self.size.get();
+                    self.with_handler(|h| {
+                        h.scale(scale);
+                        h.size(size);
+                    });
+                    self.invalidate();
+                }
+                Task::CancelDrag => {
+                    self.with_handler(|h| h.dnd_finished(DropAction::None));
+                }
+                Task::Close => self.destroy(),
+            }
+        }
+        did_work
+    }
+
+    fn handle_event(&self, event: InjectedEvent) {
+        match event {
+            InjectedEvent::KeyDown(event) => {
+                let token = self.active_text_field.get();
+                self.with_handler(|h| simulate_input(h, token, event));
+            }
+            InjectedEvent::KeyUp(event) => {
+                self.with_handler(|h| h.key_up(event));
+            }
+            InjectedEvent::PointerDown(event) => {
+                self.with_handler(|h| h.pointer_down(event));
+            }
+            InjectedEvent::PointerUp(event) => {
+                self.with_handler(|h| h.pointer_up(event));
+            }
+            InjectedEvent::PointerMove(event) => {
+                self.with_handler(|h| h.pointer_move(event));
+            }
+            InjectedEvent::PointerEnter(event) => {
+                self.with_handler(|h| h.pointer_enter(event));
+            }
+            InjectedEvent::PointerLeave => {
+                self.with_handler(|h| h.pointer_leave());
+            }
+            InjectedEvent::Wheel(event) => {
+                self.with_handler(|h| h.wheel(event));
+            }
+            InjectedEvent::Zoom(delta) => {
+                self.with_handler(|h| h.zoom(delta));
+            }
+            InjectedEvent::Resize(size) => {
+                self.size.set(size);
+                self.with_handler(|h| h.size(size));
+                self.invalidate();
+            }
+            InjectedEvent::Move(position) => {
+                self.position.set(position);
+                self.with_handler(|h| h.position(position));
+            }
+            InjectedEvent::Focus(true) => {
+                if !self.focused.replace(true) {
+                    let others = self.app.upgrade().map(|app| app.windows());
+                    for other in others.into_iter().flatten() {
+                        if !std::ptr::eq(&*other, self) && other.focused.replace(false) {
+                            other.with_handler(|h| h.lost_focus());
+                        }
+                    }
+                    self.with_handler(|h| h.got_focus());
+                }
+            }
+            InjectedEvent::Focus(false) => {
+                if self.focused.replace(false) {
+                    self.with_handler(|h| h.lost_focus());
+                }
+            }
+            InjectedEvent::CompositionUpdate(text) => {
+                self.with_input_handler(|input_handler| {
+                    let range = input_handler
+                        .composition_range()
+                        .unwrap_or_else(|| input_handler.selection().range());
+                    let start = range.start;
+                    input_handler.replace_range(range, &text);
+                    if text.is_empty() {
+                        input_handler.set_composition_range(None);
+                    } else {
+                        input_handler.set_composition_range(Some(start..(start + text.len())));
+                    }
+                });
+            }
+            InjectedEvent::CompositionCommit(text) => {
+                self.with_input_handler(|input_handler| {
+                    let range = input_handler
+                        .composition_range()
+                        .unwrap_or_else(|| input_handler.selection().range());
+                    input_handler.replace_range(range, &text);
+                    input_handler.set_composition_range(None);
+                });
+            }
+            InjectedEvent::RequestClose => {
+                self.with_handler(|h| h.request_close());
+            }
+        }
+    }
+
+    pub(super) fn process_idle(&self) -> bool {
+        let queue = std::mem::take(&mut *self.idle_queue.lock().unwrap());
+        let did_work = !queue.is_empty();
+        for item in queue {
+            if self.destroyed.get() {
+                break;
+            }
+            match item {
+                IdleKind::Callback(callback) => {
+                    self.with_handler(callback);
+                }
+                IdleKind::Token(token) => {
+                    self.with_handler(|h| h.idle(token));
+                }
+            }
+        }
+        did_work
+    }
+
+    pub(super) fn timer(&self, token: TimerToken) -> bool {
+        if self.destroyed.get() {
+            return false;
+        }
+        self.with_handler(|h| h.timer(token));
+        true
+    }
+
+    /// Paint the window, if it is visible and there is something to paint.
+    pub(super) fn paint(&self) -> bool {
+        if self.destroyed.get() || !self.visible.get() {
+            return false;
+        }
+        let anim_frame_requested = self.anim_frame_requested.replace(false);
+        if !anim_frame_requested && self.invalid.borrow().is_empty() {
+            return false;
+        }
+        self.with_handler(|h| h.prepare_paint());
+        let mut invalid = std::mem::replace(&mut *self.invalid.borrow_mut(), Region::EMPTY);
+        invalid.intersect_with(self.size.get().to_rect());
+        if !invalid.is_empty() {
+            self.with_handler(|h| h.paint(&invalid));
+        }
+        true
+    }
+
+    pub(super) fn destroy(&self) {
+        if self.destroyed.replace(true) {
+            return;
+        }
+        self.with_handler(|h| h.destroy());
+        if let Some(app) = self.app.upgrade() {
+            app.remove_window(self);
+        }
+    }
+}
+
+impl WindowHandle {
+    pub fn show(&self) {
+        if let Some(window) = self.0.upgrade() {
+            window.visible.set(true);
+            window.invalidate();
+        }
+    }
+
+    pub fn resizable(&self, _resizable: bool) {}
+
+    pub fn show_titlebar(&self, _show_titlebar: bool) {}
+
+    /// The window is moved on the next step, as if the platform had accepted the position.
+    pub fn set_position(&self, position: Point) {
+        if let Some(window) = self.0.upgrade() {
+            window.push_task(Task::Inject(InjectedEvent::Move(position)));
+        }
+    }
+
+    pub fn get_position(&self) -> Point {
+        self.0
+            .upgrade()
+            .map(|window| window.position.get())
+            .unwrap_or_default()
+    }
+
+    /// The window is resized on the next step, as if the platform had accepted the size.
+    pub fn set_size(&self, size: Size) {
+        if let Some(window) = self.0.upgrade() {
+            window.push_task(Task::Inject(InjectedEvent::Resize(size)));
+        }
+    }
+
+    pub fn get_size(&self) -> Size {
+        self.0
+            .upgrade()
+            .map(|window| window.size.get())
+            .unwrap_or_default()
+    }
+
+    pub fn set_min_size(&self, _size: Size) {}
+
+    pub fn set_max_size(&self, _size: Option<Size>) {}
+
+    pub fn set_resize_increments(&self, _increments: Option<Size>) {}
+
+    pub fn set_aspect_ratio(&self, _ratio: Option<f64>) {}
+
+    pub fn set_input_region(&self, _region: Option<Region>) {}
+
+    pub fn set_opaque_region(&self, _region: Option<Region>) {}
+
+    pub fn content_insets(&self) -> Insets {
+        Insets::ZERO
+    }
+
+    pub fn set_window_state(&self, state: window::WindowState) {
+        if let Some(window) = self.0.upgrade() {
+            window.state.set(state);
+        }
+    }
+
+    pub fn get_window_state(&self) -> window::WindowState {
+        self.0
+            .upgrade()
+            .map(|window| window.state.get())
+            .unwrap_or(window::WindowState::Restored)
+    }
+
+    pub fn handle_titlebar(&self, _val: bool) {}
+
+    pub fn begin_move_drag(&self) {}
+
+    pub fn begin_resize_drag(&self, _edge: window::ResizeEdge) {}
+
+    pub fn show_window_menu(&self, _pos: Point) {}
+
+    pub fn set_pointer_grab(&self, _grab: window::PointerGrab) {}
+
+    /// The window is destroyed on the next step.
+    pub fn close(&self) {
+        if let Some(window) = self.0.upgrade() {
+            window.push_task(Task::Close);
+        }
+    }
+
+    pub fn bring_to_front_and_focus(&self) {
+        if let Some(window) = self.0.upgrade() {
+            window.push_task(Task::Inject(InjectedEvent::Focus(true)));
+        }
+    }
+
+    pub fn request_anim_frame(&self) {
+        if let Some(window) = self.0.upgrade() {
+            window.anim_frame_requested.set(true);
+        }
+    }
+
+    pub fn invalidate(&self) {
+        if let Some(window) = self.0.upgrade() {
+            window.invalidate();
+        }
+    }
+
+    pub fn invalidate_rect(&self, rect: Rect) {
+        if let Some(window) = self.0.upgrade() {
+            window.invalid.borrow_mut().add_rect(rect);
+        }
+    }
+
+    pub fn present_pixels(&self, buffer: &PixelBuffer, damage: Option<&Region>) {
+        if let Some(window) = self.0.upgrade() {
+            window.present_pixels(buffer, damage);
+        }
+    }
+
+    pub fn set_title(&self, _title: &str) {}
+
+    pub fn set_icon(&self, _icon: Option<&Icon>) {}
+
+    pub fn set_menu(&self, _menu: Menu) {}
+
+    pub fn show_context_menu(&self, _menu: Menu, _pos: Point) {}
+
+    pub fn add_text_field(&self) -> TextFieldToken {
+        TextFieldToken::next()
+    }
+
+    pub fn remove_text_field(&self, token: TextFieldToken) {
+        if let Some(window) = self.0.upgrade() {
+            if window.active_text_field.get() == Some(token) {
+                window.active_text_field.set(None);
+            }
+        }
+    }
+
+    pub fn set_focused_text_field(&self, active_field: Option<TextFieldToken>) {
+        if let Some(window) = self.0.upgrade() {
+            window.active_text_field.set(active_field);
+        }
+    }
+
+    pub fn update_text_field(&self, _token: TextFieldToken, _update: Event) {
+        // There is no input method to tell about the change.
+    }
+
+    pub fn request_timer(&self, deadline: Instant) -> TimerToken {
+        let (Some(window), Some(app)) = (self.0.upgrade(), self.app()) else {
+            return TimerToken::INVALID;
+        };
+        // The deadline is on the real clock, so we only keep the delay.
+        let delay = deadline.saturating_duration_since(Instant::now());
+        app.add_timer(app.now() + delay, &window)
+    }
+
+    pub fn set_cursor(&mut self, _cursor: &Cursor) {}
+
+    pub fn make_cursor(&self, _desc: &CursorDesc) -> Option<Cursor> {
+        Some(Cursor::Custom(CustomCursor))
+    }
+
+    /// There is no user to choose a file, so dialogs are never shown.
+    pub fn open_file(&mut self, _options: FileDialogOptions) -> Option<FileDialogToken> {
+        None
+    }
+
+    /// There is no user to choose a file, so dialogs are never shown.
+    pub fn save_as(&mut self, _options: FileDialogOptions) -> Option<FileDialogToken> {
+        None
+    }
+
+    pub fn set_drop_target(&self, _enabled: bool) {
+        // Nothing can be dragged onto the window.
+    }
+
+    pub fn start_drag(
+        &self,
+        _formats: &[ClipboardFormat],
+        _icon: Option<DragIcon>,
+        _actions: &[DropAction],
+    ) {
+        if let Some(window) = self.0.upgrade() {
+            window.push_task(Task::CancelDrag);
+        }
+    }
+
+    pub fn get_idle_handle(&self) -> Option<IdleHandle> {
+        self.0.upgrade().map(|window| IdleHandle {
+            queue: window.idle_queue.clone(),
+        })
+    }
+
+    pub fn get_scale(&self) -> Result<Scale, ShellError> {
+        Ok(self
+            .0
+            .upgrade()
+            .ok_or(ShellError::WindowDropped)?
+            .scale
+            .get())
+    }
+
+    #[cfg(feature = "accesskit")]
+    pub fn update_accesskit_if_active(
+        &self,
+        _update_factory: impl FnOnce() -> accesskit::TreeUpdate,
+    ) {
+        // AccessKit doesn't yet support this backend.
+    }
+
+    fn app(&self) -> Option<Rc<AppState>> {
+        self.0.upgrade().and_then(|window| window.app.upgrade())
+    }
+}
+
+impl WindowHandleExt for crate::WindowHandle {
+    fn inject(&self, event: InjectedEvent) {
+        if let Some(window) = self.0 .0.upgrade() {
+            window.push_task(Task::Inject(event));
+        }
+    }
+
+    fn presented_pixels(&self) -> Option<PixelBuffer> {
+        self.0 .0.upgrade()?.framebuffer.borrow().clone()
+    }
+}
+
+unsafe impl HasRawWindowHandle for WindowHandle {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        // raw-window-handle has no handle for windows without a display. An empty web handle
+        // doesn't refer to anything, so renderers will fail to use it.
+        RawWindowHandle::Web(WebWindowHandle::empty())
+    }
+}
+
+unsafe impl HasRawDisplayHandle for WindowHandle {
+    fn raw_display_handle(&self) -> RawDisplayHandle {
+        RawDisplayHandle::Web(WebDisplayHandle::empty())
+    }
+}
+
+impl IdleHandle {
+    /// Add an idle handler, which is called (once) on the next step of the application.
+    pub fn add_idle_callback<F>(&self, callback: F)
+    where
+        F: FnOnce(&mut dyn WinHandler) + Send + 'static,
+    {
+        let mut queue = self.queue.lock().expect("IdleHandle::add_idle queue");
+        queue.push(IdleKind::Callback(Box::new(callback)));
+    }
+
+    pub fn add_idle_token(&self, token: IdleToken) {
+        let mut queue = self.queue.lock().expect("IdleHandle::add_idle queue");
+        queue.push(IdleKind::Token(token));
+    }
+}
@@ -114,4 +114,22 @@ impl crate::platform::linux::ApplicationExt for crate::Application {
             Application::Wayland(_) => unimplemented!(),
         }
     }
+
+    fn cursor_blink_interval(&self) -> Option<std::time::Duration> {
+        match &self.backend_app {
+            #[cfg(feature = "x11")]
+            Application::X11(it) => it.cursor_blink_interval(),
+            #[cfg(feature = "wayland")]
+            Application::Wayland(_) => None,
+        }
+    }
+
+    fn theme_name(&self) -> Option<String> {
+        match &self.backend_app {
+            #[cfg(feature = "x11")]
+            Application::X11(it) => it.theme_name(),
+            #[cfg(feature = "wayland")]
+            Application::Wayland(_) => None,
+        }
+    }
 }
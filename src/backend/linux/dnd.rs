@@ -0,0 +1,51 @@
+#[cfg(feature = "wayland")]
+use crate::backend::wayland;
+#[cfg(feature = "x11")]
+use crate::backend::x11;
+use crate::FormatId;
+
+#[derive(Debug, Clone)]
+pub enum DropData {
+    #[cfg(feature = "x11")]
+    X11(x11::dnd::DropData),
+    #[cfg(feature = "wayland")]
+    Wayland(wayland::dnd::DropData),
+}
+
+impl DropData {
+    pub fn get_string(&self) -> Option<String> {
+        match self {
+            #[cfg(feature = "x11")]
+            DropData::X11(data) => data.get_string(),
+            #[cfg(feature = "wayland")]
+            DropData::Wayland(data) => data.get_string(),
+        }
+    }
+
+    pub fn preferred_format(&self, formats: &[FormatId]) -> Option<FormatId> {
+        match self {
+            #[cfg(feature = "x11")]
+            DropData::X11(data) => data.preferred_format(formats),
+            #[cfg(feature = "wayland")]
+            DropData::Wayland(data) => data.preferred_format(formats),
+        }
+    }
+
+    pub fn get_format(&self, format: FormatId) -> Option<Vec<u8>> {
+        match self {
+            #[cfg(feature = "x11")]
+            DropData::X11(data) => data.get_format(format),
+            #[cfg(feature = "wayland")]
+            DropData::Wayland(data) => data.get_format(format),
+        }
+    }
+
+    pub fn available_type_names(&self) -> Vec<String> {
+        match self {
+            #[cfg(feature = "x11")]
+            DropData::X11(data) => data.available_type_names(),
+            #[cfg(feature = "wayland")]
+            DropData::Wayland(data) => data.available_type_names(),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod application;
 pub mod clipboard;
+pub mod dnd;
 pub mod error;
 pub mod menu;
 pub mod screen;
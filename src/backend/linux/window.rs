@@ -9,8 +9,9 @@ use crate::backend::wayland;
 #[cfg(feature = "x11")]
 use crate::backend::x11;
 use crate::{
-    text::Event, Cursor, CursorDesc, Error, FileDialogOptions, FileDialogToken, IdleToken, Scale,
-    TextFieldToken, TimerToken, WinHandler, WindowLevel, WindowState,
+    text::Event, ClipboardFormat, Cursor, CursorDesc, DragIcon, DropAction, Error,
+    FileDialogOptions, FileDialogToken, Icon, IdleToken, PixelBuffer, PointerGrab, Region,
+    ResizeEdge, Scale, TextFieldToken, TimerToken, WinHandler, WindowLevel, WindowState,
 };
 
 use super::{application::Application, menu::Menu};
@@ -32,6 +33,15 @@ impl CustomCursor {
             CustomCursor::Wayland(_) => panic!("Must use an X11 custom cursor here"),
         }
     }
+
+    #[cfg(feature = "wayland")]
+    pub(crate) fn unwrap_wayland(&self) -> &wayland::window::CustomCursor {
+        match self {
+            CustomCursor::Wayland(it) => it,
+            #[cfg(feature = "x11")]
+            CustomCursor::X11(_) => panic!("Must use a Wayland custom cursor here"),
+        }
+    }
 }
 
 pub(crate) enum WindowBuilder {
@@ -147,6 +157,16 @@ impl WindowBuilder {
         self
     }
 
+    pub fn icon(mut self, icon: Icon) -> Self {
+        self = match self {
+            #[cfg(feature = "x11")]
+            WindowBuilder::X11(builder) => WindowBuilder::X11(builder.icon(icon)),
+            #[cfg(feature = "wayland")]
+            WindowBuilder::Wayland(builder) => WindowBuilder::Wayland(builder.icon(icon)),
+        };
+        self
+    }
+
     pub fn menu(mut self, menu: Menu) -> Self {
         self = match self {
             #[cfg(feature = "x11")]
@@ -258,14 +278,15 @@ impl From<x11::window::WindowHandle> for crate::WindowHandle {
 }
 
 impl WindowHandle {
-    // #[cfg(feature = "wayland")]
-    // /// Assume that this WindowHandle is from Wayland
-    // pub(crate) fn unwrap_wayland(&self) -> &wayland::window::WindowHandle {
-    //     match self {
-    //         WindowHandle::Wayland(it) => it,
-    //         _ => unreachable!("Must use a wayland window handle"),
-    //     }
-    // }
+    #[cfg(feature = "wayland")]
+    /// Assume that this WindowHandle is from Wayland
+    pub(crate) fn unwrap_wayland(&self) -> &wayland::window::WindowHandle {
+        match self {
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(it) => it,
+            _ => unreachable!("Must use a wayland window handle"),
+        }
+    }
     #[cfg(feature = "x11")]
     /// Assume that this WindowHandle is from X11
     pub(crate) fn unwrap_x11(&self) -> &x11::window::WindowHandle {
@@ -430,6 +451,106 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_pointer_grab(&self, grab: PointerGrab) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.set_pointer_grab(grab),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.set_pointer_grab(grab),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn set_input_region(&self, region: Option<Region>) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.set_input_region(region),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.set_input_region(region),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn set_opaque_region(&self, region: Option<Region>) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.set_opaque_region(region),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.set_opaque_region(region),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn begin_move_drag(&self) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.begin_move_drag(),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.begin_move_drag(),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn begin_resize_drag(&self, edge: ResizeEdge) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.begin_resize_drag(edge),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.begin_resize_drag(edge),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn show_window_menu(&self, pos: Point) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.show_window_menu(pos),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.show_window_menu(pos),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn set_min_size(&self, size: Size) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.set_min_size(size),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.set_min_size(size),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn set_max_size(&self, size: Option<Size>) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.set_max_size(size),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.set_max_size(size),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn set_resize_increments(&self, increments: Option<Size>) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.set_resize_increments(increments),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.set_resize_increments(increments),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn set_aspect_ratio(&self, ratio: Option<f64>) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.set_aspect_ratio(ratio),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.set_aspect_ratio(ratio),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
     pub fn bring_to_front_and_focus(&self) {
         match self {
             #[cfg(feature = "x11")]
@@ -486,6 +607,20 @@ impl WindowHandle {
         }
     }
 
+    pub fn present_pixels(&self, buffer: &PixelBuffer, damage: Option<&Region>) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => {
+                handle.present_pixels(buffer, damage);
+            }
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => {
+                handle.present_pixels(buffer, damage);
+            }
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
     pub fn set_title(&self, title: &str) {
         match self {
             #[cfg(feature = "x11")]
@@ -500,6 +635,20 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_icon(&self, icon: Option<&Icon>) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => {
+                handle.set_icon(icon);
+            }
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => {
+                handle.set_icon(icon);
+            }
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
     pub fn set_menu(&self, menu: Menu) {
         match self {
             #[cfg(feature = "x11")]
@@ -658,6 +807,31 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_drop_target(&self, enabled: bool) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.set_drop_target(enabled),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.set_drop_target(enabled),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
+    pub fn start_drag(
+        &self,
+        formats: &[ClipboardFormat],
+        icon: Option<DragIcon>,
+        actions: &[DropAction],
+    ) {
+        match self {
+            #[cfg(feature = "x11")]
+            WindowHandle::X11(handle) => handle.start_drag(formats, icon, actions),
+            #[cfg(feature = "wayland")]
+            WindowHandle::Wayland(handle) => handle.start_drag(formats, icon, actions),
+            WindowHandle::None => panic!("Used an uninitialised WindowHandle"),
+        }
+    }
+
     pub fn get_idle_handle(&self) -> Option<IdleHandle> {
         match self {
             #[cfg(feature = "x11")]
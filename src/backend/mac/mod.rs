@@ -20,6 +20,7 @@ pub mod appkit;
 pub mod application;
 pub mod clipboard;
 pub mod dialog;
+pub mod dnd;
 pub mod error;
 mod keyboard;
 pub mod menu;
@@ -19,7 +19,7 @@
 use std::ffi::c_void;
 use std::mem;
 use std::sync::{Arc, Mutex, Weak};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "accesskit")]
 use accesskit_macos::Adapter as AccessKitAdapter;
@@ -57,18 +57,24 @@ use super::keyboard::{make_modifiers, KeyboardState};
 use super::menu::Menu;
 use super::text_input::NSRange;
 use super::util::{assert_main_thread, make_nsstring};
-use crate::common_util::IdleCallback;
+use crate::clipboard::ClipboardFormat;
+use crate::common_util::{instant_from_age, IdleCallback};
 use crate::dialog::{FileDialogOptions, FileDialogType};
+use crate::dnd::{DragIcon, DropAction};
+use crate::icon::Icon;
 use crate::keyboard_types::KeyState;
 use crate::mouse::{Cursor, CursorDesc};
+use crate::pixels::PixelBuffer;
 use crate::pointer::{
-    MouseInfo, PointerButton, PointerButtons, PointerEvent, PointerId, PointerType,
+    MouseInfo, PointerButton, PointerButtons, PointerEvent, PointerId, PointerType, ScrollInfo,
+    ScrollPhase, ScrollSource, ScrollUnit,
 };
 use crate::region::Region;
 use crate::scale::Scale;
 use crate::text::{Event, InputHandler};
 use crate::window::{
-    FileDialogToken, IdleToken, TextFieldToken, TimerToken, WinHandler, WindowLevel, WindowState,
+    FileDialogToken, IdleToken, PointerGrab, ResizeEdge, TextFieldToken, TimerToken, WinHandler,
+    WindowLevel, WindowState,
 };
 use crate::Error;
 
@@ -98,6 +104,9 @@ mod levels {
             Tooltip(_) => NSFloatingWindowLevel,
             DropDown(_) => NSFloatingWindowLevel,
             Modal(_) => NSModalPanelWindowLevel,
+            AlwaysOnTop => NSFloatingWindowLevel,
+            Utility(_) => NSFloatingWindowLevel,
+            Notification => NSStatusWindowLevel,
         }
     }
 }
@@ -267,6 +276,11 @@ impl WindowBuilder {
         self
     }
 
+    pub fn icon(self, _icon: Icon) -> Self {
+        // macOS windows don't have icons, the application's icon is shown instead.
+        self
+    }
+
     pub fn menu(mut self, menu: Menu) -> Self {
         self.menu = Some(menu);
         self
@@ -352,6 +366,7 @@ impl WindowBuilder {
                     WindowLevel::Tooltip(parent) => view_state.parent = Some(parent.clone()),
                     WindowLevel::DropDown(parent) => view_state.parent = Some(parent.clone()),
                     WindowLevel::Modal(parent) => view_state.parent = Some(parent.clone()),
+                    WindowLevel::Utility(parent) => view_state.parent = Some(parent.clone()),
                     _ => {}
                 }
                 handle.set_level(level);
@@ -702,17 +717,32 @@ fn mouse_pointer_event(
         PointerEvent {
             pointer_id: PointerId(0),
             is_primary: true,
-            pointer_type: PointerType::Mouse(MouseInfo { wheel_delta }),
+            pointer_type: PointerType::Mouse(MouseInfo {
+                wheel_delta,
+                ..MouseInfo::default()
+            }),
             pos: Point::new(view_point.x, view_point.y),
             buttons: get_mouse_buttons(NSEvent::pressedMouseButtons(nsevent)),
             modifiers: make_modifiers(nsevent.modifierFlags()),
             button,
             focus,
             count,
+            time: event_time(nsevent),
+            coalesced: Vec::new(),
         }
     }
 }
 
+/// The instant of an event, from its timestamp in seconds since the system started.
+fn event_time(nsevent: id) -> Option<Instant> {
+    unsafe {
+        let process_info: id = msg_send![class!(NSProcessInfo), processInfo];
+        let uptime: f64 = msg_send![process_info, systemUptime];
+        let age = (uptime - nsevent.timestamp()).max(0.0);
+        Some(instant_from_age(Duration::from_secs_f64(age)))
+    }
+}
+
 fn get_mouse_button(button: NSInteger) -> Option<PointerButton> {
     match button {
         0 => Some(PointerButton::Primary),
@@ -863,6 +893,7 @@ extern "C" fn mouse_enter(this: &mut Object, _sel: Sel, nsevent: id) {
         let view_state = &mut *(view_state as *mut ViewState);
         view_state.mouse_left = false;
         let event = mouse_pointer_event(nsevent, this, 0, false, PointerButton::None, Vec2::ZERO);
+        view_state.handler.pointer_enter(event.clone());
         view_state.handler.pointer_move(event);
     }
 }
@@ -880,28 +911,77 @@ extern "C" fn scroll_wheel(this: &mut Object, _: Sel, nsevent: id) {
     unsafe {
         let view_state: *mut c_void = *this.get_ivar("viewState");
         let view_state = &mut *(view_state as *mut ViewState);
-        let (dx, dy) = {
-            let dx = -nsevent.scrollingDeltaX();
-            let dy = -nsevent.scrollingDeltaY();
-            if nsevent.hasPreciseScrollingDeltas() == cocoa::base::YES {
-                (dx, dy)
-            } else {
-                (dx * 32.0, dy * 32.0)
-            }
+        let dx = -nsevent.scrollingDeltaX();
+        let dy = -nsevent.scrollingDeltaY();
+        let precise = nsevent.hasPreciseScrollingDeltas() == cocoa::base::YES;
+        let (wheel_delta, scroll) = if precise {
+            let phase: NSUInteger = msg_send![nsevent, phase];
+            let momentum_phase: NSUInteger = msg_send![nsevent, momentumPhase];
+            let phase = scroll_phase(phase, momentum_phase);
+            let scroll = ScrollInfo {
+                delta: Vec2::new(dx, dy),
+                unit: ScrollUnit::Pixels,
+                clicks: Vec2::ZERO,
+                phase,
+                // Only touchpads and the Magic Mouse report phases.
+                source: if phase.is_some() {
+                    ScrollSource::Finger
+                } else {
+                    ScrollSource::Continuous
+                },
+            };
+            (Vec2::new(dx, dy), scroll)
+        } else {
+            let scroll = ScrollInfo {
+                delta: Vec2::new(dx, dy),
+                unit: ScrollUnit::Lines,
+                clicks: Vec2::new(dx, dy),
+                phase: None,
+                source: ScrollSource::Wheel,
+            };
+            (Vec2::new(dx * 32.0, dy * 32.0), scroll)
         };
 
-        let event = mouse_pointer_event(
+        let mut event = mouse_pointer_event(
             nsevent,
             this as id,
             0,
             false,
             PointerButton::None,
-            Vec2::new(dx, dy),
+            wheel_delta,
         );
+        event.pointer_type = PointerType::Mouse(MouseInfo {
+            wheel_delta,
+            scroll,
+        });
         view_state.handler.wheel(event);
     }
 }
 
+/// The phase of a scroll, from the `NSEventPhase`s of its `phase` and `momentumPhase`.
+fn scroll_phase(phase: NSUInteger, momentum_phase: NSUInteger) -> Option<ScrollPhase> {
+    // NSEventPhaseBegan, NSEventPhaseStationary, NSEventPhaseChanged, NSEventPhaseEnded and
+    // NSEventPhaseCancelled
+    const BEGAN: NSUInteger = 1 << 0;
+    const STATIONARY: NSUInteger = 1 << 1;
+    const CHANGED: NSUInteger = 1 << 2;
+    const ENDED: NSUInteger = 1 << 3;
+    const CANCELLED: NSUInteger = 1 << 4;
+    if momentum_phase & (ENDED | CANCELLED) != 0 {
+        Some(ScrollPhase::Ended)
+    } else if momentum_phase != 0 {
+        Some(ScrollPhase::Momentum)
+    } else if phase & BEGAN != 0 {
+        Some(ScrollPhase::Began)
+    } else if phase & (STATIONARY | CHANGED) != 0 {
+        Some(ScrollPhase::Changed)
+    } else if phase & (ENDED | CANCELLED) != 0 {
+        Some(ScrollPhase::Ended)
+    } else {
+        None
+    }
+}
+
 extern "C" fn pinch_event(this: &mut Object, _: Sel, nsevent: id) {
     unsafe {
         let view_state: *mut c_void = *this.get_ivar("viewState");
@@ -1201,10 +1281,49 @@ impl WindowHandle {
                 Cursor::IBeam => msg_send![nscursor, IBeamCursor],
                 Cursor::Pointer => msg_send![nscursor, pointingHandCursor],
                 Cursor::Crosshair => msg_send![nscursor, crosshairCursor],
-                Cursor::OpenHand => msg_send![nscursor, openHandCursor],
-                Cursor::NotAllowed => msg_send![nscursor, operationNotAllowedCursor],
-                Cursor::ResizeLeftRight => msg_send![nscursor, resizeLeftRightCursor],
-                Cursor::ResizeUpDown => msg_send![nscursor, resizeUpDownCursor],
+                Cursor::OpenHand | Cursor::Grab => msg_send![nscursor, openHandCursor],
+                Cursor::Grabbing => msg_send![nscursor, closedHandCursor],
+                Cursor::NotAllowed | Cursor::NoDrop => {
+                    msg_send![nscursor, operationNotAllowedCursor]
+                }
+                Cursor::ResizeLeftRight | Cursor::ColResize => {
+                    msg_send![nscursor, resizeLeftRightCursor]
+                }
+                Cursor::ResizeUpDown | Cursor::RowResize => {
+                    msg_send![nscursor, resizeUpDownCursor]
+                }
+                Cursor::ResizeUp => msg_send![nscursor, resizeUpCursor],
+                Cursor::ResizeDown => msg_send![nscursor, resizeDownCursor],
+                Cursor::ResizeLeft => msg_send![nscursor, resizeLeftCursor],
+                Cursor::ResizeRight => msg_send![nscursor, resizeRightCursor],
+                Cursor::VerticalText => msg_send![nscursor, IBeamCursorForVerticalLayout],
+                Cursor::Copy => msg_send![nscursor, dragCopyCursor],
+                Cursor::Alias => msg_send![nscursor, dragLinkCursor],
+                Cursor::ContextMenu => msg_send![nscursor, contextualMenuCursor],
+                Cursor::Hidden => {
+                    // An empty image, so that nothing is drawn.
+                    let size = NSSize::new(1., 1.);
+                    let image: id = msg_send![class!(NSImage), alloc];
+                    let image: id = msg_send![image, initWithSize: size];
+                    let image: id = msg_send![image, autorelease];
+                    let hot = NSPoint::new(0., 0.);
+                    let cursor: id = msg_send![class!(NSCursor), alloc];
+                    let cursor: id = msg_send![cursor, initWithImage: image hotSpot: hot];
+                    msg_send![cursor, autorelease]
+                }
+                // AppKit has no public cursors for these, so they use the arrow.
+                Cursor::Wait
+                | Cursor::Progress
+                | Cursor::Help
+                | Cursor::Move
+                | Cursor::ResizeUpLeft
+                | Cursor::ResizeUpRight
+                | Cursor::ResizeDownLeft
+                | Cursor::ResizeDownRight
+                | Cursor::ResizeUpLeftDownRight
+                | Cursor::ResizeUpRightDownLeft
+                | Cursor::ZoomIn
+                | Cursor::ZoomOut => msg_send![nscursor, arrowCursor],
                 // TODO: support custom cursors
                 Cursor::Custom(_) => msg_send![nscursor, arrowCursor],
             };
@@ -1334,6 +1453,10 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_icon(&self, _icon: Option<&Icon>) {
+        // macOS windows don't have icons, the application's icon is shown instead.
+    }
+
     // TODO: Implement this
     pub fn show_titlebar(&self, _show_titlebar: bool) {}
 
@@ -1435,6 +1558,12 @@ impl WindowHandle {
             if isMin != NO {
                 return WindowState::Minimized;
             }
+            if window
+                .styleMask()
+                .contains(NSWindowStyleMask::NSFullScreenWindowMask)
+            {
+                return WindowState::Fullscreen(None);
+            }
             let isZoomed: BOOL = msg_send![window, isZoomed];
             if isZoomed != NO {
                 return WindowState::Maximized;
@@ -1449,6 +1578,19 @@ impl WindowHandle {
             let window: id = msg_send![*self.nsview.load(), window];
             match (state, cur_state) {
                 (s1, s2) if s1 == s2 => (),
+                (WindowState::Fullscreen(_), WindowState::Fullscreen(_)) => (),
+                (WindowState::Fullscreen(monitor), _) => {
+                    if monitor.is_some() {
+                        tracing::warn!(
+                            "Choosing the fullscreen monitor is currently unimplemented for Mac."
+                        );
+                    }
+                    let () = msg_send![window, toggleFullScreen: nil];
+                }
+                // Leaving fullscreen restores the window
+                (_, WindowState::Fullscreen(_)) => {
+                    let () = msg_send![window, toggleFullScreen: nil];
+                }
                 (WindowState::Minimized, _) => {
                     let () = msg_send![window, performMiniaturize: self];
                 }
@@ -1470,6 +1612,29 @@ impl WindowHandle {
         tracing::warn!("WindowHandle::handle_titlebar is currently unimplemented for Mac.");
     }
 
+    pub fn begin_move_drag(&self) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            // This is the mouse down event that the handler is responding to.
+            let event: id = msg_send![NSApp(), currentEvent];
+            if event != nil {
+                let () = msg_send![window, performWindowDragWithEvent: event];
+            }
+        }
+    }
+
+    pub fn begin_resize_drag(&self, _edge: ResizeEdge) {
+        tracing::warn!("WindowHandle::begin_resize_drag is currently unimplemented for Mac.");
+    }
+
+    pub fn show_window_menu(&self, _pos: Point) {
+        tracing::warn!("WindowHandle::show_window_menu is currently unimplemented for Mac.");
+    }
+
+    pub fn set_pointer_grab(&self, _grab: PointerGrab) {
+        tracing::warn!("WindowHandle::set_pointer_grab is currently unimplemented for Mac.");
+    }
+
     pub fn resizable(&self, resizable: bool) {
         unsafe {
             let window: id = msg_send![*self.nsview.load(), window];
@@ -1485,6 +1650,63 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_min_size(&self, size: Size) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            window.setContentMinSize_(NSSize::new(size.width, size.height));
+        }
+    }
+
+    pub fn set_max_size(&self, size: Option<Size>) {
+        // FLT_MAX is the default, which doesn't limit the size.
+        let size = size.unwrap_or(Size::new(f32::MAX as f64, f32::MAX as f64));
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            window.setContentMaxSize_(NSSize::new(size.width, size.height));
+        }
+    }
+
+    pub fn set_resize_increments(&self, increments: Option<Size>) {
+        let increments = increments.unwrap_or(Size::new(1.0, 1.0));
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            window.setContentResizeIncrements_(NSSize::new(increments.width, increments.height));
+        }
+    }
+
+    pub fn set_aspect_ratio(&self, ratio: Option<f64>) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            match ratio {
+                Some(ratio) => window.setContentAspectRatio_(NSSize::new(ratio, 1.0)),
+                // AppKit clears the aspect ratio when the resize increments are set.
+                None => window.setContentResizeIncrements_(NSSize::new(1.0, 1.0)),
+            }
+        }
+    }
+
+    pub fn set_input_region(&self, region: Option<Region>) {
+        // AppKit can only let clicks through the whole window.
+        let ignores_mouse_events = match region {
+            None => NO,
+            Some(region) if region.is_empty() => YES,
+            Some(_) => {
+                tracing::warn!(
+                    "WindowHandle::set_input_region is currently unimplemented for Mac, except for empty regions."
+                );
+                return;
+            }
+        };
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let () = msg_send![window, setIgnoresMouseEvents: ignores_mouse_events];
+        }
+    }
+
+    pub fn set_opaque_region(&self, _region: Option<Region>) {
+        tracing::warn!("WindowHandle::set_opaque_region is currently unimplemented for Mac.");
+    }
+
     pub fn set_menu(&self, menu: Menu) {
         unsafe {
             NSApp().setMainMenu_(menu.menu);
@@ -1506,6 +1728,23 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_drop_target(&self, _enabled: bool) {
+        tracing::warn!("WindowHandle::set_drop_target is currently unimplemented for Mac.");
+    }
+
+    pub fn present_pixels(&self, _buffer: &PixelBuffer, _damage: Option<&Region>) {
+        tracing::warn!("WindowHandle::present_pixels is currently unimplemented for Mac.");
+    }
+
+    pub fn start_drag(
+        &self,
+        _formats: &[ClipboardFormat],
+        _icon: Option<DragIcon>,
+        _actions: &[DropAction],
+    ) {
+        tracing::warn!("WindowHandle::start_drag is currently unimplemented for Mac.");
+    }
+
     /// Get a handle that can be used to schedule an idle task.
     pub fn get_idle_handle(&self) -> Option<IdleHandle> {
         if self.nsview.load().is_null() {
@@ -16,43 +16,53 @@
 
 // It would be clearer to use cfg_if! macros here, but that breaks rustfmt.
 
-#[cfg(target_os = "windows")]
+#[cfg(feature = "headless")]
+mod headless;
+#[cfg(feature = "headless")]
+pub use headless::*;
+
+#[cfg(all(target_os = "windows", not(feature = "headless")))]
 mod windows;
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", not(feature = "headless")))]
 pub use windows::*;
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "headless")))]
 mod mac;
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "headless")))]
 pub use mac::*;
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "headless")))]
 pub(crate) mod shared;
 
 #[cfg(all(
     any(target_os = "freebsd", target_os = "linux", target_os = "openbsd"),
-    any(feature = "x11", feature = "wayland")
+    any(feature = "x11", feature = "wayland"),
+    not(feature = "headless")
 ))]
 pub mod linux;
 #[cfg(all(
     any(target_os = "freebsd", target_os = "linux", target_os = "openbsd"),
-    any(feature = "x11", feature = "wayland")
+    any(feature = "x11", feature = "wayland"),
+    not(feature = "headless")
 ))]
 pub use linux::*;
 
 #[cfg(all(
     feature = "x11",
+    not(feature = "headless"),
     any(target_os = "freebsd", target_os = "linux", target_os = "openbsd")
 ))]
 pub(crate) mod x11;
 
 #[cfg(all(
     feature = "wayland",
+    not(feature = "headless"),
     any(target_os = "freebsd", target_os = "linux", target_os = "openbsd")
 ))]
 pub(crate) mod wayland;
 
 #[cfg(all(
     any(feature = "wayland", feature = "x11"),
+    not(feature = "headless"),
     any(target_os = "freebsd", target_os = "linux", target_os = "openbsd")
 ))]
 pub(crate) mod shared;
@@ -60,11 +70,12 @@ pub(crate) mod shared;
 #[cfg(all(
     not(feature = "x11"),
     not(feature = "wayland"),
+    not(feature = "headless"),
     any(target_os = "freebsd", target_os = "linux", target_os = "openbsd")
 ))]
 pub(crate) mod shared;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", not(feature = "headless")))]
 mod web;
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", not(feature = "headless")))]
 pub use web::*;
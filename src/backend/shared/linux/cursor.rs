@@ -0,0 +1,61 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The names of cursors in freedesktop cursor themes.
+
+use crate::mouse::Cursor;
+
+/// The names to look up for `cursor` in a cursor theme, in order of preference.
+///
+/// The first name is from the freedesktop cursor naming spec, which uses the names of CSS. It
+/// is followed by the names used by older themes and by the X11 cursor font. Custom and hidden
+/// cursors have no names.
+pub fn cursor_names(cursor: &Cursor) -> &'static [&'static str] {
+    #[allow(deprecated)]
+    match cursor {
+        Cursor::Arrow => &["default", "left_ptr"],
+        Cursor::IBeam => &["text", "xterm"],
+        Cursor::Pointer => &["pointer", "hand2", "hand1"],
+        Cursor::Crosshair => &["crosshair", "cross"],
+        Cursor::OpenHand | Cursor::Grab => &["grab", "openhand", "hand1"],
+        Cursor::NotAllowed => &["not-allowed", "crossed_circle"],
+        Cursor::ResizeLeftRight => &["ew-resize", "sb_h_double_arrow", "col-resize"],
+        Cursor::ResizeUpDown => &["ns-resize", "sb_v_double_arrow", "row-resize"],
+        Cursor::Wait => &["wait", "watch"],
+        Cursor::Progress => &["progress", "left_ptr_watch", "watch"],
+        Cursor::Help => &["help", "question_arrow", "left_ptr_help"],
+        Cursor::Move => &["move", "fleur", "all-scroll"],
+        Cursor::Grabbing => &["grabbing", "closedhand", "fleur"],
+        Cursor::ResizeUp => &["n-resize", "top_side"],
+        Cursor::ResizeDown => &["s-resize", "bottom_side"],
+        Cursor::ResizeLeft => &["w-resize", "left_side"],
+        Cursor::ResizeRight => &["e-resize", "right_side"],
+        Cursor::ResizeUpLeft => &["nw-resize", "top_left_corner"],
+        Cursor::ResizeUpRight => &["ne-resize", "top_right_corner"],
+        Cursor::ResizeDownLeft => &["sw-resize", "bottom_left_corner"],
+        Cursor::ResizeDownRight => &["se-resize", "bottom_right_corner"],
+        Cursor::ResizeUpLeftDownRight => &["nwse-resize", "size_fdiag", "bd_double_arrow"],
+        Cursor::ResizeUpRightDownLeft => &["nesw-resize", "size_bdiag", "fd_double_arrow"],
+        Cursor::ColResize => &["col-resize", "sb_h_double_arrow"],
+        Cursor::RowResize => &["row-resize", "sb_v_double_arrow"],
+        Cursor::ZoomIn => &["zoom-in"],
+        Cursor::ZoomOut => &["zoom-out"],
+        Cursor::VerticalText => &["vertical-text"],
+        Cursor::Copy => &["copy", "dnd-copy"],
+        Cursor::Alias => &["alias", "dnd-link", "link"],
+        Cursor::ContextMenu => &["context-menu"],
+        Cursor::NoDrop => &["no-drop", "dnd-no-drop", "not-allowed", "crossed_circle"],
+        Cursor::Hidden | Cursor::Custom(_) => &[],
+    }
+}
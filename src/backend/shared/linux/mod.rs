@@ -1,2 +1,21 @@
 // environment based utilities
 pub mod env;
+// cursor theme names
+pub mod cursor;
+
+/// The instant of an event with a timestamp in milliseconds of the monotonic clock, which the X
+/// server and Wayland compositors use.
+///
+/// Timestamps from the future or from long ago are assumed to come from another clock.
+pub fn event_time(millis: u32) -> Option<std::time::Instant> {
+    let now = nix::time::clock_gettime(nix::time::ClockId::CLOCK_MONOTONIC).ok()?;
+    let now_millis = now.tv_sec() as u64 * 1000 + now.tv_nsec() as u64 / 1_000_000;
+    // The timestamps wrap around every 49 days
+    let age = (now_millis as u32).wrapping_sub(millis);
+    if age > 60_000 {
+        return None;
+    }
+    Some(crate::common_util::instant_from_age(
+        std::time::Duration::from_millis(age.into()),
+    ))
+}
@@ -299,6 +299,11 @@ impl KeyEventsState {
         };
     }
 
+    /// The modifiers which are currently active, as of the last call to `update_xkb_state`.
+    pub fn active_modifiers(&self) -> Modifiers {
+        self.active_mods
+    }
+
     /// For an explanation of how our compose/dead key handling operates, see
     /// the documentation of [`crate::text::simulate_compose`]
     ///
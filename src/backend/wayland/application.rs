@@ -29,7 +29,7 @@ use smithay_client_toolkit::{
         calloop::{channel, EventLoop, LoopHandle, LoopSignal},
         client::{
             globals::{registry_queue_init, BindError},
-            protocol::wl_compositor,
+            protocol::{wl_compositor, wl_shm},
             Connection, QueueHandle, WaylandSource,
         },
     },
@@ -38,7 +38,7 @@ use smithay_client_toolkit::{
     shell::xdg::XdgShell,
 };
 
-use super::{clipboard, error::Error, ActiveAction, IdleAction, WaylandState};
+use super::{clipboard, cursor::Cursors, error::Error, ActiveAction, IdleAction, WaylandState};
 use crate::{
     backend::{
         shared::{linux, xkb::Context},
@@ -54,6 +54,7 @@ pub struct Application {
     // The inner is taken in `run`
     state: Rc<RefCell<Option<WaylandState>>>,
     pub(super) compositor: wl_compositor::WlCompositor,
+    pub(super) shm: wl_shm::WlShm,
     pub(super) wayland_queue: QueueHandle<WaylandState>,
     pub(super) xdg_shell: Weak<XdgShell>,
     loop_signal: LoopSignal,
@@ -109,11 +110,57 @@ impl Application {
             },
             |it| Ok(Some(it)),
         )?;
+        let data_device_manager = globals.bind(&qh, 1..=3, ()).map_or_else(
+            |err| match err {
+                e @ BindError::UnsupportedVersion => Err(e),
+                BindError::NotPresent => Ok(None),
+            },
+            |it| Ok(Some(it)),
+        )?;
+        let toplevel_icon_manager = globals.bind(&qh, 1..=1, ()).map_or_else(
+            |err| match err {
+                e @ BindError::UnsupportedVersion => Err(e),
+                BindError::NotPresent => Ok(None),
+            },
+            |it| Ok(Some(it)),
+        )?;
+        let pointer_constraints = globals.bind(&qh, 1..=1, ()).map_or_else(
+            |err| match err {
+                e @ BindError::UnsupportedVersion => Err(e),
+                BindError::NotPresent => Ok(None),
+            },
+            |it| Ok(Some(it)),
+        )?;
+        let relative_pointer_manager = globals.bind(&qh, 1..=1, ()).map_or_else(
+            |err| match err {
+                e @ BindError::UnsupportedVersion => Err(e),
+                BindError::NotPresent => Ok(None),
+            },
+            |it| Ok(Some(it)),
+        )?;
+        // Hold gestures need version 3
+        let pointer_gestures = globals.bind(&qh, 1..=3, ()).map_or_else(
+            |err| match err {
+                e @ BindError::UnsupportedVersion => Err(e),
+                BindError::NotPresent => Ok(None),
+            },
+            |it| Ok(Some(it)),
+        )?;
+        let tablet_manager = globals.bind(&qh, 1..=1, ()).map_or_else(
+            |err| match err {
+                e @ BindError::UnsupportedVersion => Err(e),
+                BindError::NotPresent => Ok(None),
+            },
+            |it| Ok(Some(it)),
+        )?;
+        let shm: wl_shm::WlShm = globals.bind(&qh, 1..=1, ())?;
 
         let mut state = WaylandState {
             registry_state: RegistryState::new(&globals),
             output_state: OutputState::new(&globals, &qh),
-            _compositor_state: compositor_state,
+            compositor_state,
+            shm: shm.clone(),
+            cursors: Cursors::new(conn.clone(), shm.clone(), compositor.clone()),
             _xdg_shell_state: shell,
             event_loop: Some(event_loop),
             handler: None,
@@ -127,12 +174,19 @@ impl Application {
             seats: SeatState::new(&globals, &qh),
             xkb_context: Context::new(),
             text_input: text_input_global,
+            data_device_manager,
+            toplevel_icon_manager,
+            pointer_constraints,
+            relative_pointer_manager,
+            pointer_gestures,
+            tablet_manager,
             loop_handle: loop_handle.clone(),
         };
         state.initial_seats();
         Ok(Application {
             state: Rc::new(RefCell::new(Some(state))),
             compositor,
+            shm,
             wayland_queue: qh,
             loop_signal,
             idle_sender,
@@ -0,0 +1,188 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pointer cursors, from the cursor theme or from custom images.
+
+use std::collections::HashMap;
+
+use smithay_client_toolkit::reexports::client::{
+    protocol::{wl_buffer, wl_compositor, wl_shm, wl_surface},
+    Connection, Proxy, QueueHandle,
+};
+use wayland_cursor::CursorTheme;
+
+use super::{shm, WaylandState};
+use crate::backend::shared::linux::cursor::cursor_names;
+use crate::mouse::Cursor;
+
+/// The size of cursors in display points, if `XCURSOR_SIZE` isn't set.
+const DEFAULT_CURSOR_SIZE: u32 = 24;
+
+/// The state shared by the cursors of all pointers.
+pub(super) struct Cursors {
+    conn: Connection,
+    shm: wl_shm::WlShm,
+    compositor: wl_compositor::WlCompositor,
+    size: u32,
+    /// The cursor theme, loaded once for each scale that a cursor was shown at.
+    themes: HashMap<u32, CursorTheme>,
+}
+
+impl Cursors {
+    pub(super) fn new(
+        conn: Connection,
+        shm: wl_shm::WlShm,
+        compositor: wl_compositor::WlCompositor,
+    ) -> Cursors {
+        let size = std::env::var("XCURSOR_SIZE")
+            .ok()
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(DEFAULT_CURSOR_SIZE);
+        Cursors {
+            conn,
+            shm,
+            compositor,
+            size,
+            themes: HashMap::new(),
+        }
+    }
+
+    /// The theme with cursors for `scale`.
+    ///
+    /// The name of the theme comes from `XCURSOR_THEME`.
+    fn theme(&mut self, scale: u32) -> Option<&mut CursorTheme> {
+        if !self.themes.contains_key(&scale) {
+            match CursorTheme::load(&self.conn, self.shm.clone(), self.size * scale) {
+                Ok(theme) => {
+                    self.themes.insert(scale, theme);
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to load the cursor theme: {}", err);
+                    return None;
+                }
+            }
+        }
+        self.themes.get_mut(&scale)
+    }
+}
+
+/// The surface which shows the cursor of one pointer.
+#[derive(Default)]
+pub(super) struct CursorSurface {
+    /// The surface, which is created when the first cursor is shown.
+    surface: Option<wl_surface::WlSurface>,
+    /// The buffer of a custom cursor, which must live as long as it is attached.
+    custom_buffer: Option<wl_buffer::WlBuffer>,
+}
+
+impl CursorSurface {
+    /// Show `cursor` with `set_cursor`, which passes the surface and the hot spot to the
+    /// `set_cursor` request of a pointer or a tablet tool.
+    ///
+    /// Wayland only supports integer scales, so `scale` is the scale of the buffer.
+    pub(super) fn show(
+        &mut self,
+        cursor: &Cursor,
+        scale: u32,
+        cursors: &mut Cursors,
+        qh: &QueueHandle<WaylandState>,
+        set_cursor: impl FnOnce(Option<&wl_surface::WlSurface>, i32, i32),
+    ) {
+        if matches!(cursor, Cursor::Hidden) {
+            set_cursor(None, 0, 0);
+            return;
+        }
+        let surface = self
+            .surface
+            .get_or_insert_with(|| cursors.compositor.create_surface(qh, Default::default()));
+        // The size of the buffer and the hot spot, in buffer pixels
+        let (width, height, hot_x, hot_y);
+        let mut custom_buffer = None;
+        if let Cursor::Custom(custom) = cursor {
+            let desc = &custom.unwrap_wayland().0;
+            let (image, hot) = desc.image_for_scale(scale as f64);
+            // The size of a buffer must be a multiple of its scale, so the image is padded
+            let round_up = |len: u32| match len % scale {
+                0 => len,
+                rest => len + scale - rest,
+            };
+            (width, height) = (round_up(image.width()), round_up(image.height()));
+            let mut data = vec![0; width as usize * height as usize * 4];
+            let row_len = image.width() as usize * 4;
+            for (row, pixels) in image
+                .to_premultiplied_bgra()
+                .chunks_exact(row_len.max(1))
+                .enumerate()
+            {
+                let start = row * width as usize * 4;
+                data[start..start + row_len].copy_from_slice(pixels);
+            }
+            let buffer = match shm::buffer_from_argb8888(&cursors.shm, qh, width, height, &data) {
+                Ok(buffer) => buffer,
+                Err(err) => {
+                    tracing::error!("Failed to create the cursor buffer: {}", err);
+                    return;
+                }
+            };
+            (hot_x, hot_y) = (hot.x.round() as u32, hot.y.round() as u32);
+            surface.attach(Some(&buffer), 0, 0);
+            custom_buffer = Some(buffer);
+        } else {
+            let Some(theme) = cursors.theme(scale) else {
+                return;
+            };
+            // Themes don't have all cursors, so the arrow is used as a fallback
+            let mut names = cursor_names(cursor)
+                .iter()
+                .chain(cursor_names(&Cursor::Arrow));
+            let Some(name) = names.find(|name| theme.get_cursor(name).is_some()) else {
+                tracing::warn!("The cursor theme has no cursor for {:?}", cursor);
+                return;
+            };
+            // Animated cursors only show their first frame
+            let image = &theme.get_cursor(name).unwrap()[0];
+            (width, height) = image.dimensions();
+            (hot_x, hot_y) = image.hotspot();
+            surface.attach(Some(&**image), 0, 0);
+        }
+
+        surface.set_buffer_scale(scale as i32);
+        if surface.version() >= 4 {
+            surface.damage_buffer(0, 0, width as i32, height as i32);
+        } else {
+            surface.damage(0, 0, (width / scale) as i32, (height / scale) as i32);
+        }
+        surface.commit();
+        set_cursor(
+            Some(&*surface),
+            (hot_x / scale) as i32,
+            (hot_y / scale) as i32,
+        );
+        // The previous buffer is no longer attached
+        if let Some(old) = std::mem::replace(&mut self.custom_buffer, custom_buffer) {
+            old.destroy();
+        }
+    }
+}
+
+impl Drop for CursorSurface {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.custom_buffer.take() {
+            buffer.destroy();
+        }
+        if let Some(surface) = self.surface.take() {
+            surface.destroy();
+        }
+    }
+}
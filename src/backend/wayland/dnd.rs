@@ -0,0 +1,170 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The data of drags on Wayland.
+//!
+//! The protocol handling lives with the rest of the seat, in `input::data_device`.
+
+use std::cell::Cell;
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::rc::Rc;
+
+use smithay_client_toolkit::reexports::client::{protocol::wl_data_offer, Connection};
+
+use crate::clipboard::{ClipboardFormat, FormatId};
+
+/// The mime types that text can be offered as, in order of preference.
+pub(super) const TEXT_MIME_TYPES: [&str; 4] = [
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "TEXT",
+    "text/plain",
+];
+
+/// The data offered by the source of a drag.
+#[derive(Debug, Clone)]
+pub struct DropData {
+    /// The mime types offered by the source.
+    formats: Rc<[String]>,
+    /// The last offered format that the handler asked for, which is the one that we accept.
+    chosen: Rc<Cell<Option<FormatId>>>,
+    source: Source,
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    /// The drag comes from another client, the data is sent to us through a pipe.
+    Offer(wl_data_offer::WlDataOffer, Connection),
+    /// The drag was started by this application. We can't read it through the compositor,
+    /// because we would block the event loop which is needed to send the data.
+    Local(Rc<[ClipboardFormat]>),
+}
+
+impl DropData {
+    pub(super) fn from_offer(
+        formats: Vec<String>,
+        offer: wl_data_offer::WlDataOffer,
+        conn: Connection,
+    ) -> DropData {
+        DropData {
+            formats: formats.into(),
+            chosen: Rc::default(),
+            source: Source::Offer(offer, conn),
+        }
+    }
+
+    pub(super) fn local(formats: Rc<[ClipboardFormat]>) -> DropData {
+        DropData {
+            formats: formats
+                .iter()
+                .flat_map(|format| mime_types(format.identifier))
+                .map(String::from)
+                .collect(),
+            chosen: Rc::default(),
+            source: Source::Local(formats),
+        }
+    }
+
+    pub fn get_string(&self) -> Option<String> {
+        TEXT_MIME_TYPES.iter().find_map(|mime| {
+            self.get_format(mime)
+                .and_then(|data| String::from_utf8(data).ok())
+        })
+    }
+
+    pub fn preferred_format(&self, formats: &[FormatId]) -> Option<FormatId> {
+        let format = formats
+            .iter()
+            .find(|f1| self.formats.iter().any(|f2| *f1 == f2))
+            .copied();
+        self.chosen.set(format);
+        format
+    }
+
+    pub fn get_format(&self, format: FormatId) -> Option<Vec<u8>> {
+        if !self.formats.iter().any(|it| it == format) {
+            return None;
+        }
+        self.chosen.set(Some(format));
+        match &self.source {
+            Source::Offer(offer, conn) => match receive(offer, conn, format) {
+                Ok(data) => Some(data),
+                Err(err) => {
+                    tracing::warn!("failed to read {format} from drag: {err}");
+                    None
+                }
+            },
+            Source::Local(formats) => find_format(formats, format).map(|it| it.data.clone()),
+        }
+    }
+
+    pub fn available_type_names(&self) -> Vec<String> {
+        self.formats.to_vec()
+    }
+
+    /// The format which the handler wants, from its calls to `preferred_format` or
+    /// `get_format`.
+    ///
+    /// Wayland targets have to accept a specific mime type, and the drop only happens if they
+    /// did. The handler only returns an action, so this is how we find out which mime type.
+    pub(super) fn chosen_format(&self) -> Option<FormatId> {
+        self.chosen.get()
+    }
+}
+
+/// The mime types that a format is offered as.
+///
+/// Text is offered under all of the common names, because `ClipboardFormat::TEXT` is
+/// `UTF8_STRING`, which most Wayland clients don't look for.
+pub(super) fn mime_types(format: FormatId) -> Vec<&'static str> {
+    if TEXT_MIME_TYPES.contains(&format) {
+        TEXT_MIME_TYPES.to_vec()
+    } else {
+        vec![format]
+    }
+}
+
+/// Find the format which should be sent when `mime_type` is requested.
+pub(super) fn find_format<'a>(
+    formats: &'a [ClipboardFormat],
+    mime_type: &str,
+) -> Option<&'a ClipboardFormat> {
+    formats
+        .iter()
+        .find(|format| mime_types(format.identifier).contains(&mime_type))
+}
+
+/// Ask the source to write the data in `mime_type` to a pipe, and read all of it.
+///
+/// This blocks until the source closes the pipe.
+fn receive(
+    offer: &wl_data_offer::WlDataOffer,
+    conn: &Connection,
+    mime_type: &str,
+) -> std::io::Result<Vec<u8>> {
+    let (read, write) = nix::unistd::pipe()?;
+    // SAFETY: `pipe` returns two new file descriptors, which nothing else owns.
+    let (mut read, write) = unsafe { (File::from_raw_fd(read), File::from_raw_fd(write)) };
+    offer.receive(mime_type.to_string(), write.as_raw_fd());
+    // Close our copy of the write end, so that we see the end of the data when the source
+    // closes its copy.
+    drop(write);
+    conn.flush()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+    let mut data = Vec::new();
+    read.read_to_end(&mut data)?;
+    Ok(data)
+}
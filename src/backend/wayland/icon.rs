@@ -0,0 +1,68 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Window icons through the xdg-toplevel-icon protocol.
+//!
+//! wayland-protocols doesn't include this protocol yet, so its bindings are generated here.
+
+use smithay_client_toolkit::reexports::client::{Connection, Dispatch, QueueHandle};
+
+use self::protocol::{xdg_toplevel_icon_manager_v1, xdg_toplevel_icon_v1};
+use super::WaylandState;
+
+#[allow(dead_code, non_camel_case_types, unused_imports, clippy::all)]
+pub(super) mod protocol {
+    use smithay_client_toolkit::reexports::protocols::xdg::shell::client::*;
+    use wayland_client;
+    use wayland_client::protocol::*;
+
+    pub mod __interfaces {
+        use smithay_client_toolkit::reexports::protocols::xdg::shell::client::__interfaces::*;
+        use wayland_client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!(
+            "src/backend/wayland/protocols/xdg-toplevel-icon-v1.xml"
+        );
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!(
+        "src/backend/wayland/protocols/xdg-toplevel-icon-v1.xml"
+    );
+}
+
+impl Dispatch<xdg_toplevel_icon_manager_v1::XdgToplevelIconManagerV1, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &xdg_toplevel_icon_manager_v1::XdgToplevelIconManagerV1,
+        _: xdg_toplevel_icon_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // The preferred sizes are ignored, as we can only send the sizes we were given.
+    }
+}
+
+impl Dispatch<xdg_toplevel_icon_v1::XdgToplevelIconV1, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &xdg_toplevel_icon_v1::XdgToplevelIconV1,
+        _: xdg_toplevel_icon_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // xdg_toplevel_icon_v1 has no events.
+    }
+}
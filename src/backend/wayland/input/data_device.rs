@@ -0,0 +1,483 @@
+use std::{fs::File, io::Write, rc::Rc, sync::Mutex};
+
+use smithay_client_toolkit::reexports::client::{
+    event_created_child,
+    protocol::{
+        wl_buffer,
+        wl_data_device::{self, WlDataDevice},
+        wl_data_device_manager::{self, DndAction, WlDataDeviceManager},
+        wl_data_offer::{self, WlDataOffer},
+        wl_data_source::{self, WlDataSource},
+        wl_seat, wl_surface,
+    },
+    Connection, Dispatch, Proxy, QueueHandle, WEnum,
+};
+
+use crate::{
+    backend::wayland::{
+        dnd::{self, DropData},
+        shm,
+        window::WindowId,
+    },
+    clipboard::ClipboardFormat,
+    dnd::{DragEvent, DragIcon, DropAction},
+    kurbo::Point,
+};
+
+use super::{input_state, SeatName, WaylandState};
+
+/// The seat identifier of this data device
+pub(super) struct DataDeviceUserData(SeatName);
+
+/// The seat identifier of a data source that we created for a drag
+pub(super) struct DataSourceUserData(SeatName);
+
+/// The information sent by the compositor about a data offer
+#[derive(Default)]
+pub(super) struct DataOfferData(Mutex<OfferInfo>);
+
+#[derive(Default)]
+struct OfferInfo {
+    mime_types: Vec<String>,
+    /// The preferred action of the source
+    source_action: DropAction,
+}
+
+/// A request from [`WindowHandle::start_drag`](crate::WindowHandle::start_drag), which is
+/// deferred until we are outside of the handler
+pub(in crate::backend::wayland) struct DragRequest {
+    pub formats: Vec<ClipboardFormat>,
+    pub icon: Option<DragIcon>,
+    pub actions: Vec<DropAction>,
+}
+
+/// The wl_data_device of a seat, through which drags are started and received
+pub(super) struct DataDeviceState {
+    device: WlDataDevice,
+    /// The drag which is currently over one of our windows
+    drop: Option<ActiveDrop>,
+    /// The drag which was started by one of our windows
+    drag: Option<ActiveDrag>,
+}
+
+struct ActiveDrop {
+    window: WindowId,
+    /// The offer, which is missing for drags which the source keeps within its own client
+    offer: Option<WlDataOffer>,
+    data: Option<DropData>,
+    /// The serial of the `enter` event, which is needed to accept the offer
+    serial: u32,
+    pos: Point,
+}
+
+struct ActiveDrag {
+    window: WindowId,
+    source: WlDataSource,
+    formats: Rc<[ClipboardFormat]>,
+    icon: Option<(wl_surface::WlSurface, wl_buffer::WlBuffer)>,
+    /// The last action selected by the compositor
+    action: DropAction,
+}
+
+impl DataDeviceState {
+    pub(super) fn new(
+        manager: &WlDataDeviceManager,
+        qh: &QueueHandle<WaylandState>,
+        name: SeatName,
+        seat: &wl_seat::WlSeat,
+    ) -> Self {
+        DataDeviceState {
+            device: manager.get_data_device(seat, qh, DataDeviceUserData(name)),
+            drop: None,
+            drag: None,
+        }
+    }
+}
+
+impl Drop for DataDeviceState {
+    fn drop(&mut self) {
+        if let Some(drag) = self.drag.take() {
+            drag.destroy();
+        }
+        if let Some(offer) = self.drop.take().and_then(|drop| drop.offer) {
+            offer.destroy();
+        }
+        if self.device.version() >= 2 {
+            self.device.release();
+        }
+    }
+}
+
+impl ActiveDrag {
+    fn destroy(self) {
+        self.source.destroy();
+        if let Some((surface, buffer)) = self.icon {
+            surface.destroy();
+            buffer.destroy();
+        }
+    }
+}
+
+fn dnd_action(action: DropAction) -> DndAction {
+    match action {
+        // Wayland doesn't have links
+        DropAction::None | DropAction::Link => DndAction::empty(),
+        DropAction::Copy => DndAction::Copy,
+        DropAction::Move => DndAction::Move,
+    }
+}
+
+fn drop_action(action: WEnum<DndAction>) -> DropAction {
+    match action {
+        WEnum::Value(action) if action.contains(DndAction::Copy) => DropAction::Copy,
+        WEnum::Value(action) if action.contains(DndAction::Move) => DropAction::Move,
+        // We never offer or accept `Ask`
+        _ => DropAction::None,
+    }
+}
+
+impl ActiveDrop {
+    /// Tell the source which action we would perform at the current position.
+    fn reply(&self, action: DropAction) {
+        let Some(offer) = self.offer.as_ref() else {
+            return;
+        };
+        let mime_type = match (&self.data, action) {
+            (_, DropAction::None) | (None, _) => None,
+            (Some(data), _) => data.chosen_format(),
+        };
+        // Without a mime type, the compositor would cancel the drop anyway.
+        let action = match mime_type {
+            Some(_) => action,
+            None => DropAction::None,
+        };
+        offer.accept(self.serial, mime_type.map(String::from));
+        if offer.version() >= 3 {
+            let action = dnd_action(action);
+            offer.set_actions(action, action);
+        }
+    }
+}
+
+impl WaylandState {
+    /// Run `f` with the handler of the window that the drag is over, if that window accepts
+    /// drops. Returns `None` if there is no drag over our windows.
+    fn with_drop_target(
+        &mut self,
+        seat: SeatName,
+        f: impl FnOnce(&mut dyn crate::WinHandler, DragEvent) -> DropAction,
+    ) -> Option<DropAction> {
+        let seat = input_state(&mut self.input_states, seat);
+        let modifiers = seat.modifiers();
+        let drop = seat.data_device.as_mut()?.drop.as_ref()?;
+        let window = self.windows.get_mut(&drop.window)?;
+        let source_action = drop
+            .offer
+            .as_ref()
+            .and_then(|offer| offer.data::<DataOfferData>())
+            .map(|data| data.0.lock().unwrap().source_action)
+            .unwrap_or_default();
+        let action = match (&drop.data, window.is_drop_target()) {
+            (Some(data), true) => f(
+                &mut *window.handler,
+                DragEvent {
+                    pos: drop.pos,
+                    modifiers,
+                    action: source_action,
+                    data: crate::backend::dnd::DropData::Wayland(data.clone()).into(),
+                },
+            ),
+            _ => DropAction::None,
+        };
+        Some(action)
+    }
+
+    pub(in crate::backend::wayland) fn start_drag(
+        &mut self,
+        window: &WindowId,
+        request: DragRequest,
+    ) {
+        let Some(manager) = self.data_device_manager.as_ref() else {
+            tracing::warn!("start_drag failed: the compositor doesn't support drag and drop");
+            return;
+        };
+        if manager.version() < 3 {
+            // Older versions can't report how the drag finished
+            tracing::warn!("start_drag failed: the compositor's wl_data_device_manager is too old");
+            return;
+        }
+        // Drags need the implicit grab of a button press in the window
+        let Some((wl_seat, serial)) = self.latest_press(window) else {
+            tracing::warn!("start_drag failed: no button was pressed in the window");
+            return;
+        };
+        let Some(seat) = self.input_states.iter_mut().find(|it| it.seat == wl_seat) else {
+            return;
+        };
+        let Some(device) = seat.data_device.as_mut() else {
+            return;
+        };
+        if device.drag.is_some() {
+            tracing::warn!("start_drag failed: a drag is already in progress");
+            return;
+        }
+        let Some(win) = self.windows.get(window) else {
+            return;
+        };
+
+        let qh = &self.wayland_queue;
+        let source = manager.create_data_source(qh, DataSourceUserData(seat.id));
+        for format in &request.formats {
+            for mime_type in dnd::mime_types(format.identifier) {
+                source.offer(mime_type.to_string());
+            }
+        }
+        let actions = request
+            .actions
+            .iter()
+            .fold(DndAction::empty(), |actions, &action| {
+                actions | dnd_action(action)
+            });
+        source.set_actions(actions);
+
+        let icon = request.icon.and_then(|icon| {
+            let buffer = shm::buffer_from_rgba(&self.shm, qh, icon.width, icon.height, &icon.data)
+                .map_err(|err| tracing::warn!("failed to create the drag icon: {err}"))
+                .ok()?;
+            let surface = self
+                .compositor_state
+                .wl_compositor()
+                .create_surface(qh, Default::default());
+            // Wayland only supports integer scales, and the icon is in physical pixels.
+            surface.set_buffer_scale(win.scale().x() as i32);
+            // Put the hot spot under the pointer
+            let (dx, dy) = (-icon.hot_spot.x as i32, -icon.hot_spot.y as i32);
+            if surface.version() >= 5 {
+                surface.attach(Some(&buffer), 0, 0);
+                surface.offset(dx, dy);
+            } else {
+                surface.attach(Some(&buffer), dx, dy);
+            }
+            surface.commit();
+            Some((surface, buffer))
+        });
+
+        device.device.start_drag(
+            Some(&source),
+            &win.surface(),
+            icon.as_ref().map(|(surface, _)| surface),
+            serial,
+        );
+        device.drag = Some(ActiveDrag {
+            window: window.clone(),
+            source,
+            formats: request.formats.into(),
+            icon,
+            action: DropAction::None,
+        });
+    }
+
+    /// The drag that we started has ended, either successfully or not.
+    fn finish_drag(&mut self, seat: SeatName, cancelled: bool) {
+        let seat = input_state(&mut self.input_states, seat);
+        let Some(drag) = seat
+            .data_device
+            .as_mut()
+            .and_then(|device| device.drag.take())
+        else {
+            return;
+        };
+        let action = if cancelled {
+            DropAction::None
+        } else {
+            drag.action
+        };
+        let window = drag.window.clone();
+        drag.destroy();
+        if let Some(window) = self.windows.get_mut(&window) {
+            window.handler.dnd_finished(action);
+        }
+    }
+}
+
+impl Dispatch<WlDataDeviceManager, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &WlDataDeviceManager,
+        _: wl_data_device_manager::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // wl_data_device_manager has no events.
+    }
+}
+
+impl Dispatch<WlDataDevice, DataDeviceUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &WlDataDevice,
+        event: wl_data_device::Event,
+        data: &DataDeviceUserData,
+        conn: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            // The offer is filled in by its own events, and is used in `enter` or `selection`
+            wl_data_device::Event::DataOffer { .. } => {}
+            wl_data_device::Event::Enter {
+                serial,
+                surface,
+                x,
+                y,
+                id,
+            } => {
+                let seat = input_state(&mut state.input_states, data.0);
+                let Some(device) = seat.data_device.as_mut() else {
+                    return;
+                };
+                let drop_data = match (&device.drag, &id) {
+                    // If we started a drag, it is the only drag that can be over this seat.
+                    (Some(drag), _) => Some(DropData::local(drag.formats.clone())),
+                    (None, Some(offer)) => {
+                        let formats = offer
+                            .data::<DataOfferData>()
+                            .map(|data| data.0.lock().unwrap().mime_types.clone())
+                            .unwrap_or_default();
+                        Some(DropData::from_offer(formats, offer.clone(), conn.clone()))
+                    }
+                    (None, None) => None,
+                };
+                if let Some(old) = device.drop.take().and_then(|drop| drop.offer) {
+                    old.destroy();
+                }
+                device.drop = Some(ActiveDrop {
+                    window: WindowId::of_surface(&surface),
+                    offer: id,
+                    data: drop_data,
+                    serial,
+                    pos: Point::new(x, y),
+                });
+                let action = state
+                    .with_drop_target(data.0, |handler, event| handler.drag_enter(&event))
+                    .unwrap_or_default();
+                state.reply_to_drop(data.0, action);
+            }
+            wl_data_device::Event::Motion { time: _, x, y } => {
+                let seat = input_state(&mut state.input_states, data.0);
+                let Some(drop) = seat.data_device.as_mut().and_then(|it| it.drop.as_mut()) else {
+                    return;
+                };
+                drop.pos = Point::new(x, y);
+                let action = state
+                    .with_drop_target(data.0, |handler, event| handler.drag_over(&event))
+                    .unwrap_or_default();
+                state.reply_to_drop(data.0, action);
+            }
+            wl_data_device::Event::Leave => {
+                state.with_drop_target(data.0, |handler, _| {
+                    handler.drag_leave();
+                    DropAction::None
+                });
+                let seat = input_state(&mut state.input_states, data.0);
+                let drop = seat.data_device.as_mut().and_then(|it| it.drop.take());
+                if let Some(offer) = drop.and_then(|drop| drop.offer) {
+                    offer.destroy();
+                }
+            }
+            wl_data_device::Event::Drop => {
+                let action = state
+                    .with_drop_target(data.0, |handler, event| handler.drag_drop(&event))
+                    .unwrap_or_default();
+                let seat = input_state(&mut state.input_states, data.0);
+                let drop = seat.data_device.as_mut().and_then(|it| it.drop.take());
+                if let Some(offer) = drop.and_then(|drop| drop.offer) {
+                    if action != DropAction::None && offer.version() >= 3 {
+                        offer.finish();
+                    }
+                    offer.destroy();
+                }
+            }
+            // TODO: Use this for the clipboard
+            wl_data_device::Event::Selection { id: Some(offer) } => offer.destroy(),
+            _ => {}
+        }
+    }
+
+    event_created_child!(WaylandState, WlDataDevice, [
+        wl_data_device::EVT_DATA_OFFER_OPCODE => (WlDataOffer, DataOfferData::default())
+    ]);
+}
+
+impl WaylandState {
+    fn reply_to_drop(&mut self, seat: SeatName, action: DropAction) {
+        let seat = input_state(&mut self.input_states, seat);
+        if let Some(drop) = seat.data_device.as_ref().and_then(|it| it.drop.as_ref()) {
+            drop.reply(action);
+        }
+    }
+}
+
+impl Dispatch<WlDataOffer, DataOfferData> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &WlDataOffer,
+        event: wl_data_offer::Event,
+        data: &DataOfferData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_data_offer::Event::Offer { mime_type } => {
+                data.0.lock().unwrap().mime_types.push(mime_type)
+            }
+            wl_data_offer::Event::SourceActions { source_actions } => {
+                data.0.lock().unwrap().source_action = drop_action(source_actions);
+            }
+            // We tell the source our preferred action in `reply`, and the compositor picks
+            // the final action from both sides. We don't need to know it as the target.
+            wl_data_offer::Event::Action { .. } => {}
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlDataSource, DataSourceUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &WlDataSource,
+        event: wl_data_source::Event,
+        data: &DataSourceUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_data_source::Event::Target { .. } => {}
+            wl_data_source::Event::Send { mime_type, fd } => {
+                let seat = input_state(&mut state.input_states, data.0);
+                let Some(drag) = seat.data_device.as_ref().and_then(|it| it.drag.as_ref()) else {
+                    return;
+                };
+                let Some(format) = dnd::find_format(&drag.formats, &mime_type) else {
+                    tracing::warn!("drag target asked for {mime_type}, which we didn't offer");
+                    return;
+                };
+                // This blocks until the target has read everything, like the X11 clipboard.
+                if let Err(err) = File::from(fd).write_all(&format.data) {
+                    tracing::warn!("failed to send {mime_type} to the drag target: {err}");
+                }
+            }
+            wl_data_source::Event::Action { dnd_action } => {
+                let seat = input_state(&mut state.input_states, data.0);
+                if let Some(drag) = seat.data_device.as_mut().and_then(|it| it.drag.as_mut()) {
+                    drag.action = drop_action(dnd_action);
+                }
+            }
+            // The target performed the drop, but hasn't finished reading the data
+            wl_data_source::Event::DndDropPerformed => {}
+            wl_data_source::Event::DndFinished => state.finish_drag(data.0, false),
+            wl_data_source::Event::Cancelled => state.finish_drag(data.0, true),
+            _ => {}
+        }
+    }
+}
@@ -7,10 +7,13 @@ use std::{
 use crate::{
     backend::shared::xkb::{xkb_simulate_input, KeyboardHandled},
     text::InputHandler,
-    Counter, TextFieldToken, WinHandler,
+    Counter, Modifiers, TextFieldToken, WinHandler,
 };
 
-use self::{keyboard::KeyboardState, text_input::InputState};
+use self::{
+    data_device::DataDeviceState, keyboard::KeyboardState, pointer::PointerState,
+    tablet::TabletState, text_input::InputState,
+};
 
 use super::{
     window::{WaylandWindowState, WindowId},
@@ -27,9 +30,13 @@ use smithay_client_toolkit::{
     seat::SeatHandler,
 };
 
+mod data_device;
 mod keyboard;
+mod pointer;
+mod tablet;
 mod text_input;
 
+pub(super) use data_device::DragRequest;
 pub(super) use text_input::TextInputManagerData;
 
 #[derive(Debug)]
@@ -60,6 +67,8 @@ impl TextFieldChange {
 /// - Touch
 /// Plus:
 /// - Text input
+/// - Data device, for drag and drop
+/// - Tablet seat, for pens and erasers
 ///
 /// These are stored in a vector because we expect nearly all
 /// programs to only encounter a single seat, so we don't need the overhead of a HashMap.
@@ -78,7 +87,10 @@ pub(super) struct SeatInfo {
     id: SeatName,
     seat: wl_seat::WlSeat,
     keyboard_state: Option<KeyboardState>,
+    pointer_state: Option<PointerState>,
     input_state: Option<InputState>,
+    data_device: Option<DataDeviceState>,
+    tablet_state: Option<TabletState>,
     keyboard_focused: Option<WindowId>,
 
     text_field_owner: TextFieldOwner,
@@ -349,6 +361,16 @@ impl SeatInfo {
         self.text_field_owner = TextFieldOwner::Neither;
     }
 
+    /// The keyboard modifiers which are currently held on this seat
+    fn modifiers(&self) -> Modifiers {
+        self.keyboard_state
+            .as_ref()
+            .and_then(|keyboard| keyboard.xkb_state.as_ref())
+            .map_or(Modifiers::empty(), |(xkb_state, _)| {
+                xkb_state.active_modifiers()
+            })
+    }
+
     /// Stop receiving events for the keyboard of this seat
     fn destroy_keyboard(&mut self) {
         self.keyboard_state = None;
@@ -384,6 +406,12 @@ impl SeatInfo {
         let keysym = xkb_state.get_one_sym(scancode);
         let event = xkb_state.key_event(scancode, keysym, key_state, is_repeat);
 
+        // Modal windows block the key presses to their parent, but not the releases
+        if matches!(key_state, KeyState::Down)
+            && windows.get(window).map_or(false, |w| w.has_modal_child())
+        {
+            return;
+        }
         let Some(mut handler) = handler(windows, window) else {
             return;
         };
@@ -513,6 +541,16 @@ impl WaylandState {
     }
 
     // fn seat_ref(&self, name: SeatName) -> &SeatInfo;
+
+    /// The seat and serial of the latest button press in `window`.
+    ///
+    /// Requests which need an implicit grab, such as moving the window, must pass these.
+    pub(super) fn latest_press(&self, window: &WindowId) -> Option<(wl_seat::WlSeat, u32)> {
+        self.input_states.iter().find_map(|seat| {
+            let (pressed, serial) = seat.pointer_state.as_ref()?.latest_press.clone()?;
+            (&pressed == window).then(|| (seat.seat.clone(), serial))
+        })
+    }
 }
 
 pub(super) fn input_state(seats: &mut [SeatInfo], name: SeatName) -> &mut SeatInfo {
@@ -529,7 +567,10 @@ impl WaylandState {
             id,
             seat,
             keyboard_state: None,
+            pointer_state: None,
             input_state: None,
+            data_device: None,
+            tablet_state: None,
             keyboard_focused: None,
             text_field_owner: TextFieldOwner::Neither,
         };
@@ -540,6 +581,14 @@ impl WaylandState {
             .text_input
             .as_ref()
             .map(|text_input| InputState::new(text_input, &input.seat, &self.wayland_queue, id));
+        input.data_device = self
+            .data_device_manager
+            .as_ref()
+            .map(|manager| DataDeviceState::new(manager, &self.wayland_queue, id, &input.seat));
+        input.tablet_state = self
+            .tablet_manager
+            .as_ref()
+            .map(|manager| TabletState::new(manager, &self.wayland_queue, id, &input.seat));
     }
 
     pub(super) fn initial_seats(&mut self) {
@@ -565,6 +614,8 @@ impl SeatHandler for WaylandState {
         seat: wl_seat::WlSeat,
         capability: smithay_client_toolkit::seat::Capability,
     ) {
+        let relative_pointer_manager = self.relative_pointer_manager.clone();
+        let pointer_gestures = self.pointer_gestures.clone();
         let seat_info = self.info_of_seat(&seat);
 
         match capability {
@@ -572,7 +623,16 @@ impl SeatHandler for WaylandState {
                 let state = KeyboardState::new(qh, seat_info.id, seat);
                 seat_info.keyboard_state = Some(state);
             }
-            smithay_client_toolkit::seat::Capability::Pointer => {}
+            smithay_client_toolkit::seat::Capability::Pointer => {
+                let state = PointerState::new(
+                    qh,
+                    seat_info.id,
+                    seat,
+                    relative_pointer_manager.as_ref(),
+                    pointer_gestures.as_ref(),
+                );
+                seat_info.pointer_state = Some(state);
+            }
             smithay_client_toolkit::seat::Capability::Touch => {}
             it => tracing::warn!(?seat, "Unknown seat capability {it}"),
         }
@@ -588,7 +648,7 @@ impl SeatHandler for WaylandState {
         let state = self.info_of_seat(&seat);
         match capability {
             smithay_client_toolkit::seat::Capability::Keyboard => state.destroy_keyboard(),
-            smithay_client_toolkit::seat::Capability::Pointer => {}
+            smithay_client_toolkit::seat::Capability::Pointer => state.pointer_state = None,
             smithay_client_toolkit::seat::Capability::Touch => {}
             it => tracing::info!(?seat, "Removed unknown seat capability {it}"),
         }
@@ -0,0 +1,808 @@
+use std::collections::HashMap;
+
+use smithay_client_toolkit::reexports::{
+    client::{
+        protocol::{wl_pointer, wl_seat},
+        Connection, Dispatch, Proxy, QueueHandle, WEnum,
+    },
+    protocols::wp::{
+        pointer_constraints::zv1::client::{
+            zwp_confined_pointer_v1::{self, ZwpConfinedPointerV1},
+            zwp_locked_pointer_v1::{self, ZwpLockedPointerV1},
+            zwp_pointer_constraints_v1::{self, ZwpPointerConstraintsV1},
+        },
+        pointer_gestures::zv1::client::{
+            zwp_pointer_gesture_hold_v1::{self, ZwpPointerGestureHoldV1},
+            zwp_pointer_gesture_pinch_v1::{self, ZwpPointerGesturePinchV1},
+            zwp_pointer_gesture_swipe_v1::{self, ZwpPointerGestureSwipeV1},
+            zwp_pointer_gestures_v1::{self, ZwpPointerGesturesV1},
+        },
+        relative_pointer::zv1::client::{
+            zwp_relative_pointer_manager_v1::{self, ZwpRelativePointerManagerV1},
+            zwp_relative_pointer_v1::{self, ZwpRelativePointerV1},
+        },
+    },
+};
+
+use crate::{
+    backend::{
+        shared::linux,
+        wayland::{
+            cursor::{CursorSurface, Cursors},
+            window::WindowId,
+        },
+    },
+    common_util::ClickCounter,
+    kurbo::{Point, Rect, Vec2},
+    mouse::Cursor,
+    pointer::{
+        Angle, Gesture, GestureEvent, GesturePhase, MouseInfo, PointerButton, PointerButtons,
+        PointerEvent, PointerId, PointerType, ScrollInfo, ScrollPhase, ScrollSource, ScrollUnit,
+    },
+    Modifiers, PointerGrab, Region, WinHandler,
+};
+
+use super::{input_state, SeatName, WaylandState, Windows};
+
+/// The seat identifier of this pointer
+pub(super) struct PointerUserData(SeatName);
+
+pub(super) struct PointerState {
+    pointer: wl_pointer::WlPointer,
+    /// The window which the pointer is over
+    focus: Option<WindowId>,
+    /// The serial of the latest `enter` event, which is needed to set the cursor.
+    enter_serial: u32,
+    cursor: CursorSurface,
+    /// The position of the pointer in the focused window.
+    /// Surface local coordinates are already in display points.
+    pos: Point,
+    buttons: PointerButtons,
+    /// The window and serial of the latest button press, while a button is held down.
+    ///
+    /// Requests which need an implicit grab, such as starting a drag, must pass this serial.
+    /// The grab ends when the last button is released.
+    pub(super) latest_press: Option<(WindowId, u32)>,
+    /// The scroll since the last `frame` event.
+    pending_scroll: Vec2,
+    /// The wheel clicks since the last `frame` event, which replace `pending_scroll` on each
+    /// axis where they are present.
+    pending_discrete: Vec2,
+    /// The source of the scroll in the current frame.
+    pending_source: Option<ScrollSource>,
+    /// Whether the scroll stopped in the current frame.
+    pending_stop: bool,
+    /// The timestamp of the latest scroll in the current frame.
+    pending_time: u32,
+    /// Whether a scroll by fingers is in progress, which ends with an `axis_stop` event.
+    scrolling: bool,
+    click_counter: ClickCounter,
+    /// The source of the relative motion, which is reported while the pointer is locked.
+    relative_pointer: Option<ZwpRelativePointerV1>,
+    /// The constraints on the pointer, for each window which locked or confined it.
+    constraints: HashMap<WindowId, PointerConstraint>,
+    /// Whether the pointer is locked in the focused window.
+    locked: bool,
+    gestures: Option<PointerGestures>,
+    /// The number of fingers of the current gesture, which is only sent when it begins.
+    gesture_fingers: u32,
+    /// The scale of the current pinch, to report its changes to `WinHandler::zoom`.
+    pinch_scale: f64,
+}
+
+/// The sources of the touchpad gestures of a pointer, which are destroyed when this is dropped.
+struct PointerGestures {
+    swipe: ZwpPointerGestureSwipeV1,
+    pinch: ZwpPointerGesturePinchV1,
+    /// Hold gestures were added in version 3 of the protocol.
+    hold: Option<ZwpPointerGestureHoldV1>,
+}
+
+impl Drop for PointerGestures {
+    fn drop(&mut self) {
+        self.swipe.destroy();
+        self.pinch.destroy();
+        if let Some(hold) = &self.hold {
+            hold.destroy();
+        }
+    }
+}
+
+/// A lock or confinement of the pointer to a surface, which is removed when this is dropped.
+enum PointerConstraint {
+    Locked(ZwpLockedPointerV1),
+    Confined(ZwpConfinedPointerV1),
+}
+
+impl Drop for PointerConstraint {
+    fn drop(&mut self) {
+        match self {
+            PointerConstraint::Locked(locked) => locked.destroy(),
+            PointerConstraint::Confined(confined) => confined.destroy(),
+        }
+    }
+}
+
+/// The pointer and window of a constraint
+pub(super) struct ConstraintUserData(SeatName, WindowId);
+
+impl PointerState {
+    pub(super) fn new(
+        qh: &QueueHandle<WaylandState>,
+        name: SeatName,
+        seat: wl_seat::WlSeat,
+        relative_pointer_manager: Option<&ZwpRelativePointerManagerV1>,
+        pointer_gestures: Option<&ZwpPointerGesturesV1>,
+    ) -> Self {
+        let pointer = seat.get_pointer(qh, PointerUserData(name));
+        let relative_pointer = relative_pointer_manager
+            .map(|manager| manager.get_relative_pointer(&pointer, qh, PointerUserData(name)));
+        let gestures = pointer_gestures.map(|manager| PointerGestures {
+            swipe: manager.get_swipe_gesture(&pointer, qh, PointerUserData(name)),
+            pinch: manager.get_pinch_gesture(&pointer, qh, PointerUserData(name)),
+            hold: (manager.version() >= 3)
+                .then(|| manager.get_hold_gesture(&pointer, qh, PointerUserData(name))),
+        });
+        PointerState {
+            pointer,
+            focus: None,
+            enter_serial: 0,
+            cursor: CursorSurface::default(),
+            pos: Point::ZERO,
+            buttons: PointerButtons::new(),
+            latest_press: None,
+            pending_scroll: Vec2::ZERO,
+            pending_discrete: Vec2::ZERO,
+            pending_source: None,
+            pending_stop: false,
+            pending_time: 0,
+            scrolling: false,
+            click_counter: ClickCounter::default(),
+            relative_pointer,
+            constraints: HashMap::new(),
+            locked: false,
+            gestures,
+            gesture_fingers: 0,
+            pinch_scale: 1.0,
+        }
+    }
+
+    fn gesture_event(
+        &self,
+        gesture: Gesture,
+        phase: GesturePhase,
+        modifiers: Modifiers,
+    ) -> GestureEvent {
+        GestureEvent {
+            gesture,
+            phase,
+            fingers: self.gesture_fingers,
+            pos: self.pos,
+            modifiers,
+        }
+    }
+
+    fn event(&self, seat: SeatName, modifiers: Modifiers, time: Option<u32>) -> PointerEvent {
+        PointerEvent {
+            pointer_id: PointerId(seat.0),
+            is_primary: true,
+            pointer_type: PointerType::Mouse(MouseInfo::default()),
+            time: time.and_then(linux::event_time),
+            coalesced: Vec::new(),
+            pos: self.pos,
+            buttons: self.buttons,
+            modifiers,
+            button: PointerButton::None,
+            focus: false,
+            count: 0,
+        }
+    }
+
+    /// Run `f` with the handler of the focused window, if there is one.
+    fn with_handler(&self, windows: &mut Windows, f: impl FnOnce(&mut dyn WinHandler)) {
+        let Some(focus) = self.focus.as_ref() else {
+            return;
+        };
+        if let Some(window) = windows.get_mut(focus) {
+            f(&mut *window.handler);
+        }
+    }
+
+    /// Run `f` with the handler of the focused window, unless a modal window blocks its input.
+    ///
+    /// Releases and leaves still go through [`Self::with_handler`], so that the window doesn't
+    /// miss the end of a press or hover which started before the modal window opened.
+    fn with_input_handler(&self, windows: &mut Windows, f: impl FnOnce(&mut dyn WinHandler)) {
+        let Some(focus) = self.focus.as_ref() else {
+            return;
+        };
+        if let Some(window) = windows.get_mut(focus) {
+            if !window.has_modal_child() {
+                f(&mut *window.handler);
+            }
+        }
+    }
+
+    /// Show the cursor of the focused window, or hide it while the pointer is locked.
+    fn refresh_cursor(
+        &mut self,
+        windows: &Windows,
+        cursors: &mut Cursors,
+        qh: &QueueHandle<WaylandState>,
+    ) {
+        let Some(window) = self.focus.as_ref().and_then(|focus| windows.get(focus)) else {
+            return;
+        };
+        let cursor = if self.locked {
+            Cursor::Hidden
+        } else {
+            window.cursor()
+        };
+        self.cursor.show(
+            &cursor,
+            window.scale().x().ceil() as u32,
+            cursors,
+            qh,
+            |surface, hot_x, hot_y| {
+                self.pointer
+                    .set_cursor(self.enter_serial, surface, hot_x, hot_y)
+            },
+        );
+    }
+
+    /// Record the serial of a tablet tool touching `window`, which grabs the pointer of the seat
+    /// like a button press.
+    pub(super) fn tool_down(&mut self, window: WindowId, serial: u32) {
+        self.latest_press = Some((window, serial));
+    }
+
+    /// End the grab of a tablet tool, unless buttons of the pointer are still held.
+    pub(super) fn tool_up(&mut self) {
+        if self.buttons.is_empty() {
+            self.latest_press = None;
+        }
+    }
+
+    fn flush_scroll(&mut self, seat: SeatName, modifiers: Modifiers, windows: &mut Windows) {
+        let scroll = std::mem::replace(&mut self.pending_scroll, Vec2::ZERO);
+        let discrete = std::mem::replace(&mut self.pending_discrete, Vec2::ZERO);
+        let source = self.pending_source.take().unwrap_or_default();
+        let stop = std::mem::take(&mut self.pending_stop);
+        // We use a delta of 120 per wheel click, to match the other backends.
+        let axis_delta = |scroll: f64, discrete: f64| {
+            if discrete != 0.0 {
+                discrete * 120.0
+            } else {
+                scroll
+            }
+        };
+        let delta = Vec2::new(
+            axis_delta(scroll.x, discrete.x),
+            axis_delta(scroll.y, discrete.y),
+        );
+        // Only scrolls by fingers are guaranteed to end with `axis_stop`
+        let phase = if stop && self.scrolling {
+            self.scrolling = false;
+            Some(ScrollPhase::Ended)
+        } else if delta == Vec2::ZERO {
+            return;
+        } else if source == ScrollSource::Finger {
+            Some(if std::mem::replace(&mut self.scrolling, true) {
+                ScrollPhase::Changed
+            } else {
+                ScrollPhase::Began
+            })
+        } else {
+            None
+        };
+        let mut event = self.event(seat, modifiers, Some(self.pending_time));
+        event.pointer_type = PointerType::Mouse(MouseInfo {
+            wheel_delta: delta,
+            scroll: ScrollInfo {
+                // Surface local coordinates are already in display points
+                delta: scroll,
+                unit: ScrollUnit::Pixels,
+                clicks: discrete,
+                phase,
+                source,
+            },
+        });
+        self.with_input_handler(windows, |handler| handler.wheel(event));
+    }
+}
+
+impl WaylandState {
+    /// Show the cursor of `window` for the pointers and tablet tools which are over it.
+    pub(in crate::backend::wayland) fn update_cursors(&mut self, window: &WindowId) {
+        for seat in &mut self.input_states {
+            let Some(pointer) = seat.pointer_state.as_mut() else {
+                continue;
+            };
+            if pointer.focus.as_ref() == Some(window) {
+                pointer.refresh_cursor(&self.windows, &mut self.cursors, &self.wayland_queue);
+            }
+        }
+        for seat in &mut self.input_states {
+            if let Some(tablet) = seat.tablet_state.as_mut() {
+                tablet.update_cursors(
+                    window,
+                    &self.windows,
+                    &mut self.cursors,
+                    &self.wayland_queue,
+                );
+            }
+        }
+    }
+
+    /// Lock or confine the pointers of every seat in `window`, replacing their previous
+    /// constraints.
+    pub(in crate::backend::wayland) fn set_pointer_grab(
+        &mut self,
+        window: &WindowId,
+        grab: PointerGrab,
+    ) {
+        let Some(surface) = self.windows.get(window).map(|win| win.surface()) else {
+            return;
+        };
+        if grab == PointerGrab::Capture {
+            // The compositor already keeps sending the events of a pointer to the surface where
+            // one of its buttons was pressed, and there is no way to ask for more.
+            tracing::debug!("Pointer capture is only implicit on wayland");
+        }
+        let manager = match grab {
+            PointerGrab::Lock | PointerGrab::Confine(_) => {
+                if self.pointer_constraints.is_none() {
+                    tracing::warn!("The compositor doesn't support pointer constraints");
+                }
+                self.pointer_constraints.as_ref()
+            }
+            PointerGrab::None | PointerGrab::Capture => None,
+        };
+        // Rounding inwards, so that the pointer stays over the rectangle
+        let region = match grab {
+            PointerGrab::Confine(rect) => {
+                Some(self.create_region(&Region::from(rect), Rect::trunc))
+            }
+            _ => None,
+        };
+        for seat in &mut self.input_states {
+            let Some(pointer) = seat.pointer_state.as_mut() else {
+                continue;
+            };
+            // There can only be one constraint for each surface and pointer.
+            let removed = pointer.constraints.remove(window);
+            if matches!(removed, Some(PointerConstraint::Locked(_))) && pointer.locked {
+                // The compositor doesn't report the end of a lock which we destroyed.
+                pointer.locked = false;
+                pointer.refresh_cursor(&self.windows, &mut self.cursors, &self.wayland_queue);
+            }
+            let Some(manager) = manager else {
+                continue;
+            };
+            let data = ConstraintUserData(seat.id, window.clone());
+            let lifetime = zwp_pointer_constraints_v1::Lifetime::Persistent;
+            let constraint = if let PointerGrab::Lock = grab {
+                PointerConstraint::Locked(manager.lock_pointer(
+                    &surface,
+                    &pointer.pointer,
+                    None,
+                    lifetime,
+                    &self.wayland_queue,
+                    data,
+                ))
+            } else {
+                PointerConstraint::Confined(manager.confine_pointer(
+                    &surface,
+                    &pointer.pointer,
+                    region.as_ref(),
+                    lifetime,
+                    &self.wayland_queue,
+                    data,
+                ))
+            };
+            pointer.constraints.insert(window.clone(), constraint);
+        }
+        // The constraints copy the region when they are created
+        if let Some(region) = region {
+            region.destroy();
+        }
+    }
+}
+
+impl Drop for PointerState {
+    fn drop(&mut self) {
+        if let Some(relative_pointer) = &self.relative_pointer {
+            relative_pointer.destroy();
+        }
+        // The constraints and gestures need to be destroyed before the pointer
+        self.constraints.clear();
+        self.gestures.take();
+        if self.pointer.version() >= 3 {
+            self.pointer.release()
+        }
+    }
+}
+
+/// Convert a button code from `linux/input-event-codes.h`.
+pub(super) fn pointer_button(button: u32) -> PointerButton {
+    match button {
+        0x110 => PointerButton::Primary,
+        0x111 => PointerButton::Secondary,
+        0x112 => PointerButton::Auxiliary,
+        // BTN_SIDE and BTN_BACK
+        0x113 | 0x116 => PointerButton::X1,
+        // BTN_EXTRA and BTN_FORWARD
+        0x114 | 0x115 => PointerButton::X2,
+        _ => PointerButton::None,
+    }
+}
+
+impl Dispatch<wl_pointer::WlPointer, PointerUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &wl_pointer::WlPointer,
+        event: wl_pointer::Event,
+        data: &PointerUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let seat = input_state(&mut state.input_states, data.0);
+        let modifiers = seat.modifiers();
+        let Some(pointer) = seat.pointer_state.as_mut() else {
+            return;
+        };
+        let windows = &mut state.windows;
+        match event {
+            wl_pointer::Event::Enter {
+                serial,
+                surface,
+                surface_x,
+                surface_y,
+            } => {
+                pointer.enter_serial = serial;
+                pointer.focus = Some(WindowId::of_surface(&surface));
+                pointer.pos = Point::new(surface_x, surface_y);
+                // Wayland doesn't say which buttons are already held
+                pointer.buttons.clear();
+                // The cursor needs to be set again on every entry
+                pointer.refresh_cursor(windows, &mut state.cursors, &state.wayland_queue);
+                let event = pointer.event(data.0, modifiers, None);
+                pointer.with_input_handler(windows, |handler| handler.pointer_enter(event));
+            }
+            wl_pointer::Event::Leave { .. } => {
+                pointer.with_handler(windows, |handler| handler.pointer_leave());
+                pointer.focus = None;
+                pointer.buttons.clear();
+                pointer.pending_scroll = Vec2::ZERO;
+                pointer.pending_discrete = Vec2::ZERO;
+                pointer.pending_source = None;
+                pointer.pending_stop = false;
+                pointer.scrolling = false;
+            }
+            wl_pointer::Event::Motion {
+                time,
+                surface_x,
+                surface_y,
+            } => {
+                pointer.pos = Point::new(surface_x, surface_y);
+                let event = pointer.event(data.0, modifiers, Some(time));
+                pointer.with_input_handler(windows, |handler| handler.pointer_move(event));
+            }
+            wl_pointer::Event::Button {
+                serial,
+                time,
+                button,
+                state: button_state,
+            } => {
+                let button = pointer_button(button);
+                match button_state {
+                    WEnum::Value(wl_pointer::ButtonState::Pressed) => {
+                        if let Some(focus) = pointer.focus.clone() {
+                            pointer.latest_press = Some((focus, serial));
+                        }
+                        pointer.buttons.insert(button);
+                        let mut event = pointer.event(data.0, modifiers, Some(time));
+                        event.button = button;
+                        event.count = pointer.click_counter.count_for_click(pointer.pos);
+                        pointer.with_input_handler(windows, |handler| handler.pointer_down(event));
+                    }
+                    WEnum::Value(wl_pointer::ButtonState::Released) => {
+                        pointer.buttons.remove(button);
+                        if pointer.buttons.is_empty() {
+                            pointer.latest_press = None;
+                        }
+                        let mut event = pointer.event(data.0, modifiers, Some(time));
+                        event.button = button;
+                        pointer.with_handler(windows, |handler| handler.pointer_up(event));
+                    }
+                    it => tracing::warn!(pointer = ?proxy, "unknown button state {it:?}"),
+                }
+            }
+            wl_pointer::Event::Axis { time, axis, value } => {
+                pointer.pending_time = time;
+                match axis {
+                    WEnum::Value(wl_pointer::Axis::VerticalScroll) => {
+                        pointer.pending_scroll.y += value
+                    }
+                    WEnum::Value(wl_pointer::Axis::HorizontalScroll) => {
+                        pointer.pending_scroll.x += value
+                    }
+                    it => tracing::warn!(pointer = ?proxy, "unknown scroll axis {it:?}"),
+                }
+                // Before version 5, there are no frame events to group the axes
+                if proxy.version() < 5 {
+                    pointer.flush_scroll(data.0, modifiers, windows);
+                }
+            }
+            wl_pointer::Event::Frame => pointer.flush_scroll(data.0, modifiers, windows),
+            wl_pointer::Event::AxisDiscrete { axis, discrete } => match axis {
+                WEnum::Value(wl_pointer::Axis::VerticalScroll) => {
+                    pointer.pending_discrete.y += f64::from(discrete)
+                }
+                WEnum::Value(wl_pointer::Axis::HorizontalScroll) => {
+                    pointer.pending_discrete.x += f64::from(discrete)
+                }
+                it => tracing::warn!(pointer = ?proxy, "unknown scroll axis {it:?}"),
+            },
+            // Since version 8, high-resolution wheels report fractions of a click instead of
+            // `axis_discrete`
+            wl_pointer::Event::AxisValue120 { axis, value120 } => match axis {
+                WEnum::Value(wl_pointer::Axis::VerticalScroll) => {
+                    pointer.pending_discrete.y += f64::from(value120) / 120.0
+                }
+                WEnum::Value(wl_pointer::Axis::HorizontalScroll) => {
+                    pointer.pending_discrete.x += f64::from(value120) / 120.0
+                }
+                it => tracing::warn!(pointer = ?proxy, "unknown scroll axis {it:?}"),
+            },
+            wl_pointer::Event::AxisSource { axis_source } => {
+                pointer.pending_source = Some(match axis_source {
+                    WEnum::Value(wl_pointer::AxisSource::Wheel) => ScrollSource::Wheel,
+                    WEnum::Value(wl_pointer::AxisSource::Finger) => ScrollSource::Finger,
+                    WEnum::Value(wl_pointer::AxisSource::Continuous) => ScrollSource::Continuous,
+                    WEnum::Value(wl_pointer::AxisSource::WheelTilt) => ScrollSource::WheelTilt,
+                    _ => ScrollSource::Unknown,
+                });
+            }
+            wl_pointer::Event::AxisStop { time, .. } => {
+                pointer.pending_stop = true;
+                pointer.pending_time = time;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpPointerConstraintsV1, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpPointerConstraintsV1,
+        _: zwp_pointer_constraints_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // zwp_pointer_constraints_v1 has no events.
+    }
+}
+
+impl Dispatch<ZwpLockedPointerV1, ConstraintUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &ZwpLockedPointerV1,
+        event: zwp_locked_pointer_v1::Event,
+        data: &ConstraintUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let seat = input_state(&mut state.input_states, data.0);
+        let Some(pointer) = seat.pointer_state.as_mut() else {
+            return;
+        };
+        // The lock is only active while the pointer is over the window
+        pointer.locked = match event {
+            zwp_locked_pointer_v1::Event::Locked => true,
+            zwp_locked_pointer_v1::Event::Unlocked => false,
+            _ => return,
+        };
+        if pointer.focus.as_ref() == Some(&data.1) {
+            pointer.refresh_cursor(&state.windows, &mut state.cursors, &state.wayland_queue);
+        }
+    }
+}
+
+impl Dispatch<ZwpConfinedPointerV1, ConstraintUserData> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpConfinedPointerV1,
+        _: zwp_confined_pointer_v1::Event,
+        _: &ConstraintUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // The pointer events are the same whether the pointer is confined or not.
+    }
+}
+
+impl Dispatch<ZwpRelativePointerManagerV1, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpRelativePointerManagerV1,
+        _: zwp_relative_pointer_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // zwp_relative_pointer_manager_v1 has no events.
+    }
+}
+
+impl Dispatch<ZwpRelativePointerV1, PointerUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &ZwpRelativePointerV1,
+        event: zwp_relative_pointer_v1::Event,
+        data: &PointerUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let zwp_relative_pointer_v1::Event::RelativeMotion { dx, dy, .. } = event else {
+            return;
+        };
+        let seat = input_state(&mut state.input_states, data.0);
+        let Some(pointer) = seat.pointer_state.as_mut() else {
+            return;
+        };
+        if !pointer.locked {
+            return;
+        }
+        // Surface local coordinates are already in display points
+        let delta = Vec2::new(dx, dy);
+        pointer.with_input_handler(&mut state.windows, |handler| {
+            handler.relative_pointer_move(delta)
+        });
+    }
+}
+
+/// The phase of a gesture which ended, possibly by being cancelled.
+fn end_phase(cancelled: i32) -> GesturePhase {
+    if cancelled != 0 {
+        GesturePhase::Cancel
+    } else {
+        GesturePhase::End
+    }
+}
+
+impl Dispatch<ZwpPointerGesturesV1, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpPointerGesturesV1,
+        _: zwp_pointer_gestures_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // zwp_pointer_gestures_v1 has no events.
+    }
+}
+
+impl Dispatch<ZwpPointerGestureSwipeV1, PointerUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &ZwpPointerGestureSwipeV1,
+        event: zwp_pointer_gesture_swipe_v1::Event,
+        data: &PointerUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let seat = input_state(&mut state.input_states, data.0);
+        let modifiers = seat.modifiers();
+        let Some(pointer) = seat.pointer_state.as_mut() else {
+            return;
+        };
+        let (delta, phase) = match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin { fingers, .. } => {
+                pointer.gesture_fingers = fingers;
+                (Vec2::ZERO, GesturePhase::Begin)
+            }
+            // Surface local coordinates are already in display points
+            zwp_pointer_gesture_swipe_v1::Event::Update { dx, dy, .. } => {
+                (Vec2::new(dx, dy), GesturePhase::Update)
+            }
+            zwp_pointer_gesture_swipe_v1::Event::End { cancelled, .. } => {
+                (Vec2::ZERO, end_phase(cancelled))
+            }
+            _ => return,
+        };
+        let event = pointer.gesture_event(Gesture::Swipe { delta }, phase, modifiers);
+        pointer.with_input_handler(&mut state.windows, |handler| handler.gesture(event));
+    }
+}
+
+impl Dispatch<ZwpPointerGesturePinchV1, PointerUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &ZwpPointerGesturePinchV1,
+        event: zwp_pointer_gesture_pinch_v1::Event,
+        data: &PointerUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let seat = input_state(&mut state.input_states, data.0);
+        let modifiers = seat.modifiers();
+        let Some(pointer) = seat.pointer_state.as_mut() else {
+            return;
+        };
+        let mut zoom = 0.0;
+        let (gesture, phase) = match event {
+            zwp_pointer_gesture_pinch_v1::Event::Begin { fingers, .. } => {
+                pointer.gesture_fingers = fingers;
+                pointer.pinch_scale = 1.0;
+                let gesture = Gesture::Pinch {
+                    scale: 1.0,
+                    rotation: Angle::radians(0.0),
+                    delta: Vec2::ZERO,
+                };
+                (gesture, GesturePhase::Begin)
+            }
+            zwp_pointer_gesture_pinch_v1::Event::Update {
+                dx,
+                dy,
+                scale,
+                rotation,
+                ..
+            } => {
+                zoom = scale - std::mem::replace(&mut pointer.pinch_scale, scale);
+                let gesture = Gesture::Pinch {
+                    scale,
+                    rotation: Angle::degrees(rotation),
+                    delta: Vec2::new(dx, dy),
+                };
+                (gesture, GesturePhase::Update)
+            }
+            zwp_pointer_gesture_pinch_v1::Event::End { cancelled, .. } => {
+                let gesture = Gesture::Pinch {
+                    scale: pointer.pinch_scale,
+                    rotation: Angle::radians(0.0),
+                    delta: Vec2::ZERO,
+                };
+                (gesture, end_phase(cancelled))
+            }
+            _ => return,
+        };
+        let event = pointer.gesture_event(gesture, phase, modifiers);
+        pointer.with_input_handler(&mut state.windows, |handler| {
+            handler.gesture(event);
+            if zoom != 0.0 {
+                handler.zoom(zoom);
+            }
+        });
+    }
+}
+
+impl Dispatch<ZwpPointerGestureHoldV1, PointerUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &ZwpPointerGestureHoldV1,
+        event: zwp_pointer_gesture_hold_v1::Event,
+        data: &PointerUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let seat = input_state(&mut state.input_states, data.0);
+        let modifiers = seat.modifiers();
+        let Some(pointer) = seat.pointer_state.as_mut() else {
+            return;
+        };
+        let phase = match event {
+            zwp_pointer_gesture_hold_v1::Event::Begin { fingers, .. } => {
+                pointer.gesture_fingers = fingers;
+                GesturePhase::Begin
+            }
+            zwp_pointer_gesture_hold_v1::Event::End { cancelled, .. } => end_phase(cancelled),
+            _ => return,
+        };
+        let event = pointer.gesture_event(Gesture::Hold, phase, modifiers);
+        pointer.with_input_handler(&mut state.windows, |handler| handler.gesture(event));
+    }
+}
@@ -0,0 +1,680 @@
+use std::sync::Mutex;
+
+use smithay_client_toolkit::reexports::{
+    client::{
+        event_created_child, protocol::wl_seat, Connection, Dispatch, Proxy, QueueHandle, WEnum,
+    },
+    protocols::wp::tablet::zv2::client::{
+        zwp_tablet_manager_v2::{self, ZwpTabletManagerV2},
+        zwp_tablet_pad_group_v2::{self, ZwpTabletPadGroupV2},
+        zwp_tablet_pad_ring_v2::{self, ZwpTabletPadRingV2},
+        zwp_tablet_pad_strip_v2::{self, ZwpTabletPadStripV2},
+        zwp_tablet_pad_v2::{self, ZwpTabletPadV2},
+        zwp_tablet_seat_v2::{self, ZwpTabletSeatV2},
+        zwp_tablet_tool_v2::{self, ZwpTabletToolV2},
+        zwp_tablet_v2::{self, ZwpTabletV2},
+    },
+};
+
+use crate::{
+    backend::{
+        shared::linux,
+        wayland::{
+            cursor::{CursorSurface, Cursors},
+            window::WindowId,
+        },
+    },
+    common_util::ClickCounter,
+    kurbo::Point,
+    pointer::{
+        Angle, MouseInfo, PenInclination, PenInfo, PenTool, PointerButton, PointerButtons,
+        PointerEvent, PointerId, PointerType,
+    },
+    Modifiers, WinHandler,
+};
+
+use super::{input_state, SeatName, WaylandState, Windows};
+
+/// The seat identifier of a tablet seat
+pub(super) struct TabletSeatUserData(SeatName);
+
+/// The seat of a tool, which is only known once the tablet seat has announced the tool.
+#[derive(Default)]
+pub(super) struct TabletToolUserData(Mutex<Option<SeatName>>);
+
+/// The tablet tools of a seat, such as pens and erasers.
+///
+/// Tablets and pads are also announced through the tablet seat, but only the tools move the
+/// pointer, so we ignore the others.
+pub(super) struct TabletState {
+    tablet_seat: ZwpTabletSeatV2,
+    tools: Vec<(ZwpTabletToolV2, ToolState)>,
+}
+
+impl TabletState {
+    pub(super) fn new(
+        manager: &ZwpTabletManagerV2,
+        qh: &QueueHandle<WaylandState>,
+        name: SeatName,
+        seat: &wl_seat::WlSeat,
+    ) -> Self {
+        TabletState {
+            tablet_seat: manager.get_tablet_seat(seat, qh, TabletSeatUserData(name)),
+            tools: Vec::new(),
+        }
+    }
+
+    fn tool(&mut self, tool: &ZwpTabletToolV2) -> Option<&mut ToolState> {
+        self.tools
+            .iter_mut()
+            .find(|(it, _)| it == tool)
+            .map(|(_, state)| state)
+    }
+
+    /// Show the cursor of `window` for the tools which are in proximity of it.
+    pub(super) fn update_cursors(
+        &mut self,
+        window: &WindowId,
+        windows: &Windows,
+        cursors: &mut Cursors,
+        qh: &QueueHandle<WaylandState>,
+    ) {
+        for (tool, state) in &mut self.tools {
+            if state.focus.as_ref() == Some(window) {
+                state.refresh_cursor(tool, windows, cursors, qh);
+            }
+        }
+    }
+}
+
+impl Drop for TabletState {
+    fn drop(&mut self) {
+        for (tool, _) in &self.tools {
+            tool.destroy();
+        }
+        self.tablet_seat.destroy();
+    }
+}
+
+struct ToolState {
+    /// The protocol id of the tool, which tells it apart from the pointer and the other tools.
+    id: u32,
+    /// The kind of tool, or `None` for the mouse and lens tools, which we report as mice.
+    tool_type: Option<PenTool>,
+    /// The serial number of the tool, if the tablet reports it.
+    serial: Option<u64>,
+    /// Whether the tool reports its pressure, as otherwise we report a pressure of 0.5
+    /// while it touches the tablet.
+    has_pressure: bool,
+    /// The window which the tool is in proximity of
+    focus: Option<WindowId>,
+    /// The serial of the latest `proximity_in` event, which is needed to set the cursor.
+    proximity_serial: u32,
+    cursor: CursorSurface,
+    /// The position of the tool in the focused window.
+    /// Surface local coordinates are already in display points.
+    pos: Point,
+    /// The pressure, in the range `[0.0, 1.0]`
+    pressure: f64,
+    /// The tilt along each axis, in degrees
+    tilt: (f64, f64),
+    /// The rotation around the axis of the tool, in degrees
+    rotation: f64,
+    /// The position of the slider, in the range `[-1.0, 1.0]`
+    slider: f64,
+    /// The distance from the tablet, in the range `[0.0, 1.0]`, once the tool has reported it.
+    distance: Option<f64>,
+    /// The buttons which are held, where the tip touching the tablet is the primary button
+    buttons: PointerButtons,
+    click_counter: ClickCounter,
+    /// Whether the tool came into proximity in the current frame.
+    pending_enter: bool,
+    /// Whether the tool left proximity in the current frame.
+    pending_leave: bool,
+    /// Whether the tool moved or any of its axes changed in the current frame.
+    pending_motion: bool,
+    /// The buttons pressed or released in the current frame, in order.
+    pending_buttons: Vec<(PointerButton, bool)>,
+}
+
+impl ToolState {
+    fn new(id: u32) -> Self {
+        ToolState {
+            id,
+            tool_type: Some(PenTool::Pen),
+            serial: None,
+            has_pressure: false,
+            focus: None,
+            proximity_serial: 0,
+            cursor: CursorSurface::default(),
+            pos: Point::ZERO,
+            pressure: 0.0,
+            tilt: (0.0, 0.0),
+            rotation: 0.0,
+            slider: 0.0,
+            distance: None,
+            buttons: PointerButtons::new(),
+            click_counter: ClickCounter::default(),
+            pending_enter: false,
+            pending_leave: false,
+            pending_motion: false,
+            pending_buttons: Vec::new(),
+        }
+    }
+
+    fn event(&self, seat: SeatName, modifiers: Modifiers, time: u32) -> PointerEvent {
+        let pressure = if self.has_pressure {
+            self.pressure
+        } else if self.buttons.contains(PointerButton::Primary) {
+            0.5
+        } else {
+            0.0
+        };
+        // The tip touching the tablet is the primary button, and the rest are on the barrel.
+        let mut barrel_buttons = self.buttons;
+        barrel_buttons.remove(PointerButton::Primary);
+        let info = PenInfo {
+            pressure,
+            tangential_pressure: self.slider,
+            inclination: PenInclination::from_tilt(self.tilt.0, self.tilt.1).unwrap_or_default(),
+            twist: Angle::degrees(self.rotation.rem_euclid(360.0)),
+            distance: self.distance,
+            tool: self.tool_type.unwrap_or_default(),
+            serial: self.serial,
+            barrel_buttons,
+        };
+        let pointer_type = match self.tool_type {
+            None => PointerType::Mouse(MouseInfo::default()),
+            Some(PenTool::Eraser) => PointerType::Eraser(info),
+            Some(_) => PointerType::Pen(info),
+        };
+        PointerEvent {
+            // Distinct from the pointer of the seat, whose id is the seat's name
+            pointer_id: PointerId(u64::from(self.id) << 32 | seat.0),
+            is_primary: true,
+            pointer_type,
+            time: linux::event_time(time),
+            coalesced: Vec::new(),
+            pos: self.pos,
+            buttons: self.buttons,
+            modifiers,
+            button: PointerButton::None,
+            focus: false,
+            count: 0,
+        }
+    }
+
+    /// Show the cursor of the window which the tool is in proximity of.
+    fn refresh_cursor(
+        &mut self,
+        tool: &ZwpTabletToolV2,
+        windows: &Windows,
+        cursors: &mut Cursors,
+        qh: &QueueHandle<WaylandState>,
+    ) {
+        let Some(window) = self.focus.as_ref().and_then(|focus| windows.get(focus)) else {
+            return;
+        };
+        let serial = self.proximity_serial;
+        self.cursor.show(
+            &window.cursor(),
+            window.scale().x().ceil() as u32,
+            cursors,
+            qh,
+            |surface, hot_x, hot_y| tool.set_cursor(serial, surface, hot_x, hot_y),
+        );
+    }
+
+    /// Deliver the changes of the current frame to `handler`, the handler of the focused
+    /// window. While `blocked` by a modal window, only releases and leaves are delivered.
+    fn flush(
+        &mut self,
+        seat: SeatName,
+        modifiers: Modifiers,
+        time: u32,
+        handler: Option<&mut dyn WinHandler>,
+        blocked: bool,
+    ) {
+        let enter = std::mem::take(&mut self.pending_enter);
+        let leave = std::mem::take(&mut self.pending_leave);
+        let motion = std::mem::take(&mut self.pending_motion);
+        let pending_buttons = std::mem::take(&mut self.pending_buttons);
+        let Some(handler) = handler else {
+            return;
+        };
+        if !blocked {
+            if enter {
+                handler.pointer_enter(self.event(seat, modifiers, time));
+            } else if motion {
+                handler.pointer_move(self.event(seat, modifiers, time));
+            }
+        }
+        for (button, pressed) in pending_buttons {
+            if pressed {
+                self.buttons.insert(button);
+                if blocked {
+                    continue;
+                }
+                let mut event = self.event(seat, modifiers, time);
+                event.button = button;
+                event.count = self.click_counter.count_for_click(self.pos);
+                handler.pointer_down(event);
+            } else {
+                self.buttons.remove(button);
+                let mut event = self.event(seat, modifiers, time);
+                event.button = button;
+                handler.pointer_up(event);
+            }
+        }
+        if leave {
+            handler.pointer_leave();
+            self.focus = None;
+            self.buttons.clear();
+        }
+    }
+}
+
+/// The kind of a tool, or `None` for the mouse and lens tools, which we report as mice.
+fn tool_type(tool_type: WEnum<zwp_tablet_tool_v2::Type>) -> Option<PenTool> {
+    match tool_type {
+        WEnum::Value(zwp_tablet_tool_v2::Type::Eraser) => Some(PenTool::Eraser),
+        WEnum::Value(zwp_tablet_tool_v2::Type::Brush) => Some(PenTool::Brush),
+        WEnum::Value(zwp_tablet_tool_v2::Type::Pencil) => Some(PenTool::Pencil),
+        WEnum::Value(zwp_tablet_tool_v2::Type::Airbrush) => Some(PenTool::Airbrush),
+        WEnum::Value(zwp_tablet_tool_v2::Type::Mouse | zwp_tablet_tool_v2::Type::Lens) => None,
+        // Pens, and fingers used like a pen
+        _ => Some(PenTool::Pen),
+    }
+}
+
+/// Convert a button code of a tool from `linux/input-event-codes.h`.
+fn tool_button(button: u32) -> PointerButton {
+    match button {
+        // BTN_STYLUS
+        0x14b => PointerButton::Secondary,
+        // BTN_STYLUS2
+        0x14c => PointerButton::Auxiliary,
+        // BTN_STYLUS3
+        0x149 => PointerButton::X1,
+        // The buttons of mouse and lens tools
+        _ => super::pointer::pointer_button(button),
+    }
+}
+
+impl Dispatch<ZwpTabletManagerV2, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletManagerV2,
+        _: zwp_tablet_manager_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // zwp_tablet_manager_v2 has no events.
+    }
+}
+
+impl Dispatch<ZwpTabletSeatV2, TabletSeatUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &ZwpTabletSeatV2,
+        event: zwp_tablet_seat_v2::Event,
+        data: &TabletSeatUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // The tablets and pads are destroyed once they are removed
+        if let zwp_tablet_seat_v2::Event::ToolAdded { id } = event {
+            if let Some(tool_data) = id.data::<TabletToolUserData>() {
+                *tool_data.0.lock().unwrap() = Some(data.0);
+            }
+            let seat = input_state(&mut state.input_states, data.0);
+            if let Some(tablet) = seat.tablet_state.as_mut() {
+                let tool = ToolState::new(id.id().protocol_id());
+                tablet.tools.push((id, tool));
+            } else {
+                id.destroy();
+            }
+        }
+    }
+
+    event_created_child!(WaylandState, ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (ZwpTabletToolV2, TabletToolUserData::default()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (ZwpTabletPadV2, ())
+    ]);
+}
+
+impl Dispatch<ZwpTabletToolV2, TabletToolUserData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpTabletToolV2,
+        event: zwp_tablet_tool_v2::Event,
+        data: &TabletToolUserData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(name) = *data.0.lock().unwrap() else {
+            return;
+        };
+        let seat = input_state(&mut state.input_states, name);
+        let modifiers = seat.modifiers();
+        let Some(tablet) = seat.tablet_state.as_mut() else {
+            return;
+        };
+        if let zwp_tablet_tool_v2::Event::Removed = event {
+            tablet.tools.retain(|(it, _)| it != proxy);
+            proxy.destroy();
+            return;
+        }
+        let Some(tool) = tablet.tool(proxy) else {
+            return;
+        };
+        match event {
+            zwp_tablet_tool_v2::Event::Type { tool_type: it } => tool.tool_type = tool_type(it),
+            zwp_tablet_tool_v2::Event::HardwareSerial {
+                hardware_serial_hi,
+                hardware_serial_lo,
+            } => {
+                tool.serial =
+                    Some(u64::from(hardware_serial_hi) << 32 | u64::from(hardware_serial_lo));
+            }
+            zwp_tablet_tool_v2::Event::Capability {
+                capability: WEnum::Value(zwp_tablet_tool_v2::Capability::Pressure),
+            } => tool.has_pressure = true,
+            zwp_tablet_tool_v2::Event::ProximityIn {
+                serial, surface, ..
+            } => {
+                tool.focus = Some(WindowId::of_surface(&surface));
+                tool.proximity_serial = serial;
+                tool.pending_enter = true;
+                // Like the pointer, the tool needs its cursor to be set on every entry.
+                tool.refresh_cursor(
+                    proxy,
+                    &state.windows,
+                    &mut state.cursors,
+                    &state.wayland_queue,
+                );
+            }
+            zwp_tablet_tool_v2::Event::ProximityOut => tool.pending_leave = true,
+            zwp_tablet_tool_v2::Event::Down { serial } => {
+                // Touching the tablet starts an implicit grab, just like pressing a button.
+                if let (Some(focus), Some(pointer)) = (&tool.focus, seat.pointer_state.as_mut()) {
+                    pointer.tool_down(focus.clone(), serial);
+                }
+                tool.pending_buttons.push((PointerButton::Primary, true))
+            }
+            zwp_tablet_tool_v2::Event::Up => {
+                if let Some(pointer) = seat.pointer_state.as_mut() {
+                    pointer.tool_up();
+                }
+                tool.pending_buttons.push((PointerButton::Primary, false))
+            }
+            zwp_tablet_tool_v2::Event::Motion { x, y } => {
+                tool.pos = Point::new(x, y);
+                tool.pending_motion = true;
+            }
+            zwp_tablet_tool_v2::Event::Pressure { pressure } => {
+                tool.pressure = f64::from(pressure) / 65535.0;
+                tool.pending_motion = true;
+            }
+            zwp_tablet_tool_v2::Event::Tilt { tilt_x, tilt_y } => {
+                tool.tilt = (tilt_x, tilt_y);
+                tool.pending_motion = true;
+            }
+            zwp_tablet_tool_v2::Event::Rotation { degrees } => {
+                tool.rotation = degrees;
+                tool.pending_motion = true;
+            }
+            zwp_tablet_tool_v2::Event::Slider { position } => {
+                tool.slider = f64::from(position) / 65535.0;
+                tool.pending_motion = true;
+            }
+            zwp_tablet_tool_v2::Event::Distance { distance } => {
+                tool.distance = Some(f64::from(distance) / 65535.0);
+                tool.pending_motion = true;
+            }
+            zwp_tablet_tool_v2::Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => {
+                let button = tool_button(button);
+                if button == PointerButton::None {
+                    return;
+                }
+                match button_state {
+                    WEnum::Value(zwp_tablet_tool_v2::ButtonState::Pressed) => {
+                        tool.pending_buttons.push((button, true))
+                    }
+                    WEnum::Value(zwp_tablet_tool_v2::ButtonState::Released) => {
+                        tool.pending_buttons.push((button, false))
+                    }
+                    it => tracing::warn!(tool = ?proxy, "unknown button state {it:?}"),
+                }
+            }
+            zwp_tablet_tool_v2::Event::Frame { time } => {
+                let window = tool
+                    .focus
+                    .as_ref()
+                    .and_then(|focus| state.windows.get_mut(focus));
+                let blocked = window.as_ref().map_or(false, |w| w.has_modal_child());
+                let handler = window.map(|window| &mut *window.handler as &mut dyn WinHandler);
+                tool.flush(name, modifiers, time, handler, blocked)
+            }
+            // We don't report the wheel of airbrushes or the Wacom identity of the tool.
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletV2, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        proxy: &ZwpTabletV2,
+        event: zwp_tablet_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // We don't need the description of the tablet, as the tools report their own events.
+        if let zwp_tablet_v2::Event::Removed = event {
+            proxy.destroy();
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletPadV2, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        proxy: &ZwpTabletPadV2,
+        event: zwp_tablet_pad_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Pad buttons, rings and strips aren't supported yet.
+        if let zwp_tablet_pad_v2::Event::Removed = event {
+            proxy.destroy();
+        }
+    }
+
+    event_created_child!(WaylandState, ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (ZwpTabletPadGroupV2, ())
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadGroupV2, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletPadGroupV2,
+        _: zwp_tablet_pad_group_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // We only need to create the rings and strips of a group.
+    }
+
+    event_created_child!(WaylandState, ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (ZwpTabletPadRingV2, ()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (ZwpTabletPadStripV2, ())
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadRingV2, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletPadRingV2,
+        _: zwp_tablet_pad_ring_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Pad rings aren't supported yet.
+    }
+}
+
+impl Dispatch<ZwpTabletPadStripV2, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletPadStripV2,
+        _: zwp_tablet_pad_strip_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Pad strips aren't supported yet.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{Log, Received, RecordingHandler};
+
+    /// Flush the frame to a window, which may be blocked by a modal window.
+    fn flush(tool: &mut ToolState, blocked: bool) -> Vec<Received> {
+        let log = Log::default();
+        let mut handler = RecordingHandler::new(&log);
+        tool.flush(
+            SeatName(1),
+            Modifiers::empty(),
+            0,
+            Some(&mut handler),
+            blocked,
+        );
+        log.take()
+    }
+
+    fn names(received: &[Received]) -> Vec<&'static str> {
+        received.iter().map(Received::name).collect()
+    }
+
+    fn pen(event: &PointerEvent) -> &PenInfo {
+        match &event.pointer_type {
+            PointerType::Pen(info) | PointerType::Eraser(info) => info,
+            it => panic!("not a pen: {it:?}"),
+        }
+    }
+
+    #[test]
+    fn frames_are_delivered_in_order() {
+        let mut tool = ToolState::new(3);
+        tool.pending_enter = true;
+        tool.pending_motion = true;
+        let events = flush(&mut tool, false);
+        assert_eq!(names(&events), ["pointer_enter"]);
+        let event = events[0].pointer_event().unwrap();
+        assert_eq!(event.pointer_id, PointerId(3 << 32 | 1));
+
+        tool.pending_motion = true;
+        tool.pending_buttons = vec![
+            (PointerButton::Primary, true),
+            (PointerButton::Secondary, true),
+            (PointerButton::Primary, false),
+        ];
+        let events = flush(&mut tool, false);
+        assert_eq!(
+            names(&events),
+            ["pointer_move", "pointer_down", "pointer_down", "pointer_up"]
+        );
+        let up = events[3].pointer_event().unwrap();
+        assert_eq!(up.button, PointerButton::Primary);
+        assert!(!up.buttons.contains(PointerButton::Primary));
+        assert!(pen(up).barrel_buttons.contains(PointerButton::Secondary));
+
+        tool.pending_leave = true;
+        assert_eq!(names(&flush(&mut tool, false)), ["pointer_leave"]);
+        assert!(tool.buttons.is_empty());
+    }
+
+    #[test]
+    fn frames_without_a_window_are_dropped() {
+        let mut tool = ToolState::new(3);
+        tool.pending_motion = true;
+        tool.pending_buttons = vec![(PointerButton::Primary, true)];
+        tool.flush(SeatName(1), Modifiers::empty(), 0, None, false);
+        assert!(tool.buttons.is_empty());
+        assert!(flush(&mut tool, false).is_empty());
+    }
+
+    #[test]
+    fn blocked_frames_only_deliver_releases() {
+        let mut tool = ToolState::new(3);
+        tool.buttons.insert(PointerButton::Primary);
+        tool.pending_motion = true;
+        tool.pending_buttons = vec![
+            (PointerButton::Primary, false),
+            (PointerButton::Secondary, true),
+        ];
+        tool.pending_leave = true;
+        assert_eq!(
+            names(&flush(&mut tool, true)),
+            ["pointer_up", "pointer_leave"]
+        );
+    }
+
+    #[test]
+    fn pressure_without_the_capability() {
+        let mut tool = ToolState::new(3);
+        let pressure =
+            |tool: &ToolState| pen(&tool.event(SeatName(1), Modifiers::empty(), 0)).pressure;
+        assert_eq!(pressure(&tool), 0.0);
+        tool.buttons.insert(PointerButton::Primary);
+        assert_eq!(pressure(&tool), 0.5);
+        tool.has_pressure = true;
+        tool.pressure = 0.25;
+        assert_eq!(pressure(&tool), 0.25);
+    }
+
+    #[test]
+    fn tool_types() {
+        let mut tool = ToolState::new(3);
+        tool.tool_type = tool_type(WEnum::Value(zwp_tablet_tool_v2::Type::Eraser));
+        tool.rotation = -90.0;
+        let event = tool.event(SeatName(1), Modifiers::empty(), 0);
+        assert!(matches!(event.pointer_type, PointerType::Eraser(_)));
+        assert_eq!(pen(&event).twist, Angle::degrees(270.0));
+
+        tool.tool_type = tool_type(WEnum::Value(zwp_tablet_tool_v2::Type::Lens));
+        let event = tool.event(SeatName(1), Modifiers::empty(), 0);
+        assert!(matches!(event.pointer_type, PointerType::Mouse(_)));
+
+        assert_eq!(
+            tool_type(WEnum::Value(zwp_tablet_tool_v2::Type::Finger)),
+            Some(PenTool::Pen)
+        );
+        assert_eq!(tool_type(WEnum::Unknown(42)), Some(PenTool::Pen));
+    }
+
+    #[test]
+    fn tool_buttons() {
+        assert_eq!(tool_button(0x14b), PointerButton::Secondary);
+        assert_eq!(tool_button(0x14c), PointerButton::Auxiliary);
+        assert_eq!(tool_button(0x149), PointerButton::X1);
+        // BTN_LEFT of a mouse tool
+        assert_eq!(tool_button(0x110), PointerButton::Primary);
+        assert_eq!(tool_button(0x100), PointerButton::None);
+    }
+}
@@ -26,8 +26,17 @@ use smithay_client_toolkit::{
     output::OutputState,
     reexports::{
         calloop::{channel, EventLoop, LoopHandle, LoopSignal},
-        client::QueueHandle,
-        protocols::wp::text_input::zv3::client::zwp_text_input_manager_v3::ZwpTextInputManagerV3,
+        client::{
+            protocol::{wl_data_device_manager::WlDataDeviceManager, wl_shm::WlShm},
+            QueueHandle,
+        },
+        protocols::wp::{
+            pointer_constraints::zv1::client::zwp_pointer_constraints_v1::ZwpPointerConstraintsV1,
+            pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+            relative_pointer::zv1::client::zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1,
+            tablet::zv2::client::zwp_tablet_manager_v2::ZwpTabletManagerV2,
+            text_input::zv3::client::zwp_text_input_manager_v3::ZwpTextInputManagerV3,
+        },
     },
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
@@ -38,6 +47,8 @@ use smithay_client_toolkit::{
 use crate::{AppHandler, IdleToken};
 
 use self::{
+    cursor::Cursors,
+    icon::protocol::xdg_toplevel_icon_manager_v1::XdgToplevelIconManagerV1,
     input::SeatInfo,
     window::{WaylandWindowState, WindowAction, WindowId},
 };
@@ -46,10 +57,14 @@ use super::shared::xkb::Context;
 
 pub mod application;
 pub mod clipboard;
+mod cursor;
+pub mod dnd;
 pub mod error;
+mod icon;
 mod input;
 pub mod menu;
 pub mod screen;
+mod shm;
 pub mod window;
 
 enum ActiveAction {
@@ -76,9 +91,10 @@ struct WaylandState {
     pub registry_state: RegistryState,
 
     pub output_state: OutputState,
-    // TODO: Do we need to keep this around
-    // It is unused because(?) wgpu creates the surfaces through RawDisplayHandle(?)
-    pub _compositor_state: CompositorState,
+    // Used to create the surfaces of drag icons
+    pub compositor_state: CompositorState,
+    pub shm: WlShm,
+    pub cursors: Cursors,
     // Is used: Keep the XdgShell alive, which is a Weak in all Handles
     pub _xdg_shell_state: Rc<XdgShell>,
     pub wayland_queue: QueueHandle<Self>,
@@ -98,6 +114,12 @@ struct WaylandState {
     pub input_states: Vec<SeatInfo>,
     pub xkb_context: Context,
     pub text_input: Option<ZwpTextInputManagerV3>,
+    pub data_device_manager: Option<WlDataDeviceManager>,
+    pub toplevel_icon_manager: Option<XdgToplevelIconManagerV1>,
+    pub pointer_constraints: Option<ZwpPointerConstraintsV1>,
+    pub relative_pointer_manager: Option<ZwpRelativePointerManagerV1>,
+    pub pointer_gestures: Option<ZwpPointerGesturesV1>,
+    pub tablet_manager: Option<ZwpTabletManagerV2>,
 }
 
 delegate_registry!(WaylandState);
@@ -19,6 +19,7 @@ use smithay_client_toolkit::{
     reexports::client::{protocol::wl_output::WlOutput, Connection, QueueHandle},
 };
 
+use crate::kurbo::Rect;
 use crate::screen::Monitor;
 
 use super::WaylandState;
@@ -42,6 +43,37 @@ pub(crate) fn get_monitors() -> Vec<Monitor> {
 
 delegate_output!(WaylandState);
 
+impl WaylandState {
+    /// The logical area which `output` covers.
+    fn output_rect(&self, output: &WlOutput) -> Option<Rect> {
+        let info = self.output_state.info(output)?;
+        let (Some((x, y)), Some((width, height))) = (info.logical_position, info.logical_size)
+        else {
+            return None;
+        };
+        Some(Rect::from_origin_size(
+            (x as f64, y as f64),
+            (width as f64, height as f64),
+        ))
+    }
+
+    /// The output which covers the same logical area as `monitor`.
+    pub(super) fn output_for_monitor(&self, monitor: &Monitor) -> Option<WlOutput> {
+        self.output_state
+            .outputs()
+            .find(|output| self.output_rect(output) == Some(monitor.virtual_rect()))
+    }
+
+    /// The monitor for `output`.
+    ///
+    /// Wayland has no concept of a primary output or of work areas, so the monitor is never
+    /// primary, and its work area is the whole output.
+    pub(super) fn monitor_for_output(&self, output: &WlOutput) -> Option<Monitor> {
+        let rect = self.output_rect(output)?;
+        Some(Monitor::new(false, rect, rect))
+    }
+}
+
 impl OutputHandler for WaylandState {
     fn output_state(&mut self) -> &mut OutputState {
         &mut self.output_state
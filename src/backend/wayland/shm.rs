@@ -0,0 +1,314 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sharing pixels with the compositor through wl_shm.
+
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::fs::FileExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use nix::sys::memfd::{memfd_create, MemFdCreateFlag};
+use smithay_client_toolkit::reexports::client::{
+    protocol::{wl_buffer, wl_shm, wl_shm_pool, wl_surface},
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+
+use super::WaylandState;
+use crate::kurbo::Rect;
+use crate::pixels::{PixelBuffer, PixelRect};
+
+/// Create a buffer holding a copy of an image.
+///
+/// `rgba` has four bytes per pixel, which are not premultiplied, and no padding between rows.
+pub(super) fn buffer_from_rgba(
+    shm: &wl_shm::WlShm,
+    qh: &QueueHandle<WaylandState>,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+) -> std::io::Result<wl_buffer::WlBuffer> {
+    buffer_from_argb8888(shm, qh, width, height, &to_argb8888(rgba))
+}
+
+/// Create a buffer holding a copy of pixels in the format of `wl_shm::Format::Argb8888`.
+pub(super) fn buffer_from_argb8888(
+    shm: &wl_shm::WlShm,
+    qh: &QueueHandle<WaylandState>,
+    width: u32,
+    height: u32,
+    argb: &[u8],
+) -> std::io::Result<wl_buffer::WlBuffer> {
+    let stride = width * 4;
+    let size = stride * height;
+    let mut file = create_memfd()?;
+    file.write_all(argb)?;
+
+    let pool = shm.create_pool(file.as_raw_fd(), size as i32, qh, ());
+    let buffer = pool.create_buffer(
+        0,
+        width as i32,
+        height as i32,
+        stride as i32,
+        wl_shm::Format::Argb8888,
+        qh,
+        (),
+    );
+    // The buffer keeps the memory alive.
+    pool.destroy();
+    Ok(buffer)
+}
+
+fn create_memfd() -> std::io::Result<File> {
+    let name = CStr::from_bytes_with_nul(b"glazier-shm\0").unwrap();
+    let fd = memfd_create(name, MemFdCreateFlag::MFD_CLOEXEC)?;
+    // SAFETY: `memfd_create` returns a new file descriptor, which nothing else owns.
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+/// The buffers that [`PixelBuffer`]s are copied into before they are attached to a surface.
+///
+/// There are usually two buffers, one of which can be written while the compositor reads the
+/// other. A third one is only created if the compositor holds on to both.
+#[derive(Default)]
+pub(super) struct SurfaceBuffers {
+    buffers: Vec<ShmBuffer>,
+}
+
+struct ShmBuffer {
+    buffer: wl_buffer::WlBuffer,
+    file: File,
+    width: u32,
+    height: u32,
+    /// The rows which changed since this buffer was last written.
+    pending: Vec<PixelRect>,
+}
+
+/// The user data of the buffers in [`SurfaceBuffers`].
+pub(super) struct ShmBufferData {
+    /// Whether the compositor is done reading the buffer.
+    released: AtomicBool,
+}
+
+impl ShmBuffer {
+    fn new(
+        shm: &wl_shm::WlShm,
+        qh: &QueueHandle<WaylandState>,
+        width: u32,
+        height: u32,
+    ) -> std::io::Result<ShmBuffer> {
+        let stride = width * 4;
+        let size = stride * height;
+        let file = create_memfd()?;
+        file.set_len(size as u64)?;
+        let pool = shm.create_pool(file.as_raw_fd(), size as i32, qh, ());
+        let data = ShmBufferData {
+            released: AtomicBool::new(true),
+        };
+        let buffer = pool.create_buffer(
+            0,
+            width as i32,
+            height as i32,
+            stride as i32,
+            wl_shm::Format::Argb8888,
+            qh,
+            data,
+        );
+        pool.destroy();
+        Ok(ShmBuffer {
+            buffer,
+            file,
+            width,
+            height,
+            pending: vec![PixelRect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            }],
+        })
+    }
+
+    fn is_released(&self) -> bool {
+        self.buffer
+            .data::<ShmBufferData>()
+            .map_or(true, |data| data.released.load(Ordering::Acquire))
+    }
+
+    fn set_released(&self, released: bool) {
+        if let Some(data) = self.buffer.data::<ShmBufferData>() {
+            data.released.store(released, Ordering::Release);
+        }
+    }
+
+    /// Copy the rows which this buffer hasn't seen yet.
+    fn write(&mut self, pixels: &PixelBuffer) -> std::io::Result<()> {
+        let stride = self.width as usize * 4;
+        let mut rows = Vec::new();
+        for rect in self.pending.drain(..) {
+            let rect = PixelRect {
+                x: 0,
+                width: self.width,
+                ..rect
+            };
+            rows.resize(rect.height as usize * stride, 0);
+            pixels.copy_bgra(rect, &mut rows, stride, false);
+            self.file
+                .write_all_at(&rows, rect.y as u64 * stride as u64)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ShmBuffer {
+    fn drop(&mut self) {
+        self.buffer.destroy();
+    }
+}
+
+impl SurfaceBuffers {
+    /// Show the `rects` of `pixels` on `surface`.
+    ///
+    /// `buffer_scale` is the integer scale of the surface, which must divide the size of
+    /// `pixels`.
+    pub(super) fn present(
+        &mut self,
+        shm: &wl_shm::WlShm,
+        qh: &QueueHandle<WaylandState>,
+        surface: &wl_surface::WlSurface,
+        pixels: &PixelBuffer,
+        rects: &[PixelRect],
+        buffer_scale: i32,
+    ) -> std::io::Result<()> {
+        let (width, height) = (pixels.width(), pixels.height());
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        self.buffers
+            .retain(|buffer| buffer.width == width && buffer.height == height);
+        for buffer in &mut self.buffers {
+            buffer.pending.extend_from_slice(rects);
+        }
+
+        let index = match self.buffers.iter().position(ShmBuffer::is_released) {
+            Some(index) => index,
+            None => {
+                self.buffers.push(ShmBuffer::new(shm, qh, width, height)?);
+                self.buffers.len() - 1
+            }
+        };
+        let buffer = &mut self.buffers[index];
+        buffer.write(pixels)?;
+        buffer.set_released(false);
+
+        surface.attach(Some(&buffer.buffer), 0, 0);
+        surface.set_buffer_scale(buffer_scale);
+        for rect in rects {
+            if surface.version() >= 4 {
+                surface.damage_buffer(
+                    rect.x as i32,
+                    rect.y as i32,
+                    rect.width as i32,
+                    rect.height as i32,
+                );
+            } else {
+                // Older surfaces take the damage in surface coordinates.
+                let damage = Rect::new(
+                    rect.x as f64,
+                    rect.y as f64,
+                    (rect.x + rect.width) as f64,
+                    (rect.y + rect.height) as f64,
+                )
+                .scale_from_origin(1.0 / buffer_scale as f64)
+                .expand();
+                surface.damage(
+                    damage.x0 as i32,
+                    damage.y0 as i32,
+                    damage.width() as i32,
+                    damage.height() as i32,
+                );
+            }
+        }
+        surface.commit();
+        Ok(())
+    }
+}
+
+/// Convert RGBA pixels to the premultiplied, little endian, ARGB of `wl_shm::Format::Argb8888`.
+fn to_argb8888(rgba: &[u8]) -> Vec<u8> {
+    let premultiply = |c: u8, a: u8| ((c as u16 * a as u16 + 127) / 255) as u8;
+    rgba.chunks_exact(4)
+        .flat_map(|px| {
+            let [r, g, b, a] = [px[0], px[1], px[2], px[3]];
+            [premultiply(b, a), premultiply(g, a), premultiply(r, a), a]
+        })
+        .collect()
+}
+
+impl Dispatch<wl_shm::WlShm, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &wl_shm::WlShm,
+        _: wl_shm::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // We only use Argb8888, which every compositor must support.
+    }
+}
+
+impl Dispatch<wl_shm_pool::WlShmPool, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &wl_shm_pool::WlShmPool,
+        _: wl_shm_pool::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // wl_shm_pool has no events.
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &wl_buffer::WlBuffer,
+        _: wl_buffer::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Our buffers are never changed after creation, so we don't need to know when the
+        // compositor is done reading them. They are destroyed by their owner.
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, ShmBufferData> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &wl_buffer::WlBuffer,
+        event: wl_buffer::Event,
+        data: &ShmBufferData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_buffer::Event::Release = event {
+            data.released.store(true, Ordering::Release);
+        }
+    }
+}
@@ -22,12 +22,20 @@ use raw_window_handle::{
     HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
     WaylandDisplayHandle, WaylandWindowHandle,
 };
-use smithay_client_toolkit::compositor::CompositorHandler;
+use smithay_client_toolkit::compositor::{CompositorHandler, CompositorState, SurfaceData};
+use smithay_client_toolkit::globals::GlobalData;
 use smithay_client_toolkit::reexports::calloop::timer::{TimeoutAction, Timer};
 use smithay_client_toolkit::reexports::calloop::{channel, LoopHandle};
 use smithay_client_toolkit::reexports::client::protocol::wl_compositor::WlCompositor;
-use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
-use smithay_client_toolkit::reexports::client::{protocol, Connection, Proxy, QueueHandle};
+use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
+use smithay_client_toolkit::reexports::client::protocol::wl_region::{self, WlRegion};
+use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
+use smithay_client_toolkit::reexports::client::protocol::wl_shm::WlShm;
+use smithay_client_toolkit::reexports::client::protocol::wl_surface::{self, WlSurface};
+use smithay_client_toolkit::reexports::client::{
+    protocol, Connection, Dispatch, Proxy, QueueHandle,
+};
+use smithay_client_toolkit::reexports::protocols::xdg::shell::client::xdg_toplevel;
 use smithay_client_toolkit::shell::xdg::window::{
     DecorationMode, Window, WindowConfigure, WindowDecorations, WindowHandler,
 };
@@ -38,23 +46,30 @@ use tracing;
 use wayland_backend::client::ObjectId;
 
 use super::application::{self};
+use super::icon::protocol::xdg_toplevel_icon_manager_v1::XdgToplevelIconManagerV1;
 use super::input::{
-    input_state, SeatName, TextFieldChange, TextInputCell, TextInputProperties, WeakTextInputCell,
+    input_state, DragRequest, SeatName, TextFieldChange, TextInputCell, TextInputProperties,
+    WeakTextInputCell,
 };
 use super::menu::Menu;
+use super::shm::{self, SurfaceBuffers};
 use super::{ActiveAction, IdleAction, WaylandState};
 
 use crate::{
+    clipboard::ClipboardFormat,
     dialog::FileDialogOptions,
+    dnd::{DragIcon, DropAction},
     error::Error as ShellError,
+    icon::Icon,
     kurbo::{Insets, Point, Rect, Size},
     mouse::{Cursor, CursorDesc},
     scale::Scale,
+    screen::Monitor,
     text::Event,
-    window::{self, FileDialogToken, TimerToken, WinHandler, WindowLevel},
+    window::{self, FileDialogToken, PointerGrab, ResizeEdge, TimerToken, WinHandler, WindowLevel},
     TextFieldToken,
 };
-use crate::{IdleToken, Region, Scalable};
+use crate::{IdleToken, PixelBuffer, Region, Scalable};
 
 #[derive(Clone)]
 pub struct WindowHandle {
@@ -146,27 +161,77 @@ impl WindowHandle {
         props.current_size
     }
 
-    pub fn set_window_state(&mut self, state: window::WindowState) {
+    // The size limits take effect on the next commit, when the window is next painted.
+    pub fn set_min_size(&self, size: Size) {
         let props = self.properties();
         let props = props.borrow();
-        match state {
-            crate::WindowState::Maximized => props.wayland_window.set_maximized(),
-            crate::WindowState::Minimized => props.wayland_window.set_minimized(),
-            // TODO: I don't think we can do much better than this - we can't unset being minimised
-            crate::WindowState::Restored => props.wayland_window.unset_maximized(),
-        }
+        props.wayland_window.set_min_size(Some(surface_size(size)));
+    }
+
+    pub fn set_max_size(&self, size: Option<Size>) {
+        let props = self.properties();
+        let props = props.borrow();
+        props.wayland_window.set_max_size(size.map(surface_size));
+    }
+
+    pub fn set_resize_increments(&self, _increments: Option<Size>) {
+        tracing::warn!("set_resize_increments is unimplemented on wayland");
+        // TODO: xdg_toplevel has no resize increments, but fallback decorations could follow them
+    }
+
+    pub fn set_aspect_ratio(&self, _ratio: Option<f64>) {
+        tracing::warn!("set_aspect_ratio is unimplemented on wayland");
+        // TODO: xdg_toplevel has no aspect ratio, but fallback decorations could follow it
+    }
+
+    // The regions take effect on the next commit, when the window is next painted.
+    pub fn set_input_region(&self, region: Option<Region>) {
+        self.defer(WindowAction::SetInputRegion(region));
+    }
+
+    pub fn set_opaque_region(&self, region: Option<Region>) {
+        self.defer(WindowAction::SetOpaqueRegion(region));
+    }
+
+    pub fn set_window_state(&mut self, state: window::WindowState) {
+        // The output for fullscreen windows is only known on the event loop
+        self.defer(WindowAction::SetWindowState(state));
     }
 
     pub fn get_window_state(&self) -> window::WindowState {
+        let props = self.properties();
+        let props = props.borrow();
         // We can know if we're maximised or restored, but not if minimised
-        tracing::warn!("get_window_state is unimplemented on wayland");
-        window::WindowState::Maximized
+        match &props.configure {
+            Some(configure) if configure.is_fullscreen() => {
+                window::WindowState::Fullscreen(props.fullscreen_monitor)
+            }
+            Some(configure) if configure.is_maximized() => window::WindowState::Maximized,
+            _ => window::WindowState::Restored,
+        }
     }
 
     pub fn handle_titlebar(&self, _val: bool) {
         tracing::warn!("handle_titlebar is unimplemented on wayland");
     }
 
+    pub fn begin_move_drag(&self) {
+        self.defer(WindowAction::BeginMove);
+    }
+
+    pub fn begin_resize_drag(&self, edge: ResizeEdge) {
+        self.defer(WindowAction::BeginResize(edge));
+    }
+
+    pub fn show_window_menu(&self, pos: Point) {
+        // Surface local coordinates are already in display points
+        self.defer(WindowAction::ShowWindowMenu(pos));
+    }
+
+    pub fn set_pointer_grab(&self, grab: PointerGrab) {
+        self.defer(WindowAction::SetPointerGrab(grab));
+    }
+
     /// Close the window.
     pub fn close(&self) {
         self.defer(WindowAction::Close)
@@ -199,6 +264,11 @@ impl WindowHandle {
         todo!()
     }
 
+    pub fn present_pixels(&self, buffer: &PixelBuffer, damage: Option<&Region>) {
+        let props = self.properties();
+        props.borrow_mut().present_pixels(buffer, damage);
+    }
+
     pub fn add_text_field(&self) -> TextFieldToken {
         TextFieldToken::next()
     }
@@ -268,13 +338,16 @@ impl WindowHandle {
         token
     }
 
-    pub fn set_cursor(&mut self, _cursor: &Cursor) {
-        tracing::warn!("unimplemented set_cursor called")
+    pub fn set_cursor(&mut self, cursor: &Cursor) {
+        let props = self.properties();
+        props.borrow_mut().cursor = cursor.clone();
+        self.defer(WindowAction::SetCursor);
     }
 
-    pub fn make_cursor(&self, _desc: &CursorDesc) -> Option<Cursor> {
-        tracing::warn!("unimplemented make_cursor initiated");
-        None
+    pub fn make_cursor(&self, desc: &CursorDesc) -> Option<Cursor> {
+        Some(Cursor::Custom(
+            crate::backend::window::CustomCursor::Wayland(CustomCursor(Rc::new(desc.clone()))),
+        ))
     }
 
     pub fn open_file(&mut self, _options: FileDialogOptions) -> Option<FileDialogToken> {
@@ -287,6 +360,24 @@ impl WindowHandle {
         None
     }
 
+    pub fn set_drop_target(&self, enabled: bool) {
+        let props = self.properties();
+        props.borrow_mut().drop_target = enabled;
+    }
+
+    pub fn start_drag(
+        &self,
+        formats: &[ClipboardFormat],
+        icon: Option<DragIcon>,
+        actions: &[DropAction],
+    ) {
+        self.defer(WindowAction::StartDrag(DragRequest {
+            formats: formats.to_vec(),
+            icon,
+            actions: actions.to_vec(),
+        }));
+    }
+
     /// Get a handle that can be used to schedule an idle task.
     pub fn get_idle_handle(&self) -> Option<IdleHandle> {
         Some(IdleHandle {
@@ -316,6 +407,15 @@ impl WindowHandle {
         props.wayland_window.set_title(title)
     }
 
+    /// Set the icon of the window.
+    ///
+    /// This does nothing if the compositor doesn't support the xdg-toplevel-icon protocol.
+    pub fn set_icon(&self, icon: Option<&Icon>) {
+        let props = self.properties();
+        props.borrow_mut().icon = icon.cloned();
+        self.defer(WindowAction::SetIcon);
+    }
+
     #[cfg(feature = "accesskit")]
     pub fn update_accesskit_if_active(
         &self,
@@ -415,14 +515,24 @@ impl IdleHandle {
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
-pub struct CustomCursor;
+/// A cursor made from a [`CursorDesc`].
+#[derive(Clone)]
+pub struct CustomCursor(pub(super) Rc<CursorDesc>);
+
+impl PartialEq for CustomCursor {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CustomCursor {}
 
 /// Builder abstraction for creating new windows
 pub(crate) struct WindowBuilder {
     handler: Option<Box<dyn WinHandler>>,
     title: String,
     menu: Option<Menu>,
+    icon: Option<Icon>,
     position: Option<Point>,
     level: WindowLevel,
     state: Option<window::WindowState>,
@@ -432,6 +542,7 @@ pub(crate) struct WindowBuilder {
     resizable: bool,
     show_titlebar: bool,
     compositor: WlCompositor,
+    shm: WlShm,
     wayland_queue: QueueHandle<WaylandState>,
     loop_handle: LoopHandle<'static, WaylandState>,
     xdg_state: Weak<XdgShell>,
@@ -446,6 +557,7 @@ impl WindowBuilder {
             handler: None,
             title: String::new(),
             menu: None,
+            icon: None,
             size: None,
             position: None,
             level: WindowLevel::AppWindow,
@@ -454,6 +566,7 @@ impl WindowBuilder {
             resizable: true,
             show_titlebar: true,
             compositor: app.compositor,
+            shm: app.shm,
             wayland_queue: app.wayland_queue,
             loop_handle: app.loop_handle,
             xdg_state: app.xdg_shell,
@@ -515,6 +628,11 @@ impl WindowBuilder {
         self
     }
 
+    pub fn icon(mut self, icon: Icon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
     pub fn menu(mut self, menu: Menu) -> Self {
         self.menu = Some(menu);
         self
@@ -537,9 +655,17 @@ impl WindowBuilder {
         wayland_window.set_title(self.title);
         // TODO: Pass this down
         wayland_window.set_app_id("org.linebender.glazier.user_app");
-        // TODO: Convert properly, set all properties
-        // wayland_window.set_min_size(self.min_size);
+        wayland_window.set_min_size(self.min_size.map(surface_size));
         let window_id = WindowId::new(&wayland_window);
+        let modal_parent = match &self.level {
+            WindowLevel::Modal(parent) | WindowLevel::Utility(parent) => {
+                let parent = parent.0.unwrap_wayland();
+                wayland_window.set_parent(Some(&parent.properties().borrow().wayland_window));
+                matches!(self.level, WindowLevel::Modal(_)).then(|| parent.id())
+            }
+            _ => None,
+        };
+        let has_icon = self.icon.is_some();
         let properties = WindowProperties {
             configure: None,
             requested_size: self.size,
@@ -552,6 +678,12 @@ impl WindowBuilder {
             will_repaint: false,
             pending_frame_callback: false,
             configured: false,
+            drop_target: false,
+            cursor: Cursor::Arrow,
+            icon: self.icon,
+            fullscreen_monitor: None,
+            shm: self.shm,
+            buffers: SurfaceBuffers::default(),
         };
         let properties_strong = Rc::new(RefCell::new(properties));
 
@@ -579,11 +711,20 @@ impl WindowBuilder {
                         properties: properties_strong,
                         text_input_seat: None,
                         text,
+                        outputs: Vec::new(),
+                        modal_parent,
+                        modal_children: 0,
                     },
                     handle.clone(),
                 ),
             ))
             .expect("Event loop should still be valid");
+        if has_icon {
+            handle.defer(WindowAction::SetIcon);
+        }
+        if let Some(state) = self.state {
+            handle.defer(WindowAction::SetWindowState(state));
+        }
 
         Ok(handle)
     }
@@ -603,6 +744,24 @@ impl WindowId {
     }
 }
 
+fn resize_edge(edge: ResizeEdge) -> xdg_toplevel::ResizeEdge {
+    match edge {
+        ResizeEdge::Top => xdg_toplevel::ResizeEdge::Top,
+        ResizeEdge::Bottom => xdg_toplevel::ResizeEdge::Bottom,
+        ResizeEdge::Left => xdg_toplevel::ResizeEdge::Left,
+        ResizeEdge::Right => xdg_toplevel::ResizeEdge::Right,
+        ResizeEdge::TopLeft => xdg_toplevel::ResizeEdge::TopLeft,
+        ResizeEdge::TopRight => xdg_toplevel::ResizeEdge::TopRight,
+        ResizeEdge::BottomLeft => xdg_toplevel::ResizeEdge::BottomLeft,
+        ResizeEdge::BottomRight => xdg_toplevel::ResizeEdge::BottomRight,
+    }
+}
+
+/// A size in display points, as the surface-local size which xdg_toplevel expects.
+fn surface_size(size: Size) -> (u32, u32) {
+    (size.width.round() as u32, size.height.round() as u32)
+}
+
 /// The state associated with each window, stored in [`WaylandState`]
 pub(super) struct WaylandWindowState {
     // Drop the window handler before the properties
@@ -612,6 +771,12 @@ pub(super) struct WaylandWindowState {
     properties: Rc<RefCell<WindowProperties>>,
     text_input_seat: Option<SeatName>,
     pub text: TextInputCell,
+    /// The outputs which show the window's surface, in the order that it entered them.
+    outputs: Vec<WlOutput>,
+    /// The window whose input this modal window blocks
+    modal_parent: Option<WindowId>,
+    /// The number of open modal windows of this window
+    modal_children: usize,
 }
 
 struct WindowProperties {
@@ -644,6 +809,16 @@ struct WindowProperties {
     pending_frame_callback: bool,
     // We can't draw before being configured
     configured: bool,
+    /// Whether the handler is told about drags over the window
+    drop_target: bool,
+    /// The cursor shown while the pointer is over the window
+    cursor: Cursor,
+    icon: Option<Icon>,
+    /// The monitor that was requested for the last `WindowState::Fullscreen`
+    fullscreen_monitor: Option<Monitor>,
+    shm: WlShm,
+    /// The buffers used by `present_pixels`
+    buffers: SurfaceBuffers,
 }
 
 impl WindowProperties {
@@ -692,6 +867,72 @@ impl WindowProperties {
         self.current_size = new_size_absolute.to_dp(self.current_scale);
         self.current_size
     }
+
+    /// Set the icon of the toplevel to `self.icon`.
+    fn send_icon(&self, manager: &XdgToplevelIconManagerV1) {
+        let toplevel = self.wayland_window.xdg_toplevel();
+        let Some(icon) = &self.icon else {
+            manager.set_icon(toplevel, None);
+            self.wayland_window.wl_surface().commit();
+            return;
+        };
+        let wl_icon = manager.create_icon(&self.wayland_queue, ());
+        let mut buffers = vec![];
+        for image in icon.images() {
+            if image.width != image.height {
+                tracing::warn!(
+                    "Skipping a {}x{} window icon, as Wayland only supports square icons",
+                    image.width,
+                    image.height
+                );
+                continue;
+            }
+            match shm::buffer_from_rgba(
+                &self.shm,
+                &self.wayland_queue,
+                image.width,
+                image.height,
+                &image.rgba,
+            ) {
+                Ok(buffer) => {
+                    wl_icon.add_buffer(&buffer, 1);
+                    buffers.push(buffer);
+                }
+                Err(err) => tracing::error!("Failed to create the window icon: {}", err),
+            }
+        }
+        manager.set_icon(toplevel, Some(&wl_icon));
+        self.wayland_window.wl_surface().commit();
+        // The compositor keeps the icon after it is set, so nothing needs to be kept alive.
+        wl_icon.destroy();
+        for buffer in buffers {
+            buffer.destroy();
+        }
+    }
+
+    fn present_pixels(&mut self, pixels: &PixelBuffer, damage: Option<&Region>) {
+        // The scale is always an integer, as it comes from `scale_factor_changed`
+        let buffer_scale = self.current_scale.x() as u32;
+        if pixels.width() % buffer_scale != 0 || pixels.height() % buffer_scale != 0 {
+            tracing::error!(
+                "The size of the pixel buffer must be a multiple of the window's scale, {}",
+                buffer_scale
+            );
+            return;
+        }
+        let rects = pixels.damage_rects(damage, self.current_scale);
+        let surface = self.wayland_window.wl_surface();
+        if let Err(err) = self.buffers.present(
+            &self.shm,
+            &self.wayland_queue,
+            surface,
+            pixels,
+            &rects,
+            buffer_scale as i32,
+        ) {
+            tracing::error!("Failed to present pixels: {}", err);
+        }
+    }
 }
 
 /// The context do_paint is called in
@@ -703,6 +944,11 @@ enum PaintContext {
 }
 
 impl WaylandWindowState {
+    /// Whether a modal window blocks the input to this window.
+    pub(super) fn has_modal_child(&self) -> bool {
+        self.modal_children > 0
+    }
+
     fn do_paint(&mut self, force: bool, context: PaintContext) {
         {
             let mut props = self.properties.borrow_mut();
@@ -740,6 +986,22 @@ impl WaylandWindowState {
         self.handler.paint(&region);
     }
 
+    pub(super) fn surface(&self) -> WlSurface {
+        self.properties.borrow().wayland_window.wl_surface().clone()
+    }
+
+    pub(super) fn scale(&self) -> Scale {
+        self.properties.borrow().current_scale
+    }
+
+    pub(super) fn is_drop_target(&self) -> bool {
+        self.properties.borrow().drop_target
+    }
+
+    pub(super) fn cursor(&self) -> Cursor {
+        self.properties.borrow().cursor.clone()
+    }
+
     pub(super) fn set_input_seat(&mut self, seat: SeatName) {
         assert!(self.text_input_seat.is_none());
         self.text_input_seat = Some(seat);
@@ -750,10 +1012,114 @@ impl WaylandWindowState {
     }
 }
 
+impl WaylandState {
+    /// Create a `wl_region` from a region in surface local coordinates, which are display points.
+    ///
+    /// The surface copies the region when it is set, so it can be destroyed right after.
+    pub(super) fn create_region(&self, region: &Region, round: impl Fn(Rect) -> Rect) -> WlRegion {
+        let wl_region = self
+            .compositor_state
+            .wl_compositor()
+            .create_region(&self.wayland_queue, GlobalData);
+        for &rect in region.rects() {
+            let rect = round(rect);
+            wl_region.add(
+                rect.x0 as i32,
+                rect.y0 as i32,
+                rect.width() as i32,
+                rect.height() as i32,
+            );
+        }
+        wl_region
+    }
+}
+
 delegate_xdg_shell!(WaylandState);
 delegate_xdg_window!(WaylandState);
 
-delegate_compositor!(WaylandState);
+// The surfaces are dispatched below.
+delegate_compositor!(WaylandState, surface: []);
+
+// Regions don't have any events.
+impl Dispatch<WlRegion, GlobalData> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &WlRegion,
+        _: wl_region::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+// The surface events are forwarded to client-toolkit by hand, as it doesn't tell us which outputs
+// a surface has entered.
+impl Dispatch<WlSurface, SurfaceData> for WaylandState {
+    fn event(
+        state: &mut Self,
+        surface: &WlSurface,
+        event: wl_surface::Event,
+        data: &SurfaceData,
+        conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        let output_change = match &event {
+            wl_surface::Event::Enter { output } => Some((output.clone(), true)),
+            wl_surface::Event::Leave { output } => Some((output.clone(), false)),
+            _ => None,
+        };
+        <CompositorState as Dispatch<WlSurface, SurfaceData, Self>>::event(
+            state, surface, event, data, conn, qh,
+        );
+        if let Some((output, entered)) = output_change {
+            state.surface_output_changed(surface, output, entered);
+        }
+    }
+}
+
+impl WaylandState {
+    /// Runs `f` with the window's toplevel, and the seat and serial of the latest button press
+    /// in the window, for the requests which need the implicit grab of the press.
+    fn with_press_grab(
+        &self,
+        window_id: &WindowId,
+        f: impl FnOnce(&xdg_toplevel::XdgToplevel, &WlSeat, u32),
+    ) {
+        let Some((seat, serial)) = self.latest_press(window_id) else {
+            tracing::warn!("no button was pressed in the window, the compositor can't take over");
+            return;
+        };
+        let Some(window) = self.windows.get(window_id) else {
+            return;
+        };
+        let props = window.properties.borrow();
+        f(props.wayland_window.xdg_toplevel(), &seat, serial);
+    }
+
+    fn surface_output_changed(&mut self, surface: &WlSurface, output: WlOutput, entered: bool) {
+        let window_id = WindowId::of_surface(surface);
+        // Drag icons are surfaces too, but they don't have a handler to tell
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        let previous = window.outputs.last().cloned();
+        window.outputs.retain(|it| *it != output);
+        if entered {
+            window.outputs.push(output);
+        }
+        let current = window.outputs.last().cloned();
+        if current == previous {
+            return;
+        }
+        // Wayland doesn't tell us how much of the surface each output shows, so the output the
+        // surface entered last is reported.
+        let monitor = current.and_then(|output| self.monitor_for_output(&output));
+        if let Some(window) = self.windows.get_mut(&window_id) {
+            window.handler.monitor(monitor);
+        }
+    }
+}
 
 impl CompositorHandler for WaylandState {
     fn scale_factor_changed(
@@ -765,8 +1131,10 @@ impl CompositorHandler for WaylandState {
         // This requires an update in client-toolkit and wayland-protocols
         new_factor: i32,
     ) {
-        let window = self.windows.get_mut(&WindowId::of_surface(surface));
-        let window = window.expect("Should only get events for real windows");
+        // Drag icons are surfaces too, but we don't need to know their scale
+        let Some(window) = self.windows.get_mut(&WindowId::of_surface(surface)) else {
+            return;
+        };
         let factor = f64::from(new_factor);
         let scale = Scale::new(factor, factor);
         let new_size;
@@ -828,6 +1196,8 @@ impl WindowHandler for WaylandState {
             return;
         };
         // TODO: Actually use the suggestions from requested_size
+        // TODO: Report the suspended state through `WinHandler::visibility`
+        // This requires an update in client-toolkit and wayland-protocols (xdg-shell version 6)
         let display_size;
         {
             let mut props = window.properties.borrow_mut();
@@ -850,6 +1220,24 @@ pub(super) enum WindowAction {
     Create(WaylandWindowState, WindowHandle),
     AnimationRequested,
     TextField(TextFieldChange),
+    StartDrag(DragRequest),
+    /// Show the window's cursor for the pointers which are over it
+    SetCursor,
+    /// Send the window's icon to the compositor
+    SetIcon,
+    SetWindowState(window::WindowState),
+    /// Let the user move the window, with the latest button press
+    BeginMove,
+    /// Let the user resize the window from an edge, with the latest button press
+    BeginResize(ResizeEdge),
+    /// Show the compositor's window menu at a position in the window
+    ShowWindowMenu(Point),
+    /// Set the part of the surface which receives pointer input
+    SetInputRegion(Option<Region>),
+    /// Tell the compositor which part of the surface is opaque
+    SetOpaqueRegion(Option<Region>),
+    /// Lock or confine the pointers of every seat to the window
+    SetPointerGrab(PointerGrab),
 }
 
 impl WindowAction {
@@ -870,12 +1258,21 @@ impl WindowAction {
                 window.do_paint(true, PaintContext::Requested);
             }
             WindowAction::Close => {
+                // The constraints on the pointers can't outlive the surface
+                state.set_pointer_grab(&window_id, PointerGrab::None);
                 // Remove the window from tracking
                 {
                     let Some(win) = state.windows.remove(&window_id) else {
                     tracing::error!("Tried to close the same window twice");
                     return;
                     };
+                    if let Some(parent) = win
+                        .modal_parent
+                        .as_ref()
+                        .and_then(|parent| state.windows.get_mut(parent))
+                    {
+                        parent.modal_children -= 1;
+                    }
                     if let Some(seat) = win.text_input_seat {
                         let seat = input_state(&mut state.input_states, seat);
                         seat.window_deleted(&mut state.windows);
@@ -887,6 +1284,13 @@ impl WindowAction {
                 }
             }
             WindowAction::Create(win_state, handle) => {
+                if let Some(parent) = win_state
+                    .modal_parent
+                    .as_ref()
+                    .and_then(|parent| state.windows.get_mut(parent))
+                {
+                    parent.modal_children += 1;
+                }
                 let res = state.windows.entry(window_id);
                 let win_state = res.or_insert(win_state);
                 win_state.handler.connect(&crate::WindowHandle(
@@ -912,6 +1316,86 @@ impl WindowAction {
                     &window_id,
                 );
             }
+            WindowAction::StartDrag(request) => state.start_drag(&window_id, request),
+            WindowAction::BeginMove => {
+                state.with_press_grab(&window_id, |toplevel, seat, serial| {
+                    toplevel._move(seat, serial)
+                });
+            }
+            WindowAction::BeginResize(edge) => {
+                state.with_press_grab(&window_id, |toplevel, seat, serial| {
+                    toplevel.resize(seat, serial, resize_edge(edge))
+                });
+            }
+            WindowAction::ShowWindowMenu(pos) => {
+                state.with_press_grab(&window_id, |toplevel, seat, serial| {
+                    toplevel.show_window_menu(seat, serial, pos.x as i32, pos.y as i32)
+                });
+            }
+            WindowAction::SetCursor => state.update_cursors(&window_id),
+            WindowAction::SetIcon => {
+                let Some(manager) = &state.toplevel_icon_manager else {
+                    tracing::debug!("The compositor doesn't support window icons");
+                    return;
+                };
+                let Some(window) = state.windows.get(&window_id) else {
+                    return;
+                };
+                window.properties.borrow().send_icon(manager);
+            }
+            WindowAction::SetWindowState(window_state) => {
+                let Some(window) = state.windows.get(&window_id) else {
+                    return;
+                };
+                let mut props = window.properties.borrow_mut();
+                let wayland_window = &props.wayland_window;
+                match window_state {
+                    window::WindowState::Maximized => {
+                        wayland_window.unset_fullscreen();
+                        wayland_window.set_maximized();
+                    }
+                    window::WindowState::Minimized => wayland_window.set_minimized(),
+                    // TODO: We can't do much better than this - we can't unset being minimised
+                    window::WindowState::Restored => {
+                        wayland_window.unset_fullscreen();
+                        wayland_window.unset_maximized();
+                    }
+                    window::WindowState::Fullscreen(monitor) => {
+                        let output = monitor.and_then(|monitor| state.output_for_monitor(&monitor));
+                        if monitor.is_some() && output.is_none() {
+                            tracing::warn!(
+                                "No output matches {:?}, the compositor picks one",
+                                monitor
+                            );
+                        }
+                        wayland_window.set_fullscreen(output.as_ref());
+                        props.fullscreen_monitor = monitor;
+                    }
+                }
+            }
+            WindowAction::SetPointerGrab(grab) => state.set_pointer_grab(&window_id, grab),
+            WindowAction::SetInputRegion(region) => {
+                let Some(window) = state.windows.get(&window_id) else {
+                    return;
+                };
+                // Rounding outwards, so that no input is lost at the edges
+                let wl_region = region.map(|region| state.create_region(&region, Rect::expand));
+                window.surface().set_input_region(wl_region.as_ref());
+                if let Some(wl_region) = wl_region {
+                    wl_region.destroy();
+                }
+            }
+            WindowAction::SetOpaqueRegion(region) => {
+                let Some(window) = state.windows.get(&window_id) else {
+                    return;
+                };
+                // Rounding inwards, so that no transparent pixel is claimed to be opaque
+                let wl_region = region.map(|region| state.create_region(&region, Rect::trunc));
+                window.surface().set_opaque_region(wl_region.as_ref());
+                if let Some(wl_region) = wl_region {
+                    wl_region.destroy();
+                }
+            }
         }
     }
 }
@@ -0,0 +1,41 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Drag and drop is not yet supported in the browser.
+
+use crate::clipboard::FormatId;
+
+/// The data offered by the source of a drag.
+///
+/// Drops are never received in the browser, so this can't be constructed.
+#[derive(Debug, Clone)]
+pub enum DropData {}
+
+impl DropData {
+    pub fn get_string(&self) -> Option<String> {
+        match *self {}
+    }
+
+    pub fn preferred_format(&self, _formats: &[FormatId]) -> Option<FormatId> {
+        match *self {}
+    }
+
+    pub fn get_format(&self, _format: FormatId) -> Option<Vec<u8>> {
+        match *self {}
+    }
+
+    pub fn available_type_names(&self) -> Vec<String> {
+        match *self {}
+    }
+}
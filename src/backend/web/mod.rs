@@ -16,6 +16,7 @@
 
 pub mod application;
 pub mod clipboard;
+pub mod dnd;
 pub mod error;
 pub mod keycodes;
 pub mod menu;
@@ -18,6 +18,7 @@ use std::cell::{Cell, RefCell};
 use std::ffi::OsString;
 use std::rc::{Rc, Weak};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use instant::Instant;
 use tracing::{error, warn};
@@ -34,15 +35,20 @@ use super::application::Application;
 use super::error::Error;
 use super::keycodes::convert_keyboard_event;
 use super::menu::Menu;
-use crate::common_util::{ClickCounter, IdleCallback};
+use crate::clipboard::ClipboardFormat;
+use crate::common_util::{instant_from_age, ClickCounter, IdleCallback};
 use crate::dialog::{FileDialogOptions, FileDialogType};
+use crate::dnd::{DragIcon, DropAction};
 use crate::error::Error as ShellError;
+use crate::icon::Icon;
 use crate::scale::{Scale, ScaledArea};
 
 use crate::keyboard::{KeyState, Modifiers};
 use crate::mouse::{Cursor, CursorDesc};
+use crate::pixels::PixelBuffer;
 use crate::pointer::{
-    MouseInfo, PointerButton, PointerButtons, PointerEvent, PointerId, PointerType,
+    MouseInfo, PointerButton, PointerButtons, PointerEvent, PointerId, PointerType, ScrollInfo,
+    ScrollUnit,
 };
 use crate::region::Region;
 use crate::text::{simulate_input, Event};
@@ -205,15 +211,15 @@ fn setup_mouse_down_callback(ws: &Rc<WindowState>) {
             let event = PointerEvent {
                 pointer_id: PointerId(0),
                 is_primary: true,
-                pointer_type: PointerType::Mouse(MouseInfo {
-                    wheel_delta: Vec2::ZERO,
-                }),
+                pointer_type: PointerType::Mouse(MouseInfo::default()),
                 pos,
                 buttons: get_buttons(event.buttons()),
                 modifiers: get_modifiers!(event),
                 button,
                 focus: false,
                 count,
+                time: event_time(&event),
+                coalesced: Vec::new(),
             };
             state.handler.borrow_mut().pointer_down(event);
         }
@@ -227,15 +233,15 @@ fn setup_mouse_up_callback(ws: &Rc<WindowState>) {
             let event = PointerEvent {
                 pointer_id: PointerId(0),
                 is_primary: true,
-                pointer_type: PointerType::Mouse(MouseInfo {
-                    wheel_delta: Vec2::ZERO,
-                }),
+                pointer_type: PointerType::Mouse(MouseInfo::default()),
                 pos: Point::new(event.offset_x() as f64, event.offset_y() as f64),
                 buttons: get_buttons(event.buttons()),
                 modifiers: get_modifiers!(event),
                 button,
                 focus: false,
                 count: 0,
+                time: event_time(&event),
+                coalesced: Vec::new(),
             };
             state.handler.borrow_mut().pointer_up(event);
         }
@@ -248,20 +254,40 @@ fn setup_mouse_move_callback(ws: &Rc<WindowState>) {
         let event = PointerEvent {
             pointer_id: PointerId(0),
             is_primary: true,
-            pointer_type: PointerType::Mouse(MouseInfo {
-                wheel_delta: Vec2::ZERO,
-            }),
+            pointer_type: PointerType::Mouse(MouseInfo::default()),
             pos: Point::new(event.offset_x() as f64, event.offset_y() as f64),
             buttons: get_buttons(event.buttons()),
             modifiers: get_modifiers!(event),
             button: PointerButton::None,
             focus: false,
             count: 0,
+            time: event_time(&event),
+            coalesced: Vec::new(),
         };
         state.handler.borrow_mut().pointer_move(event);
     });
 }
 
+fn setup_mouse_enter_callback(ws: &Rc<WindowState>) {
+    let state = ws.clone();
+    register_canvas_event_listener(ws, "mouseenter", move |event: web_sys::MouseEvent| {
+        let event = PointerEvent {
+            pointer_id: PointerId(0),
+            is_primary: true,
+            pointer_type: PointerType::Mouse(MouseInfo::default()),
+            pos: Point::new(event.offset_x() as f64, event.offset_y() as f64),
+            buttons: get_buttons(event.buttons()),
+            modifiers: get_modifiers!(event),
+            button: PointerButton::None,
+            focus: false,
+            count: 0,
+            time: event_time(&event),
+            coalesced: Vec::new(),
+        };
+        state.handler.borrow_mut().pointer_enter(event);
+    });
+}
+
 fn setup_scroll_callback(ws: &Rc<WindowState>) {
     let state = ws.clone();
     register_canvas_event_listener(ws, "wheel", move |event: web_sys::WheelEvent| {
@@ -271,29 +297,43 @@ fn setup_scroll_callback(ws: &Rc<WindowState>) {
         let dy = event.delta_y();
 
         // The value 35.0 was manually picked to produce similar behavior to mac/linux.
-        let wheel_delta = match delta_mode {
-            web_sys::WheelEvent::DOM_DELTA_PIXEL => Vec2::new(dx, dy),
-            web_sys::WheelEvent::DOM_DELTA_LINE => Vec2::new(35.0 * dx, 35.0 * dy),
+        let (wheel_delta, unit) = match delta_mode {
+            web_sys::WheelEvent::DOM_DELTA_PIXEL => (Vec2::new(dx, dy), ScrollUnit::Pixels),
+            web_sys::WheelEvent::DOM_DELTA_LINE => {
+                (Vec2::new(35.0 * dx, 35.0 * dy), ScrollUnit::Lines)
+            }
             web_sys::WheelEvent::DOM_DELTA_PAGE => {
                 let size_dp = state.area.get().size_dp();
-                Vec2::new(size_dp.width * dx, size_dp.height * dy)
+                let delta = Vec2::new(size_dp.width * dx, size_dp.height * dy);
+                (delta, ScrollUnit::Pages)
             }
             _ => {
                 warn!("Invalid deltaMode in WheelEvent: {}", delta_mode);
                 return;
             }
         };
+        // Browsers don't report the source or phase of the scroll.
+        let scroll = ScrollInfo {
+            delta: Vec2::new(dx, dy),
+            unit,
+            ..ScrollInfo::default()
+        };
 
         let event = PointerEvent {
             pointer_id: PointerId(0),
             is_primary: true,
-            pointer_type: PointerType::Mouse(MouseInfo { wheel_delta }),
+            pointer_type: PointerType::Mouse(MouseInfo {
+                wheel_delta,
+                scroll,
+            }),
             pos: Point::new(event.offset_x() as f64, event.offset_y() as f64),
             buttons: get_buttons(event.buttons()),
             modifiers: get_modifiers!(event),
             button: PointerButton::None,
             focus: false,
             count: 0,
+            time: event_time(&event),
+            coalesced: Vec::new(),
         };
         state.handler.borrow_mut().wheel(event);
     });
@@ -361,6 +401,7 @@ where
 fn setup_web_callbacks(window_state: &Rc<WindowState>) {
     setup_mouse_down_callback(window_state);
     setup_mouse_move_callback(window_state);
+    setup_mouse_enter_callback(window_state);
     setup_mouse_up_callback(window_state);
     setup_resize_callback(window_state);
     setup_scroll_callback(window_state);
@@ -429,6 +470,11 @@ impl WindowBuilder {
         self
     }
 
+    pub fn icon(self, _icon: Icon) -> Self {
+        warn!("WindowBuilder::icon unimplemented for web.");
+        self
+    }
+
     pub fn menu(mut self, menu: Menu) -> Self {
         self.menu = Some(menu);
         self
@@ -545,6 +591,30 @@ impl WindowHandle {
         Size::new(0.0, 0.0)
     }
 
+    pub fn set_min_size(&self, _size: Size) {
+        warn!("WindowHandle::set_min_size unimplemented for web.");
+    }
+
+    pub fn set_max_size(&self, _size: Option<Size>) {
+        warn!("WindowHandle::set_max_size unimplemented for web.");
+    }
+
+    pub fn set_resize_increments(&self, _increments: Option<Size>) {
+        warn!("WindowHandle::set_resize_increments unimplemented for web.");
+    }
+
+    pub fn set_aspect_ratio(&self, _ratio: Option<f64>) {
+        warn!("WindowHandle::set_aspect_ratio unimplemented for web.");
+    }
+
+    pub fn set_input_region(&self, _region: Option<Region>) {
+        warn!("WindowHandle::set_input_region unimplemented for web.");
+    }
+
+    pub fn set_opaque_region(&self, _region: Option<Region>) {
+        warn!("WindowHandle::set_opaque_region unimplemented for web.");
+    }
+
     pub fn content_insets(&self) -> Insets {
         warn!("WindowHandle::content_insets unimplemented for web.");
         Insets::ZERO
@@ -563,6 +633,22 @@ impl WindowHandle {
         warn!("WindowHandle::handle_titlebar unimplemented for web.");
     }
 
+    pub fn begin_move_drag(&self) {
+        warn!("WindowHandle::begin_move_drag unimplemented for web.");
+    }
+
+    pub fn begin_resize_drag(&self, _edge: window::ResizeEdge) {
+        warn!("WindowHandle::begin_resize_drag unimplemented for web.");
+    }
+
+    pub fn show_window_menu(&self, _pos: Point) {
+        warn!("WindowHandle::show_window_menu unimplemented for web.");
+    }
+
+    pub fn set_pointer_grab(&self, _grab: window::PointerGrab) {
+        warn!("WindowHandle::set_pointer_grab unimplemented for web.");
+    }
+
     pub fn close(&self) {
         // TODO
     }
@@ -696,6 +782,23 @@ impl WindowHandle {
         Err(ShellError::Platform(Error::Unimplemented))
     }
 
+    pub fn set_drop_target(&self, _enabled: bool) {
+        warn!("WindowHandle::set_drop_target unimplemented for web.");
+    }
+
+    pub fn present_pixels(&self, _buffer: &PixelBuffer, _damage: Option<&Region>) {
+        warn!("WindowHandle::present_pixels unimplemented for web.");
+    }
+
+    pub fn start_drag(
+        &self,
+        _formats: &[ClipboardFormat],
+        _icon: Option<DragIcon>,
+        _actions: &[DropAction],
+    ) {
+        warn!("WindowHandle::start_drag unimplemented for web.");
+    }
+
     /// Get a handle that can be used to schedule an idle task.
     pub fn get_idle_handle(&self) -> Option<IdleHandle> {
         self.0.upgrade().map(|w| IdleHandle {
@@ -728,6 +831,10 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_icon(&self, _icon: Option<&Icon>) {
+        warn!("WindowHandle::set_icon unimplemented for web.");
+    }
+
     #[cfg(feature = "accesskit")]
     pub fn update_accesskit_if_active(
         &self,
@@ -778,6 +885,13 @@ impl IdleHandle {
     }
 }
 
+/// The instant of a DOM event, from its timestamp in milliseconds since the page loaded.
+fn event_time(event: &web_sys::Event) -> Option<Instant> {
+    let now = web_sys::window()?.performance()?.now();
+    let age = (now - event.time_stamp()).max(0.0);
+    Some(instant_from_age(Duration::from_secs_f64(age / 1000.0)))
+}
+
 fn get_button(button: i16) -> Option<PointerButton> {
     match button {
         0 => Some(PointerButton::Primary),
@@ -820,10 +934,35 @@ fn set_cursor(canvas: &web_sys::HtmlCanvasElement, cursor: &Cursor) {
                 Cursor::IBeam => "text",
                 Cursor::Pointer => "pointer",
                 Cursor::Crosshair => "crosshair",
-                Cursor::OpenHand => "grab",
+                Cursor::OpenHand | Cursor::Grab => "grab",
                 Cursor::NotAllowed => "not-allowed",
                 Cursor::ResizeLeftRight => "ew-resize",
                 Cursor::ResizeUpDown => "ns-resize",
+                Cursor::Wait => "wait",
+                Cursor::Progress => "progress",
+                Cursor::Help => "help",
+                Cursor::Move => "move",
+                Cursor::Grabbing => "grabbing",
+                Cursor::ResizeUp => "n-resize",
+                Cursor::ResizeDown => "s-resize",
+                Cursor::ResizeLeft => "w-resize",
+                Cursor::ResizeRight => "e-resize",
+                Cursor::ResizeUpLeft => "nw-resize",
+                Cursor::ResizeUpRight => "ne-resize",
+                Cursor::ResizeDownLeft => "sw-resize",
+                Cursor::ResizeDownRight => "se-resize",
+                Cursor::ResizeUpLeftDownRight => "nwse-resize",
+                Cursor::ResizeUpRightDownLeft => "nesw-resize",
+                Cursor::ColResize => "col-resize",
+                Cursor::RowResize => "row-resize",
+                Cursor::ZoomIn => "zoom-in",
+                Cursor::ZoomOut => "zoom-out",
+                Cursor::VerticalText => "vertical-text",
+                Cursor::Copy => "copy",
+                Cursor::Alias => "alias",
+                Cursor::ContextMenu => "context-menu",
+                Cursor::NoDrop => "no-drop",
+                Cursor::Hidden => "none",
                 // TODO: support custom cursors
                 Cursor::Custom(_) => "default",
             },
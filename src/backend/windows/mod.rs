@@ -18,6 +18,7 @@ mod accels;
 pub mod application;
 pub mod clipboard;
 pub mod dialog;
+pub mod dnd;
 pub mod error;
 mod keyboard;
 pub mod menu;
@@ -37,6 +37,7 @@ use winapi::shared::winerror::*;
 use winapi::um::dwmapi::{DwmExtendFrameIntoClientArea, DwmSetWindowAttribute};
 use winapi::um::errhandlingapi::GetLastError;
 use winapi::um::shellscalingapi::MDT_EFFECTIVE_DPI;
+use winapi::um::sysinfoapi::GetTickCount;
 use winapi::um::uxtheme::*;
 use winapi::um::wingdi::*;
 use winapi::um::winnt::*;
@@ -59,20 +60,26 @@ use super::menu::Menu;
 use super::timers::TimerSlots;
 use super::util::{self, ToWide, OPTIONAL_FUNCTIONS};
 
-use crate::common_util::IdleCallback;
+use crate::clipboard::ClipboardFormat;
+use crate::common_util::{instant_from_age, IdleCallback};
 use crate::dialog::{FileDialogOptions, FileDialogType, FileInfo};
+use crate::dnd::{DragIcon, DropAction};
 use crate::error::Error as ShellError;
+use crate::icon::Icon;
 use crate::keyboard::{KbKey, KeyState, ModifiersExt};
 use crate::mouse::{Cursor, CursorDesc};
+use crate::pixels::PixelBuffer;
 use crate::pointer::{
-    MouseInfo, PointerButton, PointerButtons, PointerEvent, PointerId, PointerType,
+    MouseInfo, PointerButton, PointerButtons, PointerEvent, PointerId, PointerType, ScrollInfo,
+    ScrollSource, ScrollUnit,
 };
 use crate::region::Region;
 use crate::scale::{Scalable, Scale, ScaledArea};
 use crate::text::{simulate_input, Event};
 use crate::window;
 use crate::window::{
-    FileDialogToken, IdleToken, TextFieldToken, TimerToken, WinHandler, WindowLevel,
+    FileDialogToken, IdleToken, PointerGrab, ResizeEdge, TextFieldToken, TimerToken, WinHandler,
+    WindowLevel,
 };
 
 /// The backend target DPI.
@@ -156,8 +163,15 @@ enum DeferredOp {
     SetPosition(Point),
     SetSize(Size),
     SetResizable(bool),
+    SetMinSize(Size),
+    SetMaxSize(Option<Size>),
     SetWindowState(window::WindowState),
     ReleaseMouseCapture,
+    /// Let the system move or resize the window, as if the area with this hit test code was
+    /// pressed.
+    BeginMoveResize(LRESULT),
+    ShowWindowMenu(Point),
+    SetPointerGrab(PointerGrab),
 }
 
 #[derive(Clone, Debug, Default)]
@@ -278,11 +292,15 @@ struct MyWndProc {
 struct WndState {
     handler: Box<dyn WinHandler>,
     min_size: Option<Size>,
+    max_size: Option<Size>,
     keyboard_state: KeyboardState,
     // Stores a set of all pointer buttons that are currently holding pointer
     // capture. When the first pointer button is down on our window we enter
     // capture, and we hold it until the last pointer button is up.
     captured_pointer_buttons: PointerButtons,
+    // The grab requested by the application, which keeps the capture after the buttons are up
+    // for `PointerGrab::Capture`.
+    pointer_grab: PointerGrab,
     // Is this window the topmost window under the mouse cursor
     has_mouse_focus: bool,
     //TODO: track surrogate orphan
@@ -341,6 +359,50 @@ fn get_buttons(wparam: WPARAM) -> PointerButtons {
     buttons
 }
 
+/// The details of a wheel message, using the user's settings for how far a click scrolls.
+fn wheel_scroll_info(wheel_delta: Vec2, horizontal: bool) -> ScrollInfo {
+    let clicks = wheel_delta / WHEEL_DELTA as f64;
+    let action = if horizontal {
+        SPI_GETWHEELSCROLLCHARS
+    } else {
+        SPI_GETWHEELSCROLLLINES
+    };
+    let mut per_click: UINT = 3;
+    unsafe {
+        if SystemParametersInfoW(action, 0, &mut per_click as *mut UINT as *mut c_void, 0) == FALSE
+        {
+            per_click = 3;
+        }
+    }
+    let (delta, unit) = if per_click == WHEEL_PAGESCROLL {
+        (clicks, ScrollUnit::Pages)
+    } else {
+        (clicks * per_click as f64, ScrollUnit::Lines)
+    };
+    // Precision touchpads and free-spinning wheels send fractions of a click, but they can't be
+    // told apart.
+    let source = if clicks.x.fract() == 0.0 && clicks.y.fract() == 0.0 {
+        ScrollSource::Wheel
+    } else {
+        ScrollSource::Unknown
+    };
+    ScrollInfo {
+        delta,
+        unit,
+        clicks,
+        phase: None,
+        source,
+    }
+}
+
+/// The instant of the message which is being handled.
+fn message_time() -> Option<Instant> {
+    // Both are in milliseconds since the system started, and wrap around every 49 days.
+    let (now, time) = unsafe { (GetTickCount(), GetMessageTime() as u32) };
+    let age = Duration::from_millis(now.wrapping_sub(time).into());
+    Some(instant_from_age(age))
+}
+
 fn is_point_in_client_rect(hwnd: HWND, x: i32, y: i32) -> bool {
     unsafe {
         let mut client_rect = mem::MaybeUninit::uninit();
@@ -550,6 +612,12 @@ impl MyWndProc {
                     self.with_window_state(|s| s.has_titlebar.set(titlebar));
                     set_style(hwnd, self.resizable(), titlebar);
                 }
+                DeferredOp::SetMinSize(size) => {
+                    self.with_wnd_state(|s| s.min_size = Some(size));
+                }
+                DeferredOp::SetMaxSize(size) => {
+                    self.with_wnd_state(|s| s.max_size = size);
+                }
                 DeferredOp::SetResizable(resizable) => {
                     self.with_window_state(|s| s.is_resizable.set(resizable));
                     set_style(hwnd, resizable, self.has_titlebar());
@@ -560,6 +628,10 @@ impl MyWndProc {
                             window::WindowState::Maximized => SW_MAXIMIZE,
                             window::WindowState::Minimized => SW_MINIMIZE,
                             window::WindowState::Restored => SW_RESTORE,
+                            window::WindowState::Fullscreen(_) => {
+                                warn!("fullscreen not yet implemented on windows, maximizing");
+                                SW_MAXIMIZE
+                            }
                         }
                     } else {
                         SW_SHOWNOACTIVATE
@@ -606,7 +678,86 @@ impl MyWndProc {
                         }
                     }
                 }
+                DeferredOp::BeginMoveResize(hit_test) => unsafe {
+                    // Our own pointer capture would keep the system from taking over the pointer.
+                    ReleaseCapture();
+                    // The position of the message which is being handled, in screen coordinates.
+                    let pos = GetMessagePos();
+                    SendMessageW(hwnd, WM_NCLBUTTONDOWN, hit_test as WPARAM, pos as LPARAM);
+                },
+                DeferredOp::ShowWindowMenu(pos) => {
+                    let pos = pos.to_px(self.scale()).round();
+                    unsafe {
+                        let mut point = POINT {
+                            x: pos.x as i32,
+                            y: pos.y as i32,
+                        };
+                        ClientToScreen(hwnd, &mut point);
+                        let hmenu = GetSystemMenu(hwnd, FALSE);
+                        let command = TrackPopupMenu(
+                            hmenu,
+                            TPM_LEFTALIGN | TPM_RETURNCMD,
+                            point.x,
+                            point.y,
+                            0,
+                            hwnd,
+                            null(),
+                        );
+                        if command != 0 {
+                            PostMessageW(hwnd, WM_SYSCOMMAND, command as WPARAM, 0);
+                        }
+                    }
+                }
+                DeferredOp::SetPointerGrab(grab) => {
+                    let buttons_captured = self
+                        .with_wnd_state(|s| {
+                            s.pointer_grab = grab;
+                            !s.captured_pointer_buttons.is_empty()
+                        })
+                        .unwrap_or(false);
+                    unsafe {
+                        ClipCursor(null());
+                        match grab {
+                            PointerGrab::None => {}
+                            PointerGrab::Capture => {
+                                SetCapture(hwnd);
+                            }
+                            PointerGrab::Lock => {
+                                warn!("PointerGrab::Lock not yet implemented on windows");
+                            }
+                            PointerGrab::Confine(rect) => {
+                                // Rounding inwards, so that the cursor stays over the rectangle.
+                                let rect = rect.to_px(self.scale()).trunc();
+                                let mut top_left = POINT {
+                                    x: rect.x0 as i32,
+                                    y: rect.y0 as i32,
+                                };
+                                let mut bottom_right = POINT {
+                                    x: rect.x1 as i32,
+                                    y: rect.y1 as i32,
+                                };
+                                ClientToScreen(hwnd, &mut top_left);
+                                ClientToScreen(hwnd, &mut bottom_right);
+                                let clip = RECT {
+                                    left: top_left.x,
+                                    top: top_left.y,
+                                    right: bottom_right.x,
+                                    bottom: bottom_right.y,
+                                };
+                                ClipCursor(&clip);
+                            }
+                        }
+                        if grab != PointerGrab::Capture && !buttons_captured {
+                            ReleaseCapture();
+                        }
+                    }
+                }
                 DeferredOp::ReleaseMouseCapture => unsafe {
+                    let grab = self.with_wnd_state(|s| s.pointer_grab);
+                    if grab == Some(PointerGrab::Capture) {
+                        // The application still wants the pointer events.
+                        return;
+                    }
                     if ReleaseCapture() == FALSE {
                         let result = HRESULT_FROM_WIN32(GetLastError());
                         // When result is zero, it appears to just mean that the capture was already released
@@ -950,6 +1101,7 @@ impl WndProc for MyWndProc {
                         WM_MOUSEHWHEEL => Vec2::new(system_delta, 0.),
                         _ => unreachable!(),
                     };
+                    let scroll = wheel_scroll_info(wheel_delta, wheel_delta.x != 0.0);
 
                     let mut p = POINT {
                         x: LOWORD(lparam as u32) as i16 as i32,
@@ -968,13 +1120,18 @@ impl WndProc for MyWndProc {
                     let event = PointerEvent {
                         pointer_id: PointerId(0),
                         is_primary: true,
-                        pointer_type: PointerType::Mouse(MouseInfo { wheel_delta }),
+                        pointer_type: PointerType::Mouse(MouseInfo {
+                            wheel_delta,
+                            scroll,
+                        }),
                         pos: Point::new(p.x as f64, p.y as f64).to_dp(self.scale()),
                         buttons: get_buttons(down_state),
                         modifiers,
                         button: PointerButton::None,
                         focus: false,
                         count: 0,
+                        time: message_time(),
+                        coalesced: Vec::new(),
                     };
                     s.handler.wheel(event);
                     true
@@ -994,6 +1151,7 @@ impl WndProc for MyWndProc {
                     // WM_MOUSELEAVE event. Note that WM_MOUSEMOVE is also called even when the
                     // window under the cursor changes without moving the mouse, for example when
                     // our window is first opened under the mouse cursor.
+                    let mut entered = false;
                     if !s.has_mouse_focus && is_point_in_client_rect(hwnd, x, y) {
                         let mut desc = TRACKMOUSEEVENT {
                             cbSize: mem::size_of::<TRACKMOUSEEVENT>() as DWORD,
@@ -1004,6 +1162,7 @@ impl WndProc for MyWndProc {
                         unsafe {
                             if TrackMouseEvent(&mut desc) != FALSE {
                                 s.has_mouse_focus = true;
+                                entered = true;
                             } else {
                                 warn!(
                                     "failed to TrackMouseEvent: {}",
@@ -1016,16 +1175,19 @@ impl WndProc for MyWndProc {
                     let event = PointerEvent {
                         pointer_id: PointerId(0),
                         is_primary: true,
-                        pointer_type: PointerType::Mouse(MouseInfo {
-                            wheel_delta: Vec2::ZERO,
-                        }),
+                        pointer_type: PointerType::Mouse(MouseInfo::default()),
                         pos: Point::new(x as f64, y as f64).to_dp(self.scale()),
                         buttons: get_buttons(wparam),
                         modifiers: s.keyboard_state.get_modifiers(),
                         button: PointerButton::None,
                         focus: false,
                         count: 0,
+                        time: message_time(),
+                        coalesced: Vec::new(),
                     };
+                    if entered {
+                        s.handler.pointer_enter(event.clone());
+                    }
                     s.handler.pointer_move(event);
                 });
                 Some(0)
@@ -1103,15 +1265,15 @@ impl WndProc for MyWndProc {
                         let event = PointerEvent {
                             pointer_id: PointerId(0),
                             is_primary: true,
-                            pointer_type: PointerType::Mouse(MouseInfo {
-                                wheel_delta: Vec2::ZERO,
-                            }),
+                            pointer_type: PointerType::Mouse(MouseInfo::default()),
                             pos: Point::new(x as f64, y as f64).to_dp(self.scale()),
                             buttons: get_buttons(wparam),
                             modifiers: s.keyboard_state.get_modifiers(),
                             button,
                             focus: false,
                             count,
+                            time: message_time(),
+                            coalesced: Vec::new(),
                         };
                         if count > 0 {
                             s.enter_pointer_capture(hwnd, button);
@@ -1137,6 +1299,15 @@ impl WndProc for MyWndProc {
                 Some(0)
             }
             WM_DESTROY => {
+                if let Some(w) = self.handle.borrow().state.upgrade() {
+                    if let WindowLevel::Modal(parent) = &w.window_level {
+                        if let Some(parent_hwnd) = parent.0.get_hwnd() {
+                            unsafe {
+                                EnableWindow(parent_hwnd, TRUE);
+                            }
+                        }
+                    }
+                }
                 self.with_wnd_state(|s| s.handler.destroy());
                 Some(0)
             }
@@ -1162,6 +1333,11 @@ impl WndProc for MyWndProc {
                         min_max_info.ptMinTrackSize.x = min_size_px.width.round() as i32;
                         min_max_info.ptMinTrackSize.y = min_size_px.height.round() as i32;
                     }
+                    if let Some(max_size_dp) = s.max_size {
+                        let max_size_px = max_size_dp.to_px(self.scale());
+                        min_max_info.ptMaxTrackSize.x = max_size_px.width.round() as i32;
+                        min_max_info.ptMaxTrackSize.y = max_size_px.height.round() as i32;
+                    }
                 });
                 Some(0)
             }
@@ -1280,6 +1456,11 @@ impl WindowBuilder {
         self
     }
 
+    pub fn icon(self, _icon: Icon) -> Self {
+        warn!("WindowBuilder::icon not yet implemented on windows");
+        self
+    }
+
     pub fn menu(mut self, menu: Menu) -> Self {
         self.menu = Some(menu);
         self
@@ -1344,13 +1525,26 @@ impl WindowBuilder {
                 window_level = level.clone();
                 match level {
                     WindowLevel::AppWindow => (),
+                    WindowLevel::AlwaysOnTop => dwExStyle = WS_EX_TOPMOST,
+                    WindowLevel::Notification => {
+                        dwStyle = WS_POPUP;
+                        dwExStyle = WS_EX_NOACTIVATE | WS_EX_TOOLWINDOW | WS_EX_TOPMOST;
+                        focusable = false;
+                    }
                     WindowLevel::Tooltip(parent_window_handle)
                     | WindowLevel::DropDown(parent_window_handle)
-                    | WindowLevel::Modal(parent_window_handle) => {
+                    | WindowLevel::Modal(parent_window_handle)
+                    | WindowLevel::Utility(parent_window_handle) => {
                         parent_hwnd = parent_window_handle.0.get_hwnd();
-                        dwStyle = WS_POPUP;
-                        dwExStyle = WS_EX_NOACTIVATE | WS_EX_TOOLWINDOW;
-                        focusable = false;
+                        if matches!(window_level, WindowLevel::Utility(_)) {
+                            // A tool window with a small title bar, which can take the focus.
+                            dwStyle = WS_OVERLAPPEDWINDOW & !(WS_MINIMIZEBOX | WS_MAXIMIZEBOX);
+                            dwExStyle = WS_EX_TOOLWINDOW;
+                        } else {
+                            dwStyle = WS_POPUP;
+                            dwExStyle = WS_EX_NOACTIVATE | WS_EX_TOOLWINDOW;
+                            focusable = false;
+                        }
                         if let Some(point_in_window_coord) = self.position {
                             let screen_point = parent_window_handle.get_position()
                                 + point_in_window_coord.to_vec2();
@@ -1369,6 +1563,8 @@ impl WindowBuilder {
                 // Default window level
                 window_level = WindowLevel::AppWindow;
             }
+            let modal_parent_hwnd =
+                parent_hwnd.filter(|_| matches!(window_level, WindowLevel::Modal(_)));
 
             let window = WindowState {
                 hwnd: Cell::new(0 as HWND),
@@ -1400,8 +1596,10 @@ impl WindowBuilder {
             let state = WndState {
                 handler: self.handler.unwrap(),
                 min_size: self.min_size,
+                max_size: None,
                 keyboard_state: KeyboardState::new(),
                 captured_pointer_buttons: PointerButtons::new(),
+                pointer_grab: PointerGrab::None,
                 has_mouse_focus: false,
                 last_click_time: Instant::now(),
                 last_click_pos: (0, 0),
@@ -1421,7 +1619,9 @@ impl WindowBuilder {
             }
 
             match self.state {
-                window::WindowState::Maximized => dwStyle |= WS_MAXIMIZE,
+                window::WindowState::Maximized | window::WindowState::Fullscreen(_) => {
+                    dwStyle |= WS_MAXIMIZE
+                }
                 window::WindowState::Minimized => dwStyle |= WS_MINIMIZE,
                 _ => (),
             };
@@ -1444,6 +1644,11 @@ impl WindowBuilder {
                 return Err(Error::NullHwnd);
             }
 
+            // Like a dialog box, a modal window disables its parent until it is destroyed.
+            if let Some(parent_hwnd) = modal_parent_hwnd {
+                EnableWindow(parent_hwnd, FALSE);
+            }
+
             if let Some(size_dp) = self.size {
                 if let Ok(scale) = handle.get_scale() {
                     let size_px = size_dp.to_px(scale);
@@ -1579,13 +1784,33 @@ impl Cursor {
             Cursor::IBeam => IDC_IBEAM,
             Cursor::Pointer => IDC_HAND,
             Cursor::Crosshair => IDC_CROSS,
-            Cursor::OpenHand => {
-                warn!("Cursor::OpenHand not available on windows");
-                IDC_ARROW
+            Cursor::OpenHand | Cursor::Grab | Cursor::Grabbing => IDC_HAND,
+            Cursor::NotAllowed | Cursor::NoDrop => IDC_NO,
+            Cursor::ResizeLeftRight
+            | Cursor::ResizeLeft
+            | Cursor::ResizeRight
+            | Cursor::ColResize => IDC_SIZEWE,
+            Cursor::ResizeUpDown | Cursor::ResizeUp | Cursor::ResizeDown | Cursor::RowResize => {
+                IDC_SIZENS
+            }
+            Cursor::ResizeUpLeft | Cursor::ResizeDownRight | Cursor::ResizeUpLeftDownRight => {
+                IDC_SIZENWSE
+            }
+            Cursor::ResizeUpRight | Cursor::ResizeDownLeft | Cursor::ResizeUpRightDownLeft => {
+                IDC_SIZENESW
             }
-            Cursor::NotAllowed => IDC_NO,
-            Cursor::ResizeLeftRight => IDC_SIZEWE,
-            Cursor::ResizeUpDown => IDC_SIZENS,
+            Cursor::Wait => IDC_WAIT,
+            Cursor::Progress => IDC_APPSTARTING,
+            Cursor::Help => IDC_HELP,
+            Cursor::Move => IDC_SIZEALL,
+            Cursor::VerticalText => IDC_IBEAM,
+            // Windows has no system cursors for these.
+            Cursor::ZoomIn
+            | Cursor::ZoomOut
+            | Cursor::Copy
+            | Cursor::Alias
+            | Cursor::ContextMenu => IDC_ARROW,
+            Cursor::Hidden => return 0 as HCURSOR,
             Cursor::Custom(c) => {
                 return (c.0).0;
             }
@@ -1696,12 +1921,13 @@ impl WindowHandle {
             match &w.window_level {
                 WindowLevel::Tooltip(parent_window_handle)
                 | WindowLevel::DropDown(parent_window_handle)
-                | WindowLevel::Modal(parent_window_handle) => {
+                | WindowLevel::Modal(parent_window_handle)
+                | WindowLevel::Utility(parent_window_handle) => {
                     // Has owned window. Convert point from window coords to screen coords.
                     let screen_position = parent_window_handle.get_position() + position.to_vec2();
                     self.defer(DeferredOp::SetPosition(screen_position));
                 }
-                WindowLevel::AppWindow => {
+                WindowLevel::AppWindow | WindowLevel::AlwaysOnTop | WindowLevel::Notification => {
                     self.defer(DeferredOp::SetPosition(position));
                 }
             }
@@ -1796,6 +2022,56 @@ impl WindowHandle {
         self.defer(DeferredOp::SetResizable(resizable));
     }
 
+    pub fn begin_move_drag(&self) {
+        self.defer(DeferredOp::BeginMoveResize(HTCAPTION));
+    }
+
+    pub fn begin_resize_drag(&self, edge: ResizeEdge) {
+        let hit_test = match edge {
+            ResizeEdge::Top => HTTOP,
+            ResizeEdge::Bottom => HTBOTTOM,
+            ResizeEdge::Left => HTLEFT,
+            ResizeEdge::Right => HTRIGHT,
+            ResizeEdge::TopLeft => HTTOPLEFT,
+            ResizeEdge::TopRight => HTTOPRIGHT,
+            ResizeEdge::BottomLeft => HTBOTTOMLEFT,
+            ResizeEdge::BottomRight => HTBOTTOMRIGHT,
+        };
+        self.defer(DeferredOp::BeginMoveResize(hit_test));
+    }
+
+    pub fn show_window_menu(&self, pos: Point) {
+        self.defer(DeferredOp::ShowWindowMenu(pos));
+    }
+
+    pub fn set_pointer_grab(&self, grab: PointerGrab) {
+        self.defer(DeferredOp::SetPointerGrab(grab));
+    }
+
+    pub fn set_min_size(&self, size: Size) {
+        self.defer(DeferredOp::SetMinSize(size));
+    }
+
+    pub fn set_max_size(&self, size: Option<Size>) {
+        self.defer(DeferredOp::SetMaxSize(size));
+    }
+
+    pub fn set_resize_increments(&self, _increments: Option<Size>) {
+        warn!("WindowHandle::set_resize_increments not yet implemented on windows");
+    }
+
+    pub fn set_aspect_ratio(&self, _ratio: Option<f64>) {
+        warn!("WindowHandle::set_aspect_ratio not yet implemented on windows");
+    }
+
+    pub fn set_input_region(&self, _region: Option<Region>) {
+        warn!("WindowHandle::set_input_region not yet implemented on windows");
+    }
+
+    pub fn set_opaque_region(&self, _region: Option<Region>) {
+        warn!("WindowHandle::set_opaque_region not yet implemented on windows");
+    }
+
     // Sets the window state.
     pub fn set_window_state(&self, state: window::WindowState) {
         self.defer(DeferredOp::SetWindowState(state));
@@ -1834,6 +2110,10 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_icon(&self, _icon: Option<&Icon>) {
+        warn!("set_icon not yet implemented on windows");
+    }
+
     pub fn set_menu(&self, menu: Menu) {
         let accels = menu.accels();
         let hmenu = menu.into_hmenu();
@@ -1902,6 +2182,8 @@ impl WindowHandle {
 
     pub fn make_cursor(&self, cursor_desc: &CursorDesc) -> Option<Cursor> {
         if let Some(hwnd) = self.get_hwnd() {
+            let scale = self.get_scale().map_or(1.0, |scale| scale.x());
+            let (image, hot) = cursor_desc.image_for_scale(scale);
             unsafe {
                 let hdc = GetDC(hwnd);
                 if hdc.is_null() {
@@ -1921,8 +2203,8 @@ impl WindowHandle {
                 }
                 defer!(DeleteDC(bmp_dc););
 
-                let width = 1; //cursor_desc.image.width();
-                let height = 1; //cursor_desc.image.height();
+                let width = image.width();
+                let height = image.height();
                 let mask = CreateCompatibleBitmap(hdc, width as c_int, height as c_int);
                 if mask.is_null() {
                     return None;
@@ -1938,16 +2220,16 @@ impl WindowHandle {
                 let old_mask = SelectObject(mask_dc, mask as *mut c_void);
                 let old_bmp = SelectObject(bmp_dc, bmp as *mut c_void);
 
-                // for (row_idx, row) in cursor_desc.image.pixel_colors().enumerate() {
-                //     for (col_idx, p) in row.enumerate() {
-                //         let (r, g, b, a) = p.as_rgba8();
-                //         // TODO: what's the story on partial transparency? I couldn't find documentation.
-                //         let mask_px = RGB(255 - a, 255 - a, 255 - a);
-                //         let bmp_px = RGB(r, g, b);
-                //         SetPixel(mask_dc, col_idx as i32, row_idx as i32, mask_px);
-                //         SetPixel(bmp_dc, col_idx as i32, row_idx as i32, bmp_px);
-                //     }
-                // }
+                for y in 0..height {
+                    for x in 0..width {
+                        let [r, g, b, a] = image.pixel(x, y);
+                        // TODO: what's the story on partial transparency? I couldn't find documentation.
+                        let mask_px = RGB(255 - a, 255 - a, 255 - a);
+                        let bmp_px = RGB(r, g, b);
+                        SetPixel(mask_dc, x as i32, y as i32, mask_px);
+                        SetPixel(bmp_dc, x as i32, y as i32, bmp_px);
+                    }
+                }
 
                 SelectObject(mask_dc, old_mask);
                 SelectObject(bmp_dc, old_bmp);
@@ -1955,8 +2237,8 @@ impl WindowHandle {
                 let mut icon_info = ICONINFO {
                     // 0 means it's a cursor, not an icon.
                     fIcon: 0,
-                    xHotspot: cursor_desc.hot.x as DWORD,
-                    yHotspot: cursor_desc.hot.y as DWORD,
+                    xHotspot: hot.x as DWORD,
+                    yHotspot: hot.y as DWORD,
                     hbmMask: mask,
                     hbmColor: bmp,
                 };
@@ -1993,6 +2275,23 @@ impl WindowHandle {
         self.state.upgrade().map(|w| w.is_focusable).unwrap_or(true)
     }
 
+    pub fn set_drop_target(&self, _enabled: bool) {
+        warn!("set_drop_target not yet implemented on windows");
+    }
+
+    pub fn present_pixels(&self, _buffer: &PixelBuffer, _damage: Option<&Region>) {
+        warn!("present_pixels not yet implemented on windows");
+    }
+
+    pub fn start_drag(
+        &self,
+        _formats: &[ClipboardFormat],
+        _icon: Option<DragIcon>,
+        _actions: &[DropAction],
+    ) {
+        warn!("start_drag not yet implemented on windows");
+    }
+
     /// Get a handle that can be used to schedule an idle task.
     pub fn get_idle_handle(&self) -> Option<IdleHandle> {
         self.state.upgrade().map(|w| IdleHandle {
@@ -23,8 +23,14 @@ use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Error};
 use x11rb::connection::{Connection, RequestConnection};
+use x11rb::errors::ReplyOrIdError;
+use x11rb::protocol::randr::{self, ConnectionExt as _};
 use x11rb::protocol::render::{self, ConnectionExt as _, Pictformat};
-use x11rb::protocol::xinput::ChangeReason;
+use x11rb::protocol::shape::{self, ConnectionExt as _};
+use x11rb::protocol::shm::{self, ConnectionExt as _};
+use x11rb::protocol::sync::{self, ConnectionExt as _};
+use x11rb::protocol::xfixes::{self, ConnectionExt as _};
+use x11rb::protocol::xinput::{self, ChangeReason, ConnectionExt as _, Fp3232};
 use x11rb::protocol::xkb::{EventType, MapPart, SelectEventsAux};
 use x11rb::protocol::xproto::{
     self, ConnectionExt as _, CreateWindowAux, EventMask, Timestamp, Visualtype, WindowClass,
@@ -36,11 +42,18 @@ use x11rb::resource_manager::{
 use x11rb::xcb_ffi::XCBConnection;
 
 use crate::application::AppHandler;
+use crate::backend::shared::linux::cursor::cursor_names;
+use crate::kurbo::{Rect, Vec2};
+use crate::mouse::Cursor;
+use crate::pointer::GesturePhase;
+use crate::scale::Scale;
+use crate::Modifiers;
 
 use super::clipboard::Clipboard;
 use super::pointer::{DeviceInfo, PointersState};
 use super::util;
 use super::window::Window;
+use super::xsettings::{XSettings, XSettingsClient};
 use crate::backend::shared::linux;
 use crate::backend::shared::xkb::{self};
 
@@ -80,6 +93,57 @@ use crate::backend::shared::xkb::{self};
 //
 // The type of _NET_WM_NAME
 //
+// _NET_WM_ICON
+//
+// The icon of a window, as an array of images, which are each a width, a height and ARGB pixels.
+//
+// https://specifications.freedesktop.org/wm-spec/latest/
+//
+// WM_STATE, WM_CHANGE_STATE
+//
+// The state that the window manager gives a window, and the message which asks it to iconify one.
+//
+// https://www.x.org/releases/X11R7.6/doc/xorg-docs/specs/ICCCM/icccm.html#wm_state_property
+//
+// _NET_WM_STATE, _NET_WM_STATE_MAXIMIZED_VERT, _NET_WM_STATE_MAXIMIZED_HORZ,
+// _NET_WM_STATE_HIDDEN, _NET_WM_STATE_FULLSCREEN, _NET_WM_STATE_ABOVE, _NET_WM_STATE_MODAL
+//
+// The states of a window, which are changed by sending _NET_WM_STATE messages to the root window.
+//
+// https://specifications.freedesktop.org/wm-spec/latest/
+//
+// _NET_WM_FULLSCREEN_MONITORS
+//
+// The monitors that a fullscreen window covers, given as Xinerama indices.
+//
+// https://specifications.freedesktop.org/wm-spec/latest/
+//
+// _NET_WM_MOVERESIZE
+//
+// Asks the window manager to move or resize a window interactively, from a button press.
+//
+// https://specifications.freedesktop.org/wm-spec/latest/
+//
+// _GTK_SHOW_WINDOW_MENU
+//
+// Asks the window manager to show its menu for a window. This is an extension of GTK, which the
+// common window managers support.
+//
+// _NET_WM_OPAQUE_REGION
+//
+// The rectangles of a window that are fully opaque, so that the compositor doesn't need to draw
+// what is behind them.
+//
+// https://specifications.freedesktop.org/wm-spec/latest/
+//
+// _NET_WM_WINDOW_TYPE, _NET_WM_WINDOW_TYPE_NORMAL, _NET_WM_WINDOW_TYPE_DROPDOWN_MENU,
+// _NET_WM_WINDOW_TYPE_TOOLTIP, _NET_WM_WINDOW_TYPE_DIALOG, _NET_WM_WINDOW_TYPE_UTILITY,
+// _NET_WM_WINDOW_TYPE_NOTIFICATION
+//
+// The functional type of a window, which the window manager uses to decorate and place it.
+//
+// https://specifications.freedesktop.org/wm-spec/latest/
+//
 // CLIPBOARD
 //
 // The name of the clipboard selection; used for implementing copy&paste
@@ -95,6 +159,34 @@ use crate::backend::shared::xkb::{self};
 // INCR
 //
 // Type used for incremental selection transfers
+//
+// _NET_WORKAREA, _NET_CURRENT_DESKTOP, _NET_CLIENT_LIST, _NET_WM_STRUT, _NET_WM_STRUT_PARTIAL
+//
+// Used for computing the work area of each monitor, i.e. the part of the monitor that is not
+// covered by panels and docks.
+//
+// https://specifications.freedesktop.org/wm-spec/wm-spec-1.3.html#idm45805408014032
+//
+// _XSETTINGS_SETTINGS, MANAGER
+//
+// The property containing the XSETTINGS, and the client message a new XSETTINGS manager sends
+// when it takes ownership of the _XSETTINGS_Sn selection.
+//
+// https://specifications.freedesktop.org/xsettings-spec/xsettings-latest.html
+//
+// _NET_WM_SYNC_REQUEST, _NET_WM_SYNC_REQUEST_COUNTER
+//
+// Used for synchronizing repaints with the window manager when resizing: the window manager
+// waits for us to update the counter after painting the new size before it shows the frame.
+//
+// https://specifications.freedesktop.org/wm-spec/wm-spec-1.3.html#idm45805407916448
+//
+// XdndAware, XdndEnter, XdndPosition, XdndStatus, XdndLeave, XdndDrop, XdndFinished,
+// XdndSelection, XdndTypeList, XdndActionCopy, XdndActionMove, XdndActionLink
+//
+// The properties, messages, selection and actions of the XDND drag and drop protocol.
+//
+// https://www.freedesktop.org/wiki/Specifications/XDND/
 x11rb::atom_manager! {
     pub(crate) AppAtoms: AppAtomsCookie {
         WM_PROTOCOLS,
@@ -102,21 +194,61 @@ x11rb::atom_manager! {
         _NET_WM_PID,
         _NET_WM_NAME,
         UTF8_STRING,
+        _NET_WM_ICON,
+        WM_STATE,
+        WM_CHANGE_STATE,
+        _NET_WM_STATE,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_MODAL,
+        _NET_WM_FULLSCREEN_MONITORS,
+        _NET_WM_MOVERESIZE,
+        _GTK_SHOW_WINDOW_MENU,
+        _NET_WM_OPAQUE_REGION,
         _NET_WM_WINDOW_TYPE,
         _NET_WM_WINDOW_TYPE_NORMAL,
         _NET_WM_WINDOW_TYPE_DROPDOWN_MENU,
         _NET_WM_WINDOW_TYPE_TOOLTIP,
         _NET_WM_WINDOW_TYPE_DIALOG,
+        _NET_WM_WINDOW_TYPE_UTILITY,
+        _NET_WM_WINDOW_TYPE_NOTIFICATION,
         CLIPBOARD,
         PRIMARY,
         TARGETS,
         INCR,
+        _NET_WORKAREA,
+        _NET_CURRENT_DESKTOP,
+        _NET_CLIENT_LIST,
+        _NET_WM_STRUT,
+        _NET_WM_STRUT_PARTIAL,
+        _XSETTINGS_SETTINGS,
+        MANAGER,
+        _NET_WM_SYNC_REQUEST,
+        _NET_WM_SYNC_REQUEST_COUNTER,
+        XdndAware,
+        XdndEnter,
+        XdndPosition,
+        XdndStatus,
+        XdndLeave,
+        XdndDrop,
+        XdndFinished,
+        XdndSelection,
+        XdndTypeList,
+        XdndActionCopy,
+        XdndActionMove,
+        XdndActionLink,
         ABS_X: b"Abs X",
         ABS_Y: b"Abs Y",
         ABS_PRESSURE: b"Abs Pressure",
         ABS_XTILT: b"Abs Tilt X",
         ABS_YTILT: b"Abs Tilt Y",
         ABS_WHEEL: b"Abs Wheel",
+        ABS_DISTANCE: b"Abs Distance",
+        ABS_RZ: b"Abs Rotary Z",
+        ABS_THROTTLE: b"Abs Throttle",
     }
 }
 
@@ -181,6 +313,8 @@ pub(crate) struct AppInner {
 
     /// The X11 resource database used to query dpi.
     pub(crate) rdb: ResourceDb,
+    /// The settings published by the XSETTINGS manager, which take precedence over `rdb`.
+    xsettings: RefCell<XSettingsClient>,
     pub(crate) cursors: Cursors,
     /// The clipboard implementation
     clipboard: Clipboard,
@@ -204,8 +338,25 @@ pub(crate) struct AppInner {
     idle_write: RawFd,
     /// Support for the render extension in at least version 0.5?
     render_argb32_pictformat_cursor: Option<Pictformat>,
+    /// Support for the sync extension, needed for `_NET_WM_SYNC_REQUEST`.
+    sync_supported: bool,
+    /// Support for the MIT-SHM extension in at least version 1.2, which can attach file
+    /// descriptors.
+    shm_supported: bool,
+    /// Support for the SHAPE extension in at least version 1.1, which can shape the input of
+    /// windows.
+    shape_supported: bool,
+    /// Support for the XFixes extension in at least version 5.0, which has pointer barriers.
+    barriers_supported: bool,
+    /// Support for XInput 2.4, which has touchpad gestures.
+    gestures_supported: bool,
     /// The attached input devices, with internal mutability because X events can make them change.
     pointers: RefCell<PointersState>,
+    /// The rectangles of the monitors, which are looked up when they are first needed and
+    /// forgotten when RandR reports a change.
+    monitor_rects: RefCell<Option<Vec<Rect>>>,
+    /// The number of windows that have locked the pointer, which need its raw motion.
+    pointer_locks: Cell<usize>,
 }
 
 /// The mutable `Application` state.
@@ -217,15 +368,125 @@ struct State {
     xkb_state: xkb::KeyEventsState,
 }
 
-#[derive(Clone, Debug)]
+/// The cursors of the cursor theme, which are loaded when they are first used.
 pub(crate) struct Cursors {
-    pub default: Option<xproto::Cursor>,
-    pub text: Option<xproto::Cursor>,
-    pub pointer: Option<xproto::Cursor>,
-    pub crosshair: Option<xproto::Cursor>,
-    pub not_allowed: Option<xproto::Cursor>,
-    pub row_resize: Option<xproto::Cursor>,
-    pub col_resize: Option<xproto::Cursor>,
+    handle: RefCell<x11rb::cursor::Handle>,
+    /// The cursor for each name that was looked up, or `None` if it isn't in the theme.
+    loaded: RefCell<HashMap<&'static str, Option<xproto::Cursor>>>,
+    /// A cursor without any visible pixels, for `Cursor::Hidden`.
+    hidden: Cell<Option<xproto::Cursor>>,
+}
+
+impl Cursors {
+    /// The X11 cursor for `cursor`, falling back to the arrow if the theme doesn't have it.
+    ///
+    /// `window` is used to create the hidden cursor.
+    pub(crate) fn get(
+        &self,
+        conn: &XCBConnection,
+        window: xproto::Window,
+        cursor: &Cursor,
+    ) -> Option<xproto::Cursor> {
+        match cursor {
+            Cursor::Hidden => self.hidden(conn, window),
+            Cursor::Custom(_) => None,
+            _ => cursor_names(cursor)
+                .iter()
+                .chain(cursor_names(&Cursor::Arrow))
+                .find_map(|name| self.load(conn, name)),
+        }
+    }
+
+    fn load(&self, conn: &XCBConnection, name: &'static str) -> Option<xproto::Cursor> {
+        *self.loaded.borrow_mut().entry(name).or_insert_with(|| {
+            match self.handle.borrow().load_cursor(conn, name) {
+                Ok(x11rb::NONE) => None,
+                Ok(cursor) => Some(cursor),
+                Err(e) => {
+                    tracing::warn!("Unable to load cursor {}, error: {}", name, e);
+                    None
+                }
+            }
+        })
+    }
+
+    /// Switch to the cursor theme of `rdb`. The cursors are loaded again when they are next used.
+    fn reload(
+        &self,
+        conn: &XCBConnection,
+        screen_num: usize,
+        rdb: &ResourceDb,
+    ) -> Result<(), ReplyOrIdError> {
+        let handle = x11rb::cursor::Handle::new(conn, screen_num, rdb)?.reply()?;
+        *self.handle.borrow_mut() = handle;
+        // The windows which still show these cursors keep them until they are set again.
+        for cursor in self.loaded.take().into_values().flatten() {
+            conn.free_cursor(cursor)?;
+        }
+        Ok(())
+    }
+
+    fn hidden(&self, conn: &XCBConnection, window: xproto::Window) -> Option<xproto::Cursor> {
+        if self.hidden.get().is_none() {
+            match Cursors::create_hidden(conn, window) {
+                Ok(cursor) => self.hidden.set(Some(cursor)),
+                Err(e) => tracing::warn!("Unable to create the hidden cursor, error: {}", e),
+            }
+        }
+        self.hidden.get()
+    }
+
+    /// Create a cursor from a 1x1 bitmap, which is masked out.
+    fn create_hidden(
+        conn: &XCBConnection,
+        window: xproto::Window,
+    ) -> Result<xproto::Cursor, ReplyOrIdError> {
+        let pixmap = conn.generate_id()?;
+        conn.create_pixmap(1, pixmap, window, 1, 1)?;
+        let gc = conn.generate_id()?;
+        conn.create_gc(gc, pixmap, &xproto::CreateGCAux::new().foreground(0))?;
+        conn.poly_fill_rectangle(
+            pixmap,
+            gc,
+            &[xproto::Rectangle {
+                x: 0,
+                y: 0,
+                width: 1,
+                height: 1,
+            }],
+        )?;
+        conn.free_gc(gc)?;
+        let cursor = conn.generate_id()?;
+        conn.create_cursor(cursor, pixmap, pixmap, 0, 0, 0, 0, 0, 0, 0, 0)?;
+        conn.free_pixmap(pixmap)?;
+        Ok(cursor)
+    }
+}
+
+/// The resources which `x11rb::cursor` looks up, with the cursor theme from XSETTINGS filling in
+/// the ones that the user didn't configure in `rdb`.
+///
+/// A resource database can't be extended, so the resources are copied into a new one.
+fn cursor_resource_db(rdb: &ResourceDb, settings: &XSettings) -> ResourceDb {
+    let resource = |name| rdb.get_string(name, "").map(str::to_owned);
+    let resources = [
+        (
+            "Xcursor.theme",
+            resource("Xcursor.theme").or_else(|| settings.cursor_theme_name.clone()),
+        ),
+        (
+            "Xcursor.size",
+            resource("Xcursor.size").or_else(|| settings.cursor_theme_size.map(|s| s.to_string())),
+        ),
+        ("Xft.dpi", resource("Xft.dpi")),
+    ];
+    let mut data = String::new();
+    for (name, value) in resources {
+        if let Some(value) = value {
+            data.push_str(&format!("{}: {}\n", name, value));
+        }
+    }
+    ResourceDb::new_from_data(data.as_bytes())
 }
 
 #[derive(Clone)]
@@ -277,7 +538,7 @@ impl Application {
 }
 
 impl AppInner {
-    fn new() -> Result<Rc<AppInner>, Error> {
+    pub(super) fn new() -> Result<Rc<AppInner>, Error> {
         // If we want to support OpenGL, we will need to open a connection with Xlib support (see
         // https://xcb.freedesktop.org/opengl/ for background).  There is some sample code for this
         // in the `rust-xcb` crate (see `connect_with_xlib_display`), although it may be missing
@@ -354,30 +615,70 @@ impl AppInner {
             None
         };
 
-        let handle = x11rb::cursor::Handle::new(&connection, screen_num, &rdb)?.reply()?;
-        let load_cursor = |cursor| {
-            handle
-                .load_cursor(&connection, cursor)
-                .map_err(|e| tracing::warn!("Unable to load cursor {}, error: {}", cursor, e))
-                .ok()
-        };
-
-        let cursors = Cursors {
-            default: load_cursor("default"),
-            text: load_cursor("text"),
-            pointer: load_cursor("pointer"),
-            crosshair: load_cursor("crosshair"),
-            not_allowed: load_cursor("not-allowed"),
-            row_resize: load_cursor("row-resize"),
-            col_resize: load_cursor("col-resize"),
-        };
+        let sync_supported = connection
+            .extension_information(sync::X11_EXTENSION_NAME)?
+            .is_some()
+            && connection
+                .sync_initialize(3, 1)?
+                .reply()
+                .map(|version| version.major_version >= 3)
+                .unwrap_or(false);
+
+        let shm_supported = connection
+            .extension_information(shm::X11_EXTENSION_NAME)?
+            .is_some()
+            && connection
+                .shm_query_version()?
+                .reply()
+                .map(|version| (version.major_version, version.minor_version) >= (1, 2))
+                .unwrap_or(false);
+
+        let shape_supported = connection
+            .extension_information(shape::X11_EXTENSION_NAME)?
+            .is_some()
+            && connection
+                .shape_query_version()?
+                .reply()
+                .map(|version| (version.major_version, version.minor_version) >= (1, 1))
+                .unwrap_or(false);
+
+        let barriers_supported = connection
+            .extension_information(xfixes::X11_EXTENSION_NAME)?
+            .is_some()
+            && connection
+                .xfixes_query_version(5, 0)?
+                .reply()
+                .map(|version| version.major_version >= 5)
+                .unwrap_or(false);
 
         let atoms = AppAtoms::new(&connection)?
             .reply()
             .context("get X11 atoms")?;
 
+        // The server only sends the events and device classes of the version which we announce,
+        // so this needs to happen before querying the devices.
+        let gestures_supported = connection
+            .xinput_xi_query_version(2, 4)?
+            .reply()
+            .map(|version| (version.major_version, version.minor_version) >= (2, 4))
+            .unwrap_or(false);
         let pointers = super::pointer::initialize_pointers(&connection, &atoms, window_id)?;
 
+        // XSETTINGS are optional, so we carry on with the defaults if we can't watch them.
+        let xsettings =
+            XSettingsClient::new(&connection, screen_num, &atoms).unwrap_or_else(|err| {
+                tracing::warn!("Failed to initialize XSETTINGS: {}", err);
+                XSettingsClient::without_manager(&connection, screen_num)
+            });
+
+        let cursor_rdb = cursor_resource_db(&rdb, xsettings.settings());
+        let handle = x11rb::cursor::Handle::new(&connection, screen_num, &cursor_rdb)?.reply()?;
+        let cursors = Cursors {
+            handle: RefCell::new(handle),
+            loaded: RefCell::new(HashMap::new()),
+            hidden: Cell::new(None),
+        };
+
         let screen = connection
             .setup()
             .roots
@@ -387,6 +688,24 @@ impl AppInner {
             .ok_or_else(|| anyhow!("Couldn't get visual from screen"))?;
         let argb_visual_type = util::get_argb_visual_type(&connection, screen)?;
 
+        // The monitors are cached, so we need to know when they change.
+        let randr_supported = connection
+            .extension_information(randr::X11_EXTENSION_NAME)?
+            .is_some()
+            && connection
+                .randr_query_version(1, 2)?
+                .reply()
+                .map(|version| (version.major_version, version.minor_version) >= (1, 2))
+                .unwrap_or(false);
+        if randr_supported {
+            connection.randr_select_input(
+                screen.root,
+                randr::NotifyMask::SCREEN_CHANGE
+                    | randr::NotifyMask::CRTC_CHANGE
+                    | randr::NotifyMask::OUTPUT_CHANGE,
+            )?;
+        }
+
         let timestamp = Cell::new(x11rb::CURRENT_TIME);
 
         let shared = Rc::new(AppShared {
@@ -404,6 +723,7 @@ impl AppInner {
         Ok(Rc::new(AppInner {
             shared,
             rdb,
+            xsettings: RefCell::new(xsettings),
             window_id,
             state,
             idle_read,
@@ -414,7 +734,14 @@ impl AppInner {
             root_visual_type,
             argb_visual_type,
             render_argb32_pictformat_cursor,
+            sync_supported,
+            shm_supported,
+            shape_supported,
+            barriers_supported,
+            gestures_supported,
             pointers: RefCell::new(pointers),
+            monitor_rects: RefCell::new(None),
+            pointer_locks: Cell::new(0),
         }))
     }
 
@@ -424,6 +751,52 @@ impl AppInner {
         self.render_argb32_pictformat_cursor
     }
 
+    /// A handle for reading the `XdndSelection`, which holds the data of a drag.
+    pub(crate) fn xdnd_selection(&self) -> Clipboard {
+        Clipboard::new(Rc::clone(&self.shared), self.shared.atoms.XdndSelection)
+    }
+
+    /// The keyboard modifiers which are currently active, for events which don't include them.
+    pub(crate) fn modifiers(&self) -> Modifiers {
+        match borrow!(self.state) {
+            Ok(state) => state.xkb_state.active_modifiers(),
+            Err(err) => {
+                tracing::error!("{}", err);
+                Modifiers::empty()
+            }
+        }
+    }
+
+    /// Whether the sync extension is available, so that windows can use `_NET_WM_SYNC_REQUEST`.
+    #[inline]
+    pub(crate) fn sync_supported(&self) -> bool {
+        self.sync_supported
+    }
+
+    /// Whether MIT-SHM can be used to share pixels with the server.
+    #[inline]
+    pub(crate) fn shm_supported(&self) -> bool {
+        self.shm_supported
+    }
+
+    /// Whether the SHAPE extension can be used to set the input region of windows.
+    #[inline]
+    pub(crate) fn shape_supported(&self) -> bool {
+        self.shape_supported
+    }
+
+    /// Whether XFixes pointer barriers can be used to confine the pointer.
+    #[inline]
+    pub(crate) fn barriers_supported(&self) -> bool {
+        self.barriers_supported
+    }
+
+    /// Whether the XInput touchpad gestures can be selected.
+    #[inline]
+    pub(crate) fn gestures_supported(&self) -> bool {
+        self.gestures_supported
+    }
+
     fn create_event_window(conn: &XCBConnection, screen_num: usize) -> Result<u32, Error> {
         let id = conn.generate_id()?;
         let setup = conn.setup();
@@ -493,9 +866,20 @@ impl AppInner {
         self.shared.screen_num
     }
 
-    #[inline]
-    pub(crate) fn argb_visual_type(&self) -> Option<Visualtype> {
-        // Check if a composite manager is running
+    /// The rectangles of the monitors, in pixels.
+    pub(crate) fn monitor_rects(&self) -> Vec<Rect> {
+        self.monitor_rects
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                super::screen::get_monitor_rects(self.connection(), self.screen_num())
+            })
+            .clone()
+    }
+
+    /// Returns `true` if a compositing manager owns the `_NET_WM_CM_Sn` selection.
+    ///
+    /// Without a compositing manager, the alpha channel of ARGB windows is simply ignored.
+    pub(crate) fn compositor_running(&self) -> bool {
         let atom_name = format!("_NET_WM_CM_S{}", self.shared.screen_num);
         let owner = self
             .shared
@@ -508,11 +892,17 @@ impl AppInner {
             .and_then(|cookie| cookie.reply().ok())
             .map(|reply| reply.owner);
 
-        if Some(x11rb::NONE) == owner {
+        Some(x11rb::NONE) != owner
+    }
+
+    /// The 32 bit ARGB visual, if the server has one and a compositing manager is running.
+    #[inline]
+    pub(crate) fn argb_visual_type(&self) -> Option<Visualtype> {
+        if self.compositor_running() {
+            self.argb_visual_type
+        } else {
             tracing::debug!("_NET_WM_CM_Sn selection is unowned, not providing ARGB visual");
             None
-        } else {
-            self.argb_visual_type
         }
     }
 
@@ -520,6 +910,42 @@ impl AppInner {
         self.pointers.borrow().device_info(id).cloned()
     }
 
+    /// The wheel clicks of a pointer event, from the smooth scrolling valuators of its device.
+    pub(crate) fn scroll_clicks(&self, ev: &xinput::ButtonPressEvent) -> Vec2 {
+        self.pointers
+            .borrow_mut()
+            .scroll_clicks(ev.sourceid, &ev.valuator_mask, &ev.axisvalues)
+    }
+
+    /// Counts a window that locks or unlocks the pointer.
+    ///
+    /// The raw motion is selected on the root window while any window has the pointer locked.
+    pub(crate) fn count_pointer_lock(&self, locked: bool) -> Result<(), Error> {
+        let old = self.pointer_locks.get();
+        let new = if locked {
+            old + 1
+        } else {
+            old.saturating_sub(1)
+        };
+        if (old == 0) != (new == 0) {
+            let root = self.shared.connection.setup().roots[self.shared.screen_num].root;
+            super::pointer::select_raw_motion(&self.shared.connection, root, new > 0)?;
+        }
+        self.pointer_locks.set(new);
+        Ok(())
+    }
+
+    /// The valuators of a pointer event, with the latest values of those it leaves out.
+    pub(crate) fn valuators(&self, ev: &xinput::ButtonPressEvent) -> (Vec<u32>, Vec<Fp3232>) {
+        self.pointers
+            .borrow_mut()
+            .fill_valuators(ev.sourceid, &ev.valuator_mask, &ev.axisvalues)
+    }
+
+    pub(crate) fn reset_scroll_positions(&self) {
+        self.pointers.borrow_mut().reset_scroll_positions();
+    }
+
     fn reinitialize_pointers(&self) {
         match super::pointer::initialize_pointers(
             &self.shared.connection,
@@ -545,8 +971,96 @@ impl AppInner {
         &self.shared.atoms
     }
 
+    /// The scale of the screen.
+    ///
+    /// `GLAZIER_OVERRIDE_SCALE` takes precedence over the XSETTINGS, which take precedence over
+    /// `Xft.dpi` in the resource database.
+    pub(crate) fn scale(&self) -> Scale {
+        let scale_override = std::env::var("GLAZIER_OVERRIDE_SCALE")
+            .ok()
+            .map(|x| x.parse::<f64>());
+        let dpi = scale_override.or_else(|| {
+            let xsettings = self.xsettings.borrow();
+            let settings = xsettings.settings();
+            // Xft/DPI already includes the window scaling factor.
+            settings
+                .dpi
+                .or_else(|| {
+                    settings
+                        .window_scaling_factor
+                        .map(|factor| factor as f64 * 96.)
+                })
+                .map(Ok)
+                .or_else(|| self.rdb.get_value("Xft.dpi", "").transpose())
+        });
+
+        match dpi {
+            Some(Ok(dpi)) => {
+                let scale = dpi / 96.;
+                Scale::new(scale, scale)
+            }
+            None => Scale::default(),
+            Some(Err(err)) => {
+                let default = Scale::default();
+                tracing::warn!(
+                    "Unable to parse dpi: {:?}, defaulting to {:?}",
+                    err,
+                    default
+                );
+                default
+            }
+        }
+    }
+
+    /// The double click time in milliseconds and distance in pixels, if the XSETTINGS manager
+    /// configured them.
+    pub(crate) fn double_click_settings(&self) -> (Option<u64>, Option<f64>) {
+        let xsettings = self.xsettings.borrow();
+        let settings = xsettings.settings();
+        (settings.double_click_time, settings.double_click_distance)
+    }
+
+    /// The blink interval of the text cursor, if the XSETTINGS manager configured it.
+    pub(crate) fn cursor_blink_interval(&self) -> Option<Duration> {
+        let xsettings = self.xsettings.borrow();
+        xsettings
+            .settings()
+            .cursor_blink_time
+            .map(Duration::from_millis)
+    }
+
+    /// The name of the desktop theme, if the XSETTINGS manager configured it.
+    pub(crate) fn theme_name(&self) -> Option<String> {
+        self.xsettings.borrow().settings().theme_name.clone()
+    }
+
+    /// Tells all the windows about new XSETTINGS.
+    fn xsettings_changed(&self) -> Result<(), Error> {
+        // The cursor theme, or the size of its cursors, may have changed.
+        let cursor_rdb = cursor_resource_db(&self.rdb, borrow!(self.xsettings)?.settings());
+        self.cursors
+            .reload(self.connection(), self.screen_num(), &cursor_rdb)
+            .context("XSETTINGS - failed to reload the cursor theme")?;
+        let scale = self.scale();
+        let windows: Vec<_> = borrow!(self.state)?.windows.values().cloned().collect();
+        for window in windows {
+            window.update_click_counter();
+            window.reload_cursor();
+            window
+                .scale_changed(scale)
+                .context("XSETTINGS - failed to change scale")?;
+        }
+        Ok(())
+    }
+
     /// Returns `Ok(true)` if we want to exit the main loop.
-    fn handle_event(&self, ev: &Event) -> Result<bool, Error> {
+    ///
+    /// `coalesced_motion` are the earlier motion events which were merged into a motion event.
+    pub(super) fn handle_event(
+        &self,
+        ev: &Event,
+        coalesced_motion: &[xinput::MotionEvent],
+    ) -> Result<bool, Error> {
         if ev.server_generated() {
             // Update our latest timestamp
             let timestamp = match ev {
@@ -562,6 +1076,25 @@ impl AppInner {
             };
             self.shared.timestamp.set(timestamp);
         }
+        // The XSETTINGS manager's window and the root window are not ours, so these events need to
+        // be filtered out before looking for a window.
+        let xsettings_changed = borrow_mut!(self.xsettings)?
+            .handle_event(self.connection(), self.atoms(), ev)
+            .context("XSETTINGS event handling")?;
+        if let Some(changed) = xsettings_changed {
+            if changed {
+                self.xsettings_changed()?;
+            }
+            return Ok(false);
+        }
+
+        // Modal windows block the input to their parent.
+        if let Some(id) = input_event_window(ev) {
+            if self.window(id).map_or(false, |w| w.has_modal_child()) {
+                return Ok(false);
+            }
+        }
+
         match ev {
             // NOTE: When adding handling for any of the following events,
             //       there must be a check against self.window_id
@@ -623,6 +1156,9 @@ impl AppInner {
             Event::XinputDeviceChanged(ev) if ev.reason == ChangeReason::DEVICE_CHANGE => {
                 self.reinitialize_pointers();
             }
+            Event::XinputDeviceChanged(ev) if ev.reason == ChangeReason::SLAVE_SWITCH => {
+                self.reset_scroll_positions();
+            }
             Event::XinputButtonPress(ev) => {
                 let w = self
                     .window(ev.event)
@@ -652,7 +1188,27 @@ impl AppInner {
                 let w = self
                     .window(ev.event)
                     .context("MOTION_NOTIFY - failed to get window")?;
-                w.handle_motion_notify(ev)?;
+                w.handle_motion_notify(ev, coalesced_motion)?;
+            }
+            Event::XinputEnter(ev) => {
+                let w = self
+                    .window(ev.event)
+                    .context("ENTER - failed to get window")?;
+                w.handle_enter(ev);
+            }
+            Event::XinputLeave(ev) => {
+                let w = self
+                    .window(ev.event)
+                    .context("LEAVE - failed to get window")?;
+                w.handle_leave(ev);
+            }
+            Event::XinputRawMotion(ev) => {
+                // Raw events are reported on the root window, so every window checks whether it
+                // has locked the pointer.
+                let windows: Vec<_> = borrow!(self.state)?.windows.values().cloned().collect();
+                for w in windows {
+                    w.handle_raw_motion(ev);
+                }
             }
             Event::XinputTouchBegin(ev) => {
                 let w = self
@@ -672,11 +1228,41 @@ impl AppInner {
                     .context("TOUCH_UPDATE - failed to get window")?;
                 w.handle_touch_update(ev)?;
             }
-            Event::LeaveNotify(ev) => {
+            Event::XinputGesturePinchBegin(ev) => {
                 let w = self
                     .window(ev.event)
-                    .context("LEAVE_NOTIFY - failed to get window")?;
-                w.handle_leave_notify(ev)?;
+                    .context("GESTURE_PINCH_BEGIN - failed to get window")?;
+                w.handle_gesture_pinch(ev, GesturePhase::Begin);
+            }
+            Event::XinputGesturePinchUpdate(ev) => {
+                let w = self
+                    .window(ev.event)
+                    .context("GESTURE_PINCH_UPDATE - failed to get window")?;
+                w.handle_gesture_pinch(ev, GesturePhase::Update);
+            }
+            Event::XinputGesturePinchEnd(ev) => {
+                let w = self
+                    .window(ev.event)
+                    .context("GESTURE_PINCH_END - failed to get window")?;
+                w.handle_gesture_pinch(ev, GesturePhase::End);
+            }
+            Event::XinputGestureSwipeBegin(ev) => {
+                let w = self
+                    .window(ev.event)
+                    .context("GESTURE_SWIPE_BEGIN - failed to get window")?;
+                w.handle_gesture_swipe(ev, GesturePhase::Begin);
+            }
+            Event::XinputGestureSwipeUpdate(ev) => {
+                let w = self
+                    .window(ev.event)
+                    .context("GESTURE_SWIPE_UPDATE - failed to get window")?;
+                w.handle_gesture_swipe(ev, GesturePhase::Update);
+            }
+            Event::XinputGestureSwipeEnd(ev) => {
+                let w = self
+                    .window(ev.event)
+                    .context("GESTURE_SWIPE_END - failed to get window")?;
+                w.handle_gesture_swipe(ev, GesturePhase::End);
             }
             Event::ClientMessage(ev) => {
                 let w = self
@@ -714,6 +1300,51 @@ impl AppInner {
                         .context("CONFIGURE_NOTIFY - failed to handle")?;
                 }
             }
+            Event::ReparentNotify(ev) => {
+                if ev.window != self.window_id {
+                    let w = self
+                        .window(ev.window)
+                        .context("REPARENT_NOTIFY - failed to get window")?;
+                    w.handle_reparent_notify(ev);
+                }
+            }
+            Event::RandrScreenChangeNotify(_) | Event::RandrNotify(_) => {
+                self.monitor_rects.replace(None);
+                let windows: Vec<_> = borrow!(self.state)?.windows.values().cloned().collect();
+                for w in windows {
+                    w.handle_monitors_changed();
+                }
+            }
+            Event::MapNotify(ev) => {
+                if ev.window != self.window_id {
+                    let w = self
+                        .window(ev.window)
+                        .context("MAP_NOTIFY - failed to get window")?;
+                    w.handle_map_notify(true);
+                }
+            }
+            Event::UnmapNotify(ev) => {
+                if ev.window != self.window_id {
+                    let w = self
+                        .window(ev.window)
+                        .context("UNMAP_NOTIFY - failed to get window")?;
+                    w.handle_map_notify(false);
+                }
+            }
+            Event::VisibilityNotify(ev) => {
+                let w = self
+                    .window(ev.window)
+                    .context("VISIBILITY_NOTIFY - failed to get window")?;
+                w.handle_visibility_notify(*ev);
+            }
+            Event::ShmCompletion(ev) => {
+                // The window may have been destroyed while the server was drawing into it.
+                let w = borrow!(self.state)?.windows.get(&ev.drawable).cloned();
+                if let Some(w) = w {
+                    w.handle_shm_completion(ev)
+                        .context("SHM_COMPLETION - failed to handle")?;
+                }
+            }
             Event::SelectionClear(ev) => {
                 self.clipboard
                     .handle_clear(*ev)
@@ -737,6 +1368,12 @@ impl AppInner {
                 self.primary
                     .handle_property_notify(*ev)
                     .context("PROPERTY_NOTIFY event handling for primary")?;
+                // The clipboards also watch properties on windows that aren't ours.
+                let w = borrow!(self.state)?.windows.get(&ev.window).cloned();
+                if let Some(w) = w {
+                    w.handle_property_notify(ev)
+                        .context("PROPERTY_NOTIFY - failed to handle")?;
+                }
             }
             Event::FocusIn(ev) => {
                 let w = self
@@ -815,8 +1452,22 @@ impl AppInner {
                 .context("Error while waiting for X11 connection")?;
             }
 
+            let mut coalesced_motion = Vec::new();
             while let Some(ev) = event {
-                match self.handle_event(&ev) {
+                event = self.shared.connection.poll_for_event()?;
+                // Runs of queued motion are handled as one event, with the earlier positions
+                // coalesced into it.
+                if let (Event::XinputMotion(motion), Some(Event::XinputMotion(next))) =
+                    (&ev, &event)
+                {
+                    if can_coalesce_motion(motion, next) {
+                        coalesced_motion.push(motion.clone());
+                        continue;
+                    }
+                }
+                let result = self.handle_event(&ev, &coalesced_motion);
+                coalesced_motion.clear();
+                match result {
                     Ok(quit) => {
                         if quit {
                             return Ok(());
@@ -826,7 +1477,6 @@ impl AppInner {
                         tracing::error!("Error handling event: {:#}", e);
                     }
                 }
-                event = self.shared.connection.poll_for_event()?;
             }
 
             let now = Instant::now();
@@ -882,6 +1532,35 @@ impl AppHandle {
     }
 }
 
+/// Whether two motion events can be merged, because only the position of the pointer changed.
+fn can_coalesce_motion(ev: &xinput::MotionEvent, next: &xinput::MotionEvent) -> bool {
+    ev.event == next.event
+        && ev.deviceid == next.deviceid
+        && ev.sourceid == next.sourceid
+        && ev.button_mask == next.button_mask
+        && ev.mods == next.mods
+}
+
+/// The window which receives a keyboard, pointer or touch event that a modal window blocks.
+///
+/// Releases, leaves and the ends of touches and gestures aren't blocked, so that the parent
+/// doesn't miss the end of an interaction which started before the modal window opened.
+fn input_event_window(ev: &Event) -> Option<u32> {
+    match ev {
+        Event::KeyPress(ev) => Some(ev.event),
+        Event::XinputButtonPress(ev) => Some(ev.event),
+        Event::XinputMotion(ev) => Some(ev.event),
+        Event::XinputEnter(ev) => Some(ev.event),
+        Event::XinputTouchBegin(ev) => Some(ev.event),
+        Event::XinputTouchUpdate(ev) => Some(ev.event),
+        Event::XinputGesturePinchBegin(ev) => Some(ev.event),
+        Event::XinputGesturePinchUpdate(ev) => Some(ev.event),
+        Event::XinputGestureSwipeBegin(ev) => Some(ev.event),
+        Event::XinputGestureSwipeUpdate(ev) => Some(ev.event),
+        _ => None,
+    }
+}
+
 /// Clears out our idle pipe; `idle_read` should be the reading end of a pipe that was opened with
 /// O_NONBLOCK.
 fn drain_idle_pipe(idle_read: RawFd) -> Result<(), Error> {
@@ -24,7 +24,8 @@ use x11rb::errors::{ConnectionError, ReplyError, ReplyOrIdError};
 use x11rb::protocol::xproto::{
     Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt, EventMask, GetPropertyReply,
     GetPropertyType, PropMode, Property, PropertyNotifyEvent, SelectionClearEvent,
-    SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+    SelectionNotifyEvent, SelectionRequestEvent, Timestamp, Window, WindowClass,
+    SELECTION_NOTIFY_EVENT,
 };
 use x11rb::protocol::Event;
 use x11rb::wrapper::ConnectionExt as _;
@@ -56,6 +57,13 @@ impl Clipboard {
         ))))
     }
 
+    /// Use the timestamp of another client for reading the selection, instead of our own.
+    ///
+    /// This is needed for XDND, where the drag source tells us which timestamp to use.
+    pub(crate) fn set_transfer_timestamp(&self, timestamp: Timestamp) {
+        self.0.borrow_mut().transfer_timestamp = Some(timestamp);
+    }
+
     pub(crate) fn handle_clear(&self, event: SelectionClearEvent) -> Result<(), ConnectionError> {
         self.0.borrow_mut().handle_clear(event)
     }
@@ -110,6 +118,8 @@ impl Clipboard {
 struct ClipboardState {
     app: Rc<AppShared>,
     selection_name: Atom,
+    /// The timestamp for reading the selection, if it isn't our newest timestamp.
+    transfer_timestamp: Option<Timestamp>,
     contents: Option<ClipboardContents>,
     incremental: Vec<IncrementalTransfer>,
 }
@@ -119,6 +129,7 @@ impl ClipboardState {
         Self {
             app,
             selection_name,
+            transfer_timestamp: None,
             contents: None,
             incremental: Vec::new(),
         }
@@ -249,7 +260,8 @@ impl ClipboardState {
             self.selection_name,
             format_atom,
             TRANSFER_ATOM,
-            self.app.timestamp.get(),
+            self.transfer_timestamp
+                .unwrap_or_else(|| self.app.timestamp.get()),
         )?;
 
         // Now wait for the selection notify event
@@ -0,0 +1,543 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Drag and drop on X11, using the XDND protocol.
+//!
+//! We only implement the target side of version 5 of the protocol. The data is transferred through
+//! the `XdndSelection` selection, exactly like the clipboard.
+//!
+//! https://www.freedesktop.org/wiki/Specifications/XDND/
+
+use std::rc::Rc;
+
+use x11rb::connection::Connection;
+use x11rb::errors::{ConnectionError, ReplyError};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ClientMessageEvent, ConnectionExt as _, EventMask, Timestamp, Window,
+};
+
+use super::application::AppAtoms;
+use super::clipboard::Clipboard;
+use crate::clipboard::FormatId;
+use crate::dnd::DropAction;
+
+/// The version of the XDND protocol that we implement.
+pub(crate) const XDND_VERSION: u32 = 5;
+
+/// The data offered by the source of an XDND drag.
+#[derive(Debug, Clone)]
+pub struct DropData {
+    /// The formats offered by the source, from `XdndEnter` or `XdndTypeList`.
+    formats: Rc<[String]>,
+    /// The `XdndSelection`, owned by the source.
+    selection: Clipboard,
+}
+
+impl DropData {
+    pub(crate) fn new(formats: Vec<String>, selection: Clipboard) -> DropData {
+        DropData {
+            formats: formats.into(),
+            selection,
+        }
+    }
+
+    /// Reads the data with the timestamp of the source, as the XDND specification requires.
+    pub(crate) fn set_timestamp(&self, time: Timestamp) {
+        if time != x11rb::CURRENT_TIME {
+            self.selection.set_transfer_timestamp(time);
+        }
+    }
+
+    pub fn get_string(&self) -> Option<String> {
+        self.selection.get_string()
+    }
+
+    pub fn preferred_format(&self, formats: &[FormatId]) -> Option<FormatId> {
+        formats
+            .iter()
+            .find(|f1| self.formats.iter().any(|f2| *f1 == f2))
+            .copied()
+    }
+
+    pub fn get_format(&self, format: FormatId) -> Option<Vec<u8>> {
+        self.selection.get_format(format)
+    }
+
+    pub fn available_type_names(&self) -> Vec<String> {
+        self.formats.to_vec()
+    }
+}
+
+/// An XDND message from the source of a drag, to a target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum XdndMessage {
+    /// A drag entered the window. The offered formats are read with [`offered_formats`].
+    Enter { source: Window, version: u32 },
+    /// The drag moved, to `root_pos` in root window coordinates. `action` is the atom of the
+    /// action requested by the source.
+    Position {
+        source: Window,
+        root_pos: (i16, i16),
+        time: Timestamp,
+        action: Atom,
+    },
+    /// The drag left the window, or was cancelled.
+    Leave { source: Window },
+    /// The data was dropped onto the window.
+    Drop { source: Window, time: Timestamp },
+}
+
+/// The types of the messages in [`XdndMessage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MessageKind {
+    Enter,
+    Position,
+    Leave,
+    Drop,
+}
+
+impl XdndMessage {
+    /// Parses a client message, or returns `None` if it isn't a message for XDND targets.
+    pub(crate) fn parse(atoms: &AppAtoms, message: &ClientMessageEvent) -> Option<XdndMessage> {
+        let kind = [
+            (atoms.XdndEnter, MessageKind::Enter),
+            (atoms.XdndPosition, MessageKind::Position),
+            (atoms.XdndLeave, MessageKind::Leave),
+            (atoms.XdndDrop, MessageKind::Drop),
+        ]
+        .into_iter()
+        .find_map(|(atom, kind)| (atom == message.type_).then_some(kind))?;
+        if message.format != 32 {
+            return None;
+        }
+        Some(XdndMessage::from_data(kind, message.data.as_data32()))
+    }
+
+    fn from_data(kind: MessageKind, data: [u32; 5]) -> XdndMessage {
+        let source = data[0];
+        match kind {
+            MessageKind::Enter => XdndMessage::Enter {
+                source,
+                version: data[1] >> 24,
+            },
+            MessageKind::Position => XdndMessage::Position {
+                source,
+                root_pos: ((data[2] >> 16) as i16, data[2] as u16 as i16),
+                time: data[3],
+                action: data[4],
+            },
+            MessageKind::Leave => XdndMessage::Leave { source },
+            MessageKind::Drop => XdndMessage::Drop {
+                source,
+                time: data[2],
+            },
+        }
+    }
+}
+
+/// A drag that is currently over a window, as seen by its handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DragInfo<D> {
+    pub data: D,
+    /// The position of the drag, in root window coordinates.
+    pub root_pos: (i16, i16),
+    /// The action requested by the source.
+    pub action: DropAction,
+}
+
+/// What a window needs to do after an XDND message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DropStep<D> {
+    /// The message isn't about the current drag, and needs no reply.
+    Ignore,
+    /// Call `drag_enter`, and reply with `XdndStatus`.
+    Enter(DragInfo<D>),
+    /// Call `drag_over`, and reply with `XdndStatus`.
+    Over(DragInfo<D>),
+    /// Call `drag_leave`.
+    Leave,
+    /// Call `drag_drop`, and reply with `XdndFinished`.
+    Drop(DragInfo<D>),
+    /// Reply with an `XdndFinished` that refuses the drop, after calling `drag_leave` if
+    /// `leave` is set.
+    Refuse { leave: bool },
+}
+
+/// The XDND drag that is over a window, if there is one.
+///
+/// This only keeps track of the protocol, the window does the calls to its handler and the
+/// replies to the source.
+#[derive(Debug)]
+pub(crate) struct DropTarget<D = DropData> {
+    drag: Option<DropState<D>>,
+}
+
+#[derive(Debug)]
+struct DropState<D> {
+    /// The window of the drag source, where our replies go.
+    source: Window,
+    data: D,
+    /// The last position of the drag, in root window coordinates.
+    root_pos: (i16, i16),
+    /// The last action requested by the source.
+    requested: DropAction,
+    /// Whether the handler already got `drag_enter`. XdndEnter doesn't have a position, so we
+    /// wait for the first XdndPosition.
+    entered: bool,
+    /// The action returned by the handler for the last position.
+    action: DropAction,
+}
+
+impl<D> Default for DropTarget<D> {
+    fn default() -> Self {
+        DropTarget { drag: None }
+    }
+}
+
+impl<D: Clone> DropTarget<D> {
+    /// A new drag entered the window. Returns whether the handler needs a `drag_leave` for a
+    /// previous drag, whose source never sent us XdndLeave.
+    pub(crate) fn enter(&mut self, source: Window, data: D) -> bool {
+        let old = self.drag.replace(DropState {
+            source,
+            data,
+            root_pos: (0, 0),
+            requested: DropAction::None,
+            entered: false,
+            action: DropAction::None,
+        });
+        old.is_some_and(|old| old.entered)
+    }
+
+    pub(crate) fn position(
+        &mut self,
+        source: Window,
+        root_pos: (i16, i16),
+        requested: DropAction,
+    ) -> DropStep<D> {
+        match self.drag.as_mut() {
+            Some(drag) if drag.source == source => {
+                drag.root_pos = root_pos;
+                drag.requested = requested;
+                let info = drag.info();
+                if std::mem::replace(&mut drag.entered, true) {
+                    DropStep::Over(info)
+                } else {
+                    DropStep::Enter(info)
+                }
+            }
+            // We missed the XdndEnter, or this is a stale message from an old drag.
+            _ => DropStep::Ignore,
+        }
+    }
+
+    /// Remembers the action that the handler returned for the last position.
+    pub(crate) fn set_action(&mut self, source: Window, action: DropAction) {
+        match self.drag.as_mut() {
+            Some(drag) if drag.source == source => drag.action = action,
+            _ => {}
+        }
+    }
+
+    pub(crate) fn leave(&mut self, source: Window) -> DropStep<D> {
+        match self.drag.take() {
+            Some(drag) if drag.source == source => {
+                if drag.entered {
+                    DropStep::Leave
+                } else {
+                    DropStep::Ignore
+                }
+            }
+            // Not for the current drag, so put it back.
+            drag => {
+                self.drag = drag;
+                DropStep::Ignore
+            }
+        }
+    }
+
+    pub(crate) fn drop(&mut self, source: Window) -> DropStep<D> {
+        match self.drag.take() {
+            Some(drag) if drag.source == source => {
+                if drag.entered && drag.action != DropAction::None {
+                    DropStep::Drop(drag.info())
+                } else {
+                    // We told the source that we don't want the drop, it shouldn't have sent it.
+                    DropStep::Refuse {
+                        leave: drag.entered,
+                    }
+                }
+            }
+            // We don't know about this drag, but the source is waiting for an answer.
+            drag => {
+                self.drag = drag;
+                DropStep::Refuse { leave: false }
+            }
+        }
+    }
+}
+
+impl<D: Clone> DropState<D> {
+    fn info(&self) -> DragInfo<D> {
+        DragInfo {
+            data: self.data.clone(),
+            root_pos: self.root_pos,
+            action: self.requested,
+        }
+    }
+}
+
+/// Reads the formats offered by the source, from an `XdndEnter` message.
+#[allow(clippy::needless_collect)]
+pub(crate) fn offered_formats(
+    conn: &impl Connection,
+    atoms: &AppAtoms,
+    enter: &ClientMessageEvent,
+) -> Result<Vec<String>, ReplyError> {
+    let data = enter.data.as_data32();
+    let source = data[0];
+    // If the source offers more than three formats, they are in the XdndTypeList property.
+    let types: Vec<Atom> = if data[1] & 1 != 0 {
+        conn.get_property(
+            false,
+            source,
+            atoms.XdndTypeList,
+            AtomEnum::ATOM,
+            0,
+            u32::MAX,
+        )?
+        .reply()?
+        .value32()
+        .map(|types| types.collect())
+        .unwrap_or_default()
+    } else {
+        data[2..5]
+            .iter()
+            .copied()
+            .filter(|&atom| atom != x11rb::NONE)
+            .collect()
+    };
+
+    // Send all the requests before waiting for the replies, like in the clipboard.
+    let requests = types
+        .into_iter()
+        .map(|atom| conn.get_atom_name(atom))
+        .collect::<Vec<_>>();
+    let mut formats = Vec::new();
+    for request in requests {
+        if let Ok(name) = String::from_utf8(request?.reply()?.name) {
+            formats.push(name);
+        }
+    }
+    Ok(formats)
+}
+
+/// The action for an action atom of the source, which is `None` for actions that we don't know.
+pub(crate) fn drop_action(atoms: &AppAtoms, atom: Atom) -> DropAction {
+    if atom == atoms.XdndActionCopy {
+        DropAction::Copy
+    } else if atom == atoms.XdndActionMove {
+        DropAction::Move
+    } else if atom == atoms.XdndActionLink {
+        DropAction::Link
+    } else {
+        DropAction::None
+    }
+}
+
+fn action_atom(atoms: &AppAtoms, action: DropAction) -> Atom {
+    match action {
+        DropAction::None => x11rb::NONE,
+        DropAction::Copy => atoms.XdndActionCopy,
+        DropAction::Move => atoms.XdndActionMove,
+        DropAction::Link => atoms.XdndActionLink,
+    }
+}
+
+/// Tells the source whether we would accept a drop at the current position.
+pub(crate) fn send_status(
+    conn: &impl Connection,
+    atoms: &AppAtoms,
+    target: Window,
+    source: Window,
+    action: DropAction,
+) -> Result<(), ConnectionError> {
+    let accept = u32::from(action != DropAction::None);
+    // We leave the "no more positions in this rectangle" rectangle empty, so that the source
+    // keeps sending us positions.
+    let data = [target, accept, 0, 0, action_atom(atoms, action)];
+    let event = ClientMessageEvent::new(32, source, atoms.XdndStatus, data);
+    conn.send_event(false, source, EventMask::NO_EVENT, event)?;
+    conn.flush()
+}
+
+/// Tells the source that we are done with the drop.
+pub(crate) fn send_finished(
+    conn: &impl Connection,
+    atoms: &AppAtoms,
+    target: Window,
+    source: Window,
+    action: DropAction,
+) -> Result<(), ConnectionError> {
+    let accepted = u32::from(action != DropAction::None);
+    let data = [target, accepted, action_atom(atoms, action), 0, 0];
+    let event = ClientMessageEvent::new(32, source, atoms.XdndFinished, data);
+    conn.send_event(false, source, EventMask::NO_EVENT, event)?;
+    conn.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: Window = 0x0120_0001;
+    const OTHER_SOURCE: Window = 0x0140_0001;
+
+    fn info(
+        data: &'static str,
+        root_pos: (i16, i16),
+        action: DropAction,
+    ) -> DragInfo<&'static str> {
+        DragInfo {
+            data,
+            root_pos,
+            action,
+        }
+    }
+
+    #[test]
+    fn parse_messages() {
+        assert_eq!(
+            XdndMessage::from_data(MessageKind::Enter, [SOURCE, 5 << 24 | 1, 31, 0, 0]),
+            XdndMessage::Enter {
+                source: SOURCE,
+                version: 5
+            }
+        );
+        assert_eq!(
+            XdndMessage::from_data(
+                MessageKind::Position,
+                [SOURCE, 0, 100 << 16 | 0xfff6, 1234, 77]
+            ),
+            XdndMessage::Position {
+                source: SOURCE,
+                root_pos: (100, -10),
+                time: 1234,
+                action: 77,
+            }
+        );
+        assert_eq!(
+            XdndMessage::from_data(MessageKind::Leave, [SOURCE, 0, 0, 0, 0]),
+            XdndMessage::Leave { source: SOURCE }
+        );
+        assert_eq!(
+            XdndMessage::from_data(MessageKind::Drop, [SOURCE, 0, 4321, 0, 0]),
+            XdndMessage::Drop {
+                source: SOURCE,
+                time: 4321
+            }
+        );
+    }
+
+    #[test]
+    fn enter_over_drop() {
+        let mut target = DropTarget::default();
+        assert!(!target.enter(SOURCE, "data"));
+        assert_eq!(
+            target.position(SOURCE, (10, 20), DropAction::Copy),
+            DropStep::Enter(info("data", (10, 20), DropAction::Copy))
+        );
+        target.set_action(SOURCE, DropAction::Copy);
+        assert_eq!(
+            target.position(SOURCE, (11, 21), DropAction::Move),
+            DropStep::Over(info("data", (11, 21), DropAction::Move))
+        );
+        target.set_action(SOURCE, DropAction::Move);
+        assert_eq!(
+            target.drop(SOURCE),
+            DropStep::Drop(info("data", (11, 21), DropAction::Move))
+        );
+        // The drag is over, so there is nothing left to drop.
+        assert_eq!(target.drop(SOURCE), DropStep::Refuse { leave: false });
+    }
+
+    #[test]
+    fn refused_drop() {
+        let mut target = DropTarget::default();
+        target.enter(SOURCE, "data");
+        target.position(SOURCE, (10, 20), DropAction::Copy);
+        target.set_action(SOURCE, DropAction::None);
+        assert_eq!(target.drop(SOURCE), DropStep::Refuse { leave: true });
+
+        // A drop without any position never reached the handler.
+        target.enter(SOURCE, "data");
+        assert_eq!(target.drop(SOURCE), DropStep::Refuse { leave: false });
+    }
+
+    #[test]
+    fn leave() {
+        let mut target = DropTarget::default();
+        target.enter(SOURCE, "data");
+        assert_eq!(target.leave(SOURCE), DropStep::Ignore);
+
+        target.enter(SOURCE, "data");
+        target.position(SOURCE, (10, 20), DropAction::Copy);
+        assert_eq!(target.leave(SOURCE), DropStep::Leave);
+        assert_eq!(
+            target.position(SOURCE, (10, 20), DropAction::Copy),
+            DropStep::Ignore
+        );
+    }
+
+    #[test]
+    fn messages_from_other_sources() {
+        let mut target = DropTarget::default();
+        assert_eq!(
+            target.position(SOURCE, (10, 20), DropAction::Copy),
+            DropStep::Ignore
+        );
+
+        target.enter(SOURCE, "data");
+        target.position(SOURCE, (10, 20), DropAction::Copy);
+        target.set_action(SOURCE, DropAction::Copy);
+        assert_eq!(
+            target.position(OTHER_SOURCE, (30, 40), DropAction::Move),
+            DropStep::Ignore
+        );
+        target.set_action(OTHER_SOURCE, DropAction::None);
+        assert_eq!(target.leave(OTHER_SOURCE), DropStep::Ignore);
+        assert_eq!(target.drop(OTHER_SOURCE), DropStep::Refuse { leave: false });
+        // The current drag is unaffected.
+        assert_eq!(
+            target.drop(SOURCE),
+            DropStep::Drop(info("data", (10, 20), DropAction::Copy))
+        );
+    }
+
+    #[test]
+    fn enter_replaces_a_stale_drag() {
+        let mut target = DropTarget::default();
+        target.enter(SOURCE, "old");
+        target.position(SOURCE, (10, 20), DropAction::Copy);
+        assert!(target.enter(OTHER_SOURCE, "new"));
+        assert_eq!(
+            target.position(SOURCE, (10, 20), DropAction::Copy),
+            DropStep::Ignore
+        );
+        assert_eq!(
+            target.position(OTHER_SOURCE, (10, 20), DropAction::Link),
+            DropStep::Enter(info("new", (10, 20), DropAction::Link))
+        );
+    }
+}
@@ -1,20 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use anyhow::bail;
 use memchr::memmem;
 use x11rb::{
     protocol::xinput::{
         self, ConnectionExt as _, DeviceClass, DeviceClassData, DeviceType, EventMask, Fp3232,
-        XIDeviceInfo, XIEventMask,
+        ScrollType, XIDeviceInfo, XIEventMask,
     },
     xcb_ffi::XCBConnection,
 };
 
 use super::application::AppAtoms;
+use crate::kurbo::Vec2;
 
 #[derive(Clone, Debug, Default)]
 pub struct PointersState {
     pub device_infos: HashMap<u16, DeviceInfo>,
+    /// The latest value of each valuator of each device, by device id and valuator index.
+    valuator_values: HashMap<u16, BTreeMap<usize, Fp3232>>,
 }
 
 #[derive(Clone, Debug)]
@@ -26,10 +29,22 @@ pub struct ValuatorInfo {
 }
 
 impl ValuatorInfo {
-    pub fn read(&self, axisvalues: &[Fp3232]) -> Option<f64> {
-        axisvalues
-            .get(self.idx)
-            .map(|x| fixed_to_floating(*x).clamp(self.min, self.max))
+    /// The value of this valuator in an event, if the event has it.
+    pub fn read(&self, valuator_mask: &[u32], axisvalues: &[Fp3232]) -> Option<f64> {
+        valuator_value(valuator_mask, axisvalues, self.idx).map(|x| x.clamp(self.min, self.max))
+    }
+
+    /// The value of this valuator in an event scaled to the range `[0.0, 1.0]`, if the event
+    /// has it.
+    pub fn read_normalized(&self, valuator_mask: &[u32], axisvalues: &[Fp3232]) -> Option<f64> {
+        let range = self.max - self.min;
+        self.read(valuator_mask, axisvalues).map(|x| {
+            if range > 0.0 {
+                (x - self.min) / range
+            } else {
+                0.0
+            }
+        })
     }
 }
 
@@ -45,6 +60,109 @@ impl PointersState {
     pub fn device_info(&self, id: u16) -> Option<&DeviceInfo> {
         self.device_infos.get(&id)
     }
+
+    /// The wheel clicks of an event from the device `id`, from the changes of its scroll
+    /// valuators.
+    pub fn scroll_clicks(&mut self, id: u16, valuator_mask: &[u32], axisvalues: &[Fp3232]) -> Vec2 {
+        let Some(info) = self.device_infos.get_mut(&id) else {
+            return Vec2::ZERO;
+        };
+        let mut clicks = Vec2::ZERO;
+        for scroll in &mut info.scroll_valuators {
+            let Some(value) = valuator_value(valuator_mask, axisvalues, scroll.idx) else {
+                continue;
+            };
+            // The valuators keep counting while the pointer is elsewhere, so there is no change
+            // until the position is known again.
+            if let Some(position) = scroll.position.replace(value) {
+                let delta = (value - position) / scroll.increment;
+                if scroll.horizontal {
+                    clicks.x += delta;
+                } else {
+                    clicks.y += delta;
+                }
+            }
+        }
+        clicks
+    }
+
+    /// The valuators of an event from the device `id`, where the valuators which the event
+    /// leaves out keep their latest values.
+    ///
+    /// Events only carry the values of the valuators which changed, so a pen that stops tilting
+    /// would otherwise seem to stand up straight.
+    pub fn fill_valuators(
+        &mut self,
+        id: u16,
+        valuator_mask: &[u32],
+        axisvalues: &[Fp3232],
+    ) -> (Vec<u32>, Vec<Fp3232>) {
+        let values = self.valuator_values.entry(id).or_default();
+        let set = (0..valuator_mask.len() * 32)
+            .filter(|&idx| valuator_mask[idx / 32] & (1 << (idx % 32)) != 0);
+        for (idx, value) in set.zip(axisvalues) {
+            values.insert(idx, *value);
+        }
+        let mut mask = Vec::new();
+        for &idx in values.keys() {
+            if mask.len() <= idx / 32 {
+                mask.resize(idx / 32 + 1, 0);
+            }
+            mask[idx / 32] |= 1 << (idx % 32);
+        }
+        (mask, values.values().copied().collect())
+    }
+
+    /// Forget the positions of the scroll valuators, after the pointer left the windows or
+    /// switched devices.
+    pub fn reset_scroll_positions(&mut self) {
+        for info in self.device_infos.values_mut() {
+            for scroll in &mut info.scroll_valuators {
+                scroll.position = None;
+            }
+        }
+    }
+}
+
+/// A valuator which accumulates smooth scrolling, from XInput 2.1.
+#[derive(Clone, Debug)]
+pub struct ScrollValuator {
+    pub idx: usize,
+    pub horizontal: bool,
+    /// The change of the value for one wheel click.
+    pub increment: f64,
+    /// The latest value, to compute the change of the next event.
+    pub position: Option<f64>,
+}
+
+impl ScrollValuator {
+    fn from_classes(classes: &[DeviceClass]) -> Vec<ScrollValuator> {
+        classes
+            .iter()
+            .filter_map(|cl| match &cl.data {
+                DeviceClassData::Scroll(scroll) => Some(scroll),
+                _ => None,
+            })
+            .filter_map(|scroll| {
+                let increment = fixed_to_floating(scroll.increment);
+                if increment == 0.0 {
+                    return None;
+                }
+                let position = classes.iter().find_map(|cl| match &cl.data {
+                    DeviceClassData::Valuator(val) if val.number == scroll.number => {
+                        Some(fixed_to_floating(val.value))
+                    }
+                    _ => None,
+                });
+                Some(ScrollValuator {
+                    idx: scroll.number as usize,
+                    horizontal: scroll.scroll_type == ScrollType::HORIZONTAL,
+                    increment,
+                    position,
+                })
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -52,6 +170,12 @@ pub struct PenValuators {
     pub pressure: Option<ValuatorInfo>,
     pub x_tilt: Option<ValuatorInfo>,
     pub y_tilt: Option<ValuatorInfo>,
+    /// The distance of a hovering pen from the tablet.
+    pub distance: Option<ValuatorInfo>,
+    /// The rotation of the pen around its axis, such as for the Wacom Art Pen.
+    pub rotation: Option<ValuatorInfo>,
+    /// The finger wheel of an airbrush.
+    pub throttle: Option<ValuatorInfo>,
 }
 
 impl PenValuators {
@@ -71,6 +195,12 @@ impl PenValuators {
                     ret.x_tilt = Some(info);
                 } else if val.label == atoms.ABS_YTILT && ret.y_tilt.is_none() {
                     ret.y_tilt = Some(info);
+                } else if val.label == atoms.ABS_DISTANCE && ret.distance.is_none() {
+                    ret.distance = Some(info);
+                } else if val.label == atoms.ABS_RZ && ret.rotation.is_none() {
+                    ret.rotation = Some(info);
+                } else if val.label == atoms.ABS_THROTTLE && ret.throttle.is_none() {
+                    ret.throttle = Some(info);
                 }
             }
         }
@@ -89,6 +219,7 @@ pub struct DeviceInfo {
     pub device_type: DeviceType,
     pub device_kind: DeviceKind,
     pub valuators: PenValuators,
+    pub scroll_valuators: Vec<ScrollValuator>,
 }
 
 impl std::fmt::Debug for DeviceInfo {
@@ -99,6 +230,7 @@ impl std::fmt::Debug for DeviceInfo {
             .field("device_type", &self.device_type)
             .field("device_kind", &self.device_kind)
             .field("valuators", &self.valuators)
+            .field("scroll_valuators", &self.scroll_valuators)
             .finish()
     }
 }
@@ -123,6 +255,7 @@ impl DeviceInfo {
             device_type: dev.type_,
             device_kind: DeviceKind::Mouse,
             valuators: PenValuators::new(&dev.classes, atoms),
+            scroll_valuators: ScrollValuator::from_classes(&dev.classes),
         };
 
         ret.detect_device_kind(&dev.classes);
@@ -188,24 +321,211 @@ pub(crate) fn initialize_pointers(
     )?
     .check()?;
 
-    Ok(PointersState { device_infos })
+    Ok(PointersState {
+        device_infos,
+        valuator_values: HashMap::new(),
+    })
 }
 
-pub(crate) fn enable_window_pointers(conn: &XCBConnection, window: u32) -> anyhow::Result<()> {
+/// Select the pointer events of `window`, with the touchpad gestures if the server has
+/// XInput 2.4.
+pub(crate) fn enable_window_pointers(
+    conn: &XCBConnection,
+    window: u32,
+    gestures: bool,
+) -> anyhow::Result<()> {
+    let mut mask = vec![
+        XIEventMask::BUTTON_PRESS
+            | XIEventMask::BUTTON_RELEASE
+            | XIEventMask::MOTION
+            | XIEventMask::ENTER
+            | XIEventMask::LEAVE
+            | XIEventMask::TOUCH_BEGIN
+            | XIEventMask::TOUCH_UPDATE
+            | XIEventMask::TOUCH_END,
+    ];
+    if gestures {
+        // `XIEventMask` doesn't have the gesture events, and the last one doesn't even fit in
+        // its first word, so we set their bits from the event codes.
+        for event in [
+            xinput::GESTURE_PINCH_BEGIN_EVENT,
+            xinput::GESTURE_PINCH_UPDATE_EVENT,
+            xinput::GESTURE_PINCH_END_EVENT,
+            xinput::GESTURE_SWIPE_BEGIN_EVENT,
+            xinput::GESTURE_SWIPE_UPDATE_EVENT,
+            xinput::GESTURE_SWIPE_END_EVENT,
+        ] {
+            let word = usize::from(event / 32);
+            if mask.len() <= word {
+                mask.resize(word + 1, XIEventMask::from(0u32));
+            }
+            mask[word] |= 1u32 << (event % 32);
+        }
+    }
     conn.xinput_xi_select_events(
         window,
         &[EventMask {
             deviceid: xinput::Device::ALL_MASTER.into(),
-            mask: vec![
-                (XIEventMask::BUTTON_PRESS
-                    | XIEventMask::BUTTON_RELEASE
-                    | XIEventMask::MOTION
-                    | XIEventMask::TOUCH_BEGIN
-                    | XIEventMask::TOUCH_UPDATE
-                    | XIEventMask::TOUCH_END),
-            ],
+            mask,
         }],
     )?
     .check()?;
     Ok(())
 }
+
+/// Select or deselect the raw motion of all the master pointers, which is reported on the root
+/// window even while the pointer can't move.
+pub(crate) fn select_raw_motion(
+    conn: &XCBConnection,
+    root: u32,
+    enabled: bool,
+) -> anyhow::Result<()> {
+    let mask = if enabled {
+        XIEventMask::RAW_MOTION
+    } else {
+        XIEventMask::from(0u32)
+    };
+    conn.xinput_xi_select_events(
+        root,
+        &[EventMask {
+            deviceid: xinput::Device::ALL_MASTER.into(),
+            mask: vec![mask],
+        }],
+    )?
+    .check()?;
+    Ok(())
+}
+
+/// The value of the valuator `idx` in an event, if the event has it.
+///
+/// Events only carry the values of the valuators which are set in their mask, in order.
+pub(crate) fn valuator_value(
+    valuator_mask: &[u32],
+    axisvalues: &[Fp3232],
+    idx: usize,
+) -> Option<f64> {
+    let is_set = |idx: usize| {
+        valuator_mask
+            .get(idx / 32)
+            .map_or(false, |bits| bits & (1 << (idx % 32)) != 0)
+    };
+    if !is_set(idx) {
+        return None;
+    }
+    let position = (0..idx).filter(|&i| is_set(i)).count();
+    axisvalues
+        .get(position)
+        .map(|value| fixed_to_floating(*value))
+}
+
+/// The motion along the x and y axes of a raw event.
+pub(crate) fn raw_motion_delta(valuator_mask: &[u32], axisvalues: &[Fp3232]) -> Vec2 {
+    Vec2::new(
+        valuator_value(valuator_mask, axisvalues, 0).unwrap_or(0.0),
+        valuator_value(valuator_mask, axisvalues, 1).unwrap_or(0.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed(value: i32) -> Fp3232 {
+        Fp3232 {
+            integral: value,
+            frac: 0,
+        }
+    }
+
+    #[test]
+    fn raw_motion_delta_skips_unset_axes() {
+        assert_eq!(
+            raw_motion_delta(&[0b11], &[fixed(3), fixed(-2)]),
+            Vec2::new(3.0, -2.0)
+        );
+        assert_eq!(
+            raw_motion_delta(&[0b10], &[fixed(-2)]),
+            Vec2::new(0.0, -2.0)
+        );
+        assert_eq!(
+            raw_motion_delta(&[0b101], &[fixed(4), fixed(9)]),
+            Vec2::new(4.0, 0.0)
+        );
+        assert_eq!(raw_motion_delta(&[], &[]), Vec2::ZERO);
+    }
+
+    #[test]
+    fn valuator_value_counts_set_bits() {
+        let mask = [0b1001, 0b1];
+        let values = [fixed(1), fixed(2), fixed(3)];
+        assert_eq!(valuator_value(&mask, &values, 0), Some(1.0));
+        assert_eq!(valuator_value(&mask, &values, 1), None);
+        assert_eq!(valuator_value(&mask, &values, 3), Some(2.0));
+        assert_eq!(valuator_value(&mask, &values, 32), Some(3.0));
+        assert_eq!(valuator_value(&mask, &values, 64), None);
+    }
+
+    #[test]
+    fn valuator_info_reads_through_the_mask() {
+        let info = ValuatorInfo {
+            idx: 3,
+            min: 0.0,
+            max: 2048.0,
+            resolution: 0,
+        };
+        let values = [fixed(10), fixed(512)];
+        assert_eq!(info.read(&[0b1001], &values), Some(512.0));
+        assert_eq!(info.read_normalized(&[0b1001], &values), Some(0.25));
+        assert_eq!(info.read(&[0b11], &values), None);
+        assert_eq!(info.read(&[0b1001], &[fixed(0), fixed(4096)]), Some(2048.0));
+    }
+
+    #[test]
+    fn valuators_keep_their_latest_value() {
+        let mut state = PointersState::default();
+        let (mask, values) = state.fill_valuators(2, &[0b101], &[fixed(1), fixed(3)]);
+        assert_eq!(mask, [0b101]);
+        assert_eq!(values, [fixed(1), fixed(3)]);
+        let (mask, values) = state.fill_valuators(2, &[0b110], &[fixed(4), fixed(5)]);
+        assert_eq!(valuator_value(&mask, &values, 0), Some(1.0));
+        assert_eq!(valuator_value(&mask, &values, 1), Some(4.0));
+        assert_eq!(valuator_value(&mask, &values, 2), Some(5.0));
+        // Valuators past the first word of the mask
+        let (mask, values) = state.fill_valuators(2, &[0, 0b1], &[fixed(6)]);
+        assert_eq!(valuator_value(&mask, &values, 32), Some(6.0));
+        assert_eq!(valuator_value(&mask, &values, 2), Some(5.0));
+        // Each device has its own values
+        let (mask, values) = state.fill_valuators(3, &[], &[]);
+        assert_eq!(valuator_value(&mask, &values, 0), None);
+    }
+
+    #[test]
+    fn scroll_clicks_need_a_known_position() {
+        let mut state = PointersState::default();
+        state.device_infos.insert(
+            2,
+            DeviceInfo {
+                id: 2,
+                name: b"touchpad".to_vec(),
+                device_type: DeviceType::SLAVE_POINTER,
+                device_kind: DeviceKind::Mouse,
+                valuators: PenValuators::default(),
+                scroll_valuators: vec![ScrollValuator {
+                    idx: 2,
+                    horizontal: false,
+                    increment: 15.0,
+                    position: None,
+                }],
+            },
+        );
+        let mask = [0b100];
+        assert_eq!(state.scroll_clicks(2, &mask, &[fixed(30)]), Vec2::ZERO);
+        assert_eq!(
+            state.scroll_clicks(2, &mask, &[fixed(60)]),
+            Vec2::new(0.0, 2.0)
+        );
+        assert_eq!(state.scroll_clicks(3, &mask, &[fixed(90)]), Vec2::ZERO);
+        state.reset_scroll_positions();
+        assert_eq!(state.scroll_clicks(2, &mask, &[fixed(0)]), Vec2::ZERO);
+    }
+}
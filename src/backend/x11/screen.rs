@@ -17,34 +17,33 @@
 use x11rb::connection::Connection;
 use x11rb::errors::ReplyOrIdError;
 use x11rb::protocol::randr::{self, ConnectionExt as _, Crtc};
-use x11rb::protocol::xproto::{Screen, Timestamp};
+use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt as _, Screen, Timestamp, Window};
 
-use crate::kurbo::Rect;
+use crate::kurbo::{Rect, Size};
 use crate::screen::Monitor;
 
-use super::application::Application;
+use super::application::{AppAtoms, Application};
 
-fn monitor<Pos>(primary: bool, (x, y): (Pos, Pos), (width, height): (u16, u16)) -> Monitor
+/// A monitor as reported by the X server, before its work area is known.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct RawMonitor {
+    primary: bool,
+    rect: Rect,
+}
+
+fn monitor<Pos>(primary: bool, (x, y): (Pos, Pos), (width, height): (u16, u16)) -> RawMonitor
 where
     Pos: Into<i32>,
 {
-    let rect = Rect::from_points(
+    let rect = Rect::from_origin_size(
         (x.into() as f64, y.into() as f64),
         (width as f64, height as f64),
     );
-    // TODO: Support for work_rect. It's complicated...
-    Monitor::new(primary, rect, rect)
+    RawMonitor { primary, rect }
 }
 
 pub(crate) fn get_monitors(app: &Application) -> Vec<Monitor> {
-    // let (conn, screen_num) = match x11rb::connect(None) {
-    //     Ok(res) => res,
-    //     Err(err) => {
-    //         tracing::error!("Error in Screen::get_monitors(): {:?}", err);
-    //         return Vec::new();
-    //     }
-    // };
-    let result = get_monitors_impl(app.connection(), app.screen_num());
+    let result = get_monitors_impl(app.connection(), app.screen_num(), app.atoms());
 
     match result {
         Ok(monitors) => monitors,
@@ -58,9 +57,28 @@ pub(crate) fn get_monitors(app: &Application) -> Vec<Monitor> {
 fn get_monitors_impl(
     conn: &impl Connection,
     screen_num: usize,
+    atoms: &AppAtoms,
 ) -> Result<Vec<Monitor>, ReplyOrIdError> {
     let screen = &conn.setup().roots[screen_num];
+    let monitors = dedup_monitors(get_raw_monitors(conn, screen)?);
+    let work_rects = match get_work_rects(conn, screen, atoms, &monitors) {
+        Ok(work_rects) => work_rects,
+        Err(err) => {
+            tracing::warn!("Failed to get the monitor work areas: {:?}", err);
+            monitors.iter().map(|monitor| monitor.rect).collect()
+        }
+    };
+    Ok(monitors
+        .iter()
+        .zip(work_rects)
+        .map(|(monitor, work_rect)| Monitor::new(monitor.primary, monitor.rect, work_rect))
+        .collect())
+}
 
+fn get_raw_monitors(
+    conn: &impl Connection,
+    screen: &Screen,
+) -> Result<Vec<RawMonitor>, ReplyOrIdError> {
     if conn
         .extension_information(randr::X11_EXTENSION_NAME)?
         .is_none()
@@ -79,7 +97,7 @@ fn get_monitors_impl(
     }
 }
 
-fn get_monitors_core(screen: &Screen) -> Result<Vec<Monitor>, ReplyOrIdError> {
+fn get_monitors_core(screen: &Screen) -> Result<Vec<RawMonitor>, ReplyOrIdError> {
     Ok(vec![monitor(
         true,
         (0, 0),
@@ -90,7 +108,7 @@ fn get_monitors_core(screen: &Screen) -> Result<Vec<Monitor>, ReplyOrIdError> {
 fn get_monitors_randr_monitors(
     conn: &impl Connection,
     screen: &Screen,
-) -> Result<Vec<Monitor>, ReplyOrIdError> {
+) -> Result<Vec<RawMonitor>, ReplyOrIdError> {
     let result = conn
         .randr_get_monitors(screen.root, true)?
         .reply()?
@@ -104,7 +122,7 @@ fn get_monitors_randr_monitors(
 fn get_monitors_randr_screen_resources_current(
     conn: &impl Connection,
     screen: &Screen,
-) -> Result<Vec<Monitor>, ReplyOrIdError> {
+) -> Result<Vec<RawMonitor>, ReplyOrIdError> {
     let reply = conn
         .randr_get_screen_resources_current(screen.root)?
         .reply()?;
@@ -114,7 +132,7 @@ fn get_monitors_randr_screen_resources_current(
 fn get_monitors_randr_screen_resources(
     conn: &impl Connection,
     screen: &Screen,
-) -> Result<Vec<Monitor>, ReplyOrIdError> {
+) -> Result<Vec<RawMonitor>, ReplyOrIdError> {
     let reply = conn.randr_get_screen_resources(screen.root)?.reply()?;
     get_monitors_randr_crtcs_timestamp(conn, &reply.crtcs, reply.config_timestamp)
 }
@@ -126,7 +144,7 @@ fn get_monitors_randr_crtcs_timestamp(
     conn: &impl Connection,
     crtcs: &[Crtc],
     config_timestamp: Timestamp,
-) -> Result<Vec<Monitor>, ReplyOrIdError> {
+) -> Result<Vec<RawMonitor>, ReplyOrIdError> {
     // Request information about all CRTCs
     let requests = crtcs
         .iter()
@@ -147,8 +165,415 @@ fn get_monitors_randr_crtcs_timestamp(
             ));
         }
     }
-    // TODO: I think we need to deduplicate monitors. In clone mode, each "clone" appears as its
-    // own monitor otherwise.
 
     Ok(result)
 }
+
+/// Merges monitors that show the same part of the screen.
+///
+/// In clone mode, each cloned output is reported as its own CRTC (or RandR monitor). A clone that
+/// runs at a lower resolution only shows the top left part of the larger output, so any monitor
+/// that is contained in another one is merged into it.
+fn dedup_monitors(monitors: Vec<RawMonitor>) -> Vec<RawMonitor> {
+    let mut result: Vec<RawMonitor> = Vec::with_capacity(monitors.len());
+    for monitor in monitors {
+        let existing = result.iter_mut().find(|existing| {
+            let union = existing.rect.union(monitor.rect);
+            union == existing.rect || union == monitor.rect
+        });
+        match existing {
+            Some(existing) => {
+                existing.rect = existing.rect.union(monitor.rect);
+                existing.primary |= monitor.primary;
+            }
+            None => result.push(monitor),
+        }
+    }
+    result
+}
+
+/// The rectangles of the monitors, in pixels.
+///
+/// This is cheaper than [`get_monitors`], because it doesn't look up the work areas.
+pub(crate) fn get_monitor_rects(conn: &impl Connection, screen_num: usize) -> Vec<Rect> {
+    let screen = &conn.setup().roots[screen_num];
+    match get_raw_monitors(conn, screen) {
+        Ok(monitors) => dedup_monitors(monitors)
+            .into_iter()
+            .map(|monitor| monitor.rect)
+            .collect(),
+        Err(err) => {
+            tracing::error!("Failed to get the monitors: {:?}", err);
+            Vec::new()
+        }
+    }
+}
+
+/// The rectangle of the monitor that shows the largest part of `rect`, in pixels.
+///
+/// This uses the monitors which the application has cached, so it can be called whenever a
+/// window moves.
+pub(crate) fn monitor_rect_at(app: &Application, rect: Rect) -> Option<Rect> {
+    largest_intersection(&app.monitor_rects(), rect)
+}
+
+fn largest_intersection(monitors: &[Rect], rect: Rect) -> Option<Rect> {
+    monitors
+        .iter()
+        .map(|monitor| (*monitor, monitor.intersect(rect).area()))
+        .filter(|(_, area)| *area > 0.)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(rect, _)| rect)
+}
+
+/// The space reserved by a panel or dock at the edges of the root window.
+///
+/// This is the contents of the `_NET_WM_STRUT_PARTIAL` property, in root window coordinates.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Strut {
+    left: f64,
+    right: f64,
+    top: f64,
+    bottom: f64,
+    left_start_y: f64,
+    left_end_y: f64,
+    right_start_y: f64,
+    right_end_y: f64,
+    top_start_x: f64,
+    top_end_x: f64,
+    bottom_start_x: f64,
+    bottom_end_x: f64,
+}
+
+impl Strut {
+    fn from_partial(values: &[u32]) -> Option<Strut> {
+        let v = |idx: usize| values.get(idx).map(|&val| val as f64);
+        Some(Strut {
+            left: v(0)?,
+            right: v(1)?,
+            top: v(2)?,
+            bottom: v(3)?,
+            left_start_y: v(4)?,
+            left_end_y: v(5)?,
+            right_start_y: v(6)?,
+            right_end_y: v(7)?,
+            top_start_x: v(8)?,
+            top_end_x: v(9)?,
+            bottom_start_x: v(10)?,
+            bottom_end_x: v(11)?,
+        })
+    }
+
+    /// `_NET_WM_STRUT` is the older form, which always spans the whole edge of the root window.
+    fn from_full(values: &[u32], root_size: Size) -> Option<Strut> {
+        let v = |idx: usize| values.get(idx).map(|&val| val as f64);
+        Some(Strut {
+            left: v(0)?,
+            right: v(1)?,
+            top: v(2)?,
+            bottom: v(3)?,
+            left_start_y: 0.,
+            left_end_y: root_size.height,
+            right_start_y: 0.,
+            right_end_y: root_size.height,
+            top_start_x: 0.,
+            top_end_x: root_size.width,
+            bottom_start_x: 0.,
+            bottom_end_x: root_size.width,
+        })
+    }
+
+    /// Shrinks the work area of the monitor at `monitor` by the parts of this strut that cover it.
+    fn apply(&self, monitor: Rect, work: Rect, root_size: Size) -> Rect {
+        // The end coordinates are inclusive.
+        let left = Rect::new(0., self.left_start_y, self.left, self.left_end_y + 1.);
+        let right = Rect::new(
+            root_size.width - self.right,
+            self.right_start_y,
+            root_size.width,
+            self.right_end_y + 1.,
+        );
+        let top = Rect::new(self.top_start_x, 0., self.top_end_x + 1., self.top);
+        let bottom = Rect::new(
+            self.bottom_start_x,
+            root_size.height - self.bottom,
+            self.bottom_end_x + 1.,
+            root_size.height,
+        );
+        let covers =
+            |reserved: Rect| reserved.area() > 0. && monitor.intersect(reserved).area() > 0.;
+
+        let mut work = work;
+        if covers(left) {
+            work.x0 = work.x0.max(left.x1);
+        }
+        if covers(right) {
+            work.x1 = work.x1.min(right.x0);
+        }
+        if covers(top) {
+            work.y0 = work.y0.max(top.y1);
+        }
+        if covers(bottom) {
+            work.y1 = work.y1.min(bottom.y0);
+        }
+        work
+    }
+}
+
+fn work_rect_from_struts(monitor: Rect, struts: &[Strut], root_size: Size) -> Rect {
+    let work = struts
+        .iter()
+        .fold(monitor, |work, strut| strut.apply(monitor, work, root_size));
+    if work.area() > 0. {
+        work
+    } else {
+        monitor
+    }
+}
+
+/// Picks the work area that belongs to `monitor` out of a list of work areas, such as the one in
+/// `_GTK_WORKAREAS_Dn` or `_NET_WORKAREA`.
+fn work_rect_from_areas(monitor: Rect, areas: &[Rect]) -> Rect {
+    areas
+        .iter()
+        .map(|area| area.intersect(monitor))
+        .find(|work| work.area() > 0.)
+        .unwrap_or(monitor)
+}
+
+fn rects_from_cardinals(values: &[u32]) -> Vec<Rect> {
+    values
+        .chunks_exact(4)
+        .map(|area| {
+            Rect::from_origin_size(
+                (area[0] as i32 as f64, area[1] as i32 as f64),
+                (area[2] as f64, area[3] as f64),
+            )
+        })
+        .collect()
+}
+
+fn get_cardinals(
+    conn: &impl Connection,
+    window: Window,
+    property: Atom,
+) -> Result<Option<Vec<u32>>, ReplyOrIdError> {
+    let reply = conn
+        .get_property(false, window, property, AtomEnum::CARDINAL, 0, u32::MAX)?
+        .reply()?;
+    Ok(reply.value32().map(|values| values.collect()))
+}
+
+fn get_work_rects(
+    conn: &impl Connection,
+    screen: &Screen,
+    atoms: &AppAtoms,
+    monitors: &[RawMonitor],
+) -> Result<Vec<Rect>, ReplyOrIdError> {
+    let root = screen.root;
+    let root_size = Size::new(
+        screen.width_in_pixels as f64,
+        screen.height_in_pixels as f64,
+    );
+    let desktop = get_cardinals(conn, root, atoms._NET_CURRENT_DESKTOP)?
+        .and_then(|values| values.first().copied())
+        .unwrap_or(0) as usize;
+
+    // Mutter publishes one work area per monitor for each desktop, which is the most accurate
+    // source we can get.
+    let gtk_workareas = format!("_GTK_WORKAREAS_D{}", desktop);
+    let gtk_workareas = conn
+        .intern_atom(true, gtk_workareas.as_bytes())?
+        .reply()?
+        .atom;
+    if gtk_workareas != x11rb::NONE {
+        if let Some(values) = get_cardinals(conn, root, gtk_workareas)? {
+            let areas = rects_from_cardinals(&values);
+            if !areas.is_empty() {
+                return Ok(monitors
+                    .iter()
+                    .map(|monitor| work_rect_from_areas(monitor.rect, &areas))
+                    .collect());
+            }
+        }
+    }
+
+    // _NET_WORKAREA is a single rectangle for the whole root window, which is meaningless for
+    // monitors that don't touch the panels. If we can, we compute the work areas from the struts
+    // of all the panels instead.
+    if monitors.len() > 1 {
+        if let Some(struts) = get_struts(conn, root, atoms, root_size)? {
+            return Ok(monitors
+                .iter()
+                .map(|monitor| work_rect_from_struts(monitor.rect, &struts, root_size))
+                .collect());
+        }
+    }
+
+    if let Some(values) = get_cardinals(conn, root, atoms._NET_WORKAREA)? {
+        if let Some(area) = rects_from_cardinals(&values).get(desktop) {
+            return Ok(monitors
+                .iter()
+                .map(|monitor| work_rect_from_areas(monitor.rect, &[*area]))
+                .collect());
+        }
+    }
+
+    Ok(monitors.iter().map(|monitor| monitor.rect).collect())
+}
+
+// Like with the CRTCs, we send all the requests before looking at any of the replies to save
+// round-trips.
+#[allow(clippy::needless_collect)]
+fn get_struts(
+    conn: &impl Connection,
+    root: Window,
+    atoms: &AppAtoms,
+    root_size: Size,
+) -> Result<Option<Vec<Strut>>, ReplyOrIdError> {
+    let clients = conn
+        .get_property(
+            false,
+            root,
+            atoms._NET_CLIENT_LIST,
+            AtomEnum::WINDOW,
+            0,
+            u32::MAX,
+        )?
+        .reply()?;
+    let Some(clients) = clients.value32() else {
+        // The window manager doesn't support EWMH
+        return Ok(None);
+    };
+
+    let requests = clients
+        .map(|client| {
+            let partial = conn.get_property(
+                false,
+                client,
+                atoms._NET_WM_STRUT_PARTIAL,
+                AtomEnum::CARDINAL,
+                0,
+                12,
+            );
+            let full =
+                conn.get_property(false, client, atoms._NET_WM_STRUT, AtomEnum::CARDINAL, 0, 4);
+            (partial, full)
+        })
+        .collect::<Vec<_>>();
+
+    let mut struts = Vec::new();
+    for (partial, full) in requests.into_iter() {
+        // Clients can disappear at any time, so errors on the reply are not fatal.
+        let partial = partial?.reply().ok();
+        let full = full?.reply().ok();
+        let partial = partial
+            .as_ref()
+            .and_then(|reply| reply.value32())
+            .map(|values| values.collect::<Vec<_>>())
+            .and_then(|values| Strut::from_partial(&values));
+        let strut = partial.or_else(|| {
+            full.as_ref()
+                .and_then(|reply| reply.value32())
+                .map(|values| values.collect::<Vec<_>>())
+                .and_then(|values| Strut::from_full(&values, root_size))
+        });
+        struts.extend(strut);
+    }
+    Ok(Some(struts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_are_merged() {
+        let monitors = vec![
+            monitor(true, (0, 0), (1920, 1080)),
+            monitor(false, (0, 0), (1920, 1080)),
+            monitor(false, (0, 0), (1280, 720)),
+            monitor(false, (1920, 0), (1920, 1080)),
+        ];
+        assert_eq!(
+            dedup_monitors(monitors),
+            vec![
+                monitor(true, (0, 0), (1920, 1080)),
+                monitor(false, (1920, 0), (1920, 1080)),
+            ]
+        );
+    }
+
+    #[test]
+    fn smaller_clone_keeps_primary() {
+        let monitors = vec![
+            monitor(false, (0, 0), (1280, 720)),
+            monitor(true, (0, 0), (1920, 1080)),
+        ];
+        assert_eq!(
+            dedup_monitors(monitors),
+            vec![monitor(true, (0, 0), (1920, 1080))]
+        );
+    }
+
+    #[test]
+    fn window_belongs_to_monitor_with_largest_part() {
+        let monitors = [
+            Rect::new(0., 0., 1920., 1080.),
+            Rect::new(1920., 0., 3840., 1080.),
+        ];
+        let mostly_left = Rect::new(1000., 100., 2100., 600.);
+        let mostly_right = Rect::new(1800., 100., 2900., 600.);
+        let offscreen = Rect::new(4000., 100., 4500., 600.);
+        assert_eq!(
+            largest_intersection(&monitors, mostly_left),
+            Some(Rect::new(0., 0., 1920., 1080.))
+        );
+        assert_eq!(
+            largest_intersection(&monitors, mostly_right),
+            Some(Rect::new(1920., 0., 3840., 1080.))
+        );
+        assert_eq!(largest_intersection(&monitors, offscreen), None);
+    }
+
+    #[test]
+    fn struts_only_affect_covered_monitors() {
+        let root_size = Size::new(3840., 1080.);
+        let left = Rect::new(0., 0., 1920., 1080.);
+        let right = Rect::new(1920., 0., 3840., 1080.);
+        // A 32px high panel at the top of the left monitor only.
+        let panel = Strut {
+            top: 32.,
+            top_start_x: 0.,
+            top_end_x: 1919.,
+            ..Default::default()
+        };
+        assert_eq!(
+            work_rect_from_struts(left, &[panel], root_size),
+            Rect::new(0., 32., 1920., 1080.)
+        );
+        assert_eq!(work_rect_from_struts(right, &[panel], root_size), right);
+
+        // A dock on the right edge of the root window.
+        let dock = Strut::from_full(&[0, 64, 0, 0], root_size).unwrap();
+        assert_eq!(work_rect_from_struts(left, &[dock], root_size), left);
+        assert_eq!(
+            work_rect_from_struts(right, &[panel, dock], root_size),
+            Rect::new(1920., 0., 3776., 1080.)
+        );
+    }
+
+    #[test]
+    fn work_areas_are_matched_to_monitors() {
+        let areas = rects_from_cardinals(&[0, 27, 1920, 1053, 1920, 0, 1920, 1080]);
+        let left = Rect::new(0., 0., 1920., 1080.);
+        let right = Rect::new(1920., 0., 3840., 1080.);
+        let other = Rect::new(0., 1080., 1920., 2160.);
+        assert_eq!(
+            work_rect_from_areas(left, &areas),
+            Rect::new(0., 27., 1920., 1080.)
+        );
+        assert_eq!(work_rect_from_areas(right, &areas), right);
+        assert_eq!(work_rect_from_areas(other, &areas), other);
+    }
+}
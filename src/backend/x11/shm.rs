@@ -0,0 +1,252 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Showing a [`PixelBuffer`] in a window, through MIT-SHM or plain `PutImage` requests.
+
+use std::ffi::CStr;
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::FromRawFd;
+
+use anyhow::{anyhow, Context, Error};
+use nix::sys::memfd::{memfd_create, MemFdCreateFlag};
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::shm::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{self, ConnectionExt as _, ImageFormat, ImageOrder};
+use x11rb::xcb_ffi::XCBConnection;
+
+use crate::pixels::{PixelBuffer, PixelRect};
+
+/// The state needed to copy pixels into one window.
+pub(super) struct Presenter {
+    window: xproto::Window,
+    depth: u8,
+    opaque: bool,
+    gc: xproto::Gcontext,
+    /// The shared memory segment, which grows as needed.
+    segment: Option<Segment>,
+    /// Whether to try MIT-SHM. This is cleared if attaching a segment fails, which happens when
+    /// the X server is on another machine.
+    use_shm: bool,
+    /// Whether the server may still be reading the segment. This is set by `put_shm`, and
+    /// cleared when the completion event of its last request arrives.
+    busy: bool,
+}
+
+struct Segment {
+    id: shm::Seg,
+    file: File,
+    len: usize,
+}
+
+impl Presenter {
+    pub(super) fn new(
+        conn: &XCBConnection,
+        window: xproto::Window,
+        depth: u8,
+        opaque: bool,
+        shm_supported: bool,
+    ) -> Result<Presenter, Error> {
+        let format = conn
+            .setup()
+            .pixmap_formats
+            .iter()
+            .find(|format| format.depth == depth)
+            .ok_or_else(|| anyhow!("no pixmap format for depth {}", depth))?;
+        if format.bits_per_pixel != 32 {
+            return Err(anyhow!(
+                "pixmap format for depth {} has {} bits per pixel, expected 32",
+                depth,
+                format.bits_per_pixel
+            ));
+        }
+
+        let gc = conn.generate_id()?;
+        conn.create_gc(
+            gc,
+            window,
+            &xproto::CreateGCAux::new().graphics_exposures(0),
+        )?
+        .check()
+        .context("create graphics context")?;
+        Ok(Presenter {
+            window,
+            depth,
+            opaque,
+            gc,
+            segment: None,
+            use_shm: shm_supported,
+            busy: false,
+        })
+    }
+
+    /// Copy the `rects` of `buffer` into the window.
+    pub(super) fn present(
+        &mut self,
+        conn: &XCBConnection,
+        buffer: &PixelBuffer,
+        rects: &[PixelRect],
+    ) -> Result<(), Error> {
+        // Each rectangle is converted into the server's layout, one after the other.
+        let len = rects
+            .iter()
+            .map(|rect| rect.width as usize * rect.height as usize * 4)
+            .sum();
+        if len == 0 {
+            return Ok(());
+        }
+        let mut data = vec![0; len];
+        let mut offset = 0;
+        for rect in rects {
+            let stride = rect.width as usize * 4;
+            let end = offset + stride * rect.height as usize;
+            buffer.copy_bgra(*rect, &mut data[offset..end], stride, self.opaque);
+            offset = end;
+        }
+        if conn.setup().image_byte_order == ImageOrder::MSB_FIRST {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.reverse();
+            }
+        }
+
+        // If the server hasn't finished with the segment yet, this frame is sent in the requests
+        // instead of waiting for it.
+        if self.use_shm && !self.busy {
+            match self.ensure_segment(conn, len) {
+                Ok(()) => return self.put_shm(conn, rects, &data),
+                Err(err) => {
+                    tracing::warn!(
+                        "MIT-SHM is unavailable, falling back to PutImage: {:#}",
+                        err
+                    );
+                    self.use_shm = false;
+                }
+            }
+        }
+        self.put_image(conn, rects, &data)
+    }
+
+    /// Make sure that there is a segment of at least `len` bytes.
+    fn ensure_segment(&mut self, conn: &XCBConnection, len: usize) -> Result<(), Error> {
+        if matches!(&self.segment, Some(segment) if segment.len >= len) {
+            return Ok(());
+        }
+        if let Some(segment) = self.segment.take() {
+            conn.shm_detach(segment.id)?;
+        }
+
+        let name = CStr::from_bytes_with_nul(b"glazier-shm\0").unwrap();
+        let fd = memfd_create(name, MemFdCreateFlag::MFD_CLOEXEC)?;
+        // SAFETY: `memfd_create` returns a new file descriptor, which nothing else owns.
+        let file = unsafe { File::from_raw_fd(fd) };
+        file.set_len(len as u64)?;
+        let id = conn.generate_id()?;
+        conn.shm_attach_fd(id, file.try_clone()?, true)?
+            .check()
+            .context("attach shared memory")?;
+        self.segment = Some(Segment { id, file, len });
+        Ok(())
+    }
+
+    fn put_shm(
+        &mut self,
+        conn: &XCBConnection,
+        rects: &[PixelRect],
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let segment = self.segment.as_ref().unwrap();
+        segment.file.write_all_at(data, 0)?;
+        let mut offset = 0;
+        for (i, rect) in rects.iter().enumerate() {
+            // The server handles the requests in order, so only the last one needs to report
+            // that it is done.
+            let send_event = i == rects.len() - 1;
+            conn.shm_put_image(
+                self.window,
+                self.gc,
+                rect.width as u16,
+                rect.height as u16,
+                0,
+                0,
+                rect.width as u16,
+                rect.height as u16,
+                rect.x as i16,
+                rect.y as i16,
+                self.depth,
+                ImageFormat::Z_PIXMAP.into(),
+                send_event,
+                segment.id,
+                offset as u32,
+            )?;
+            offset += rect.width as usize * rect.height as usize * 4;
+        }
+        // The server reads the segment asynchronously, so it can't be reused until the
+        // completion event arrives.
+        self.busy = true;
+        conn.flush()?;
+        Ok(())
+    }
+
+    /// Handle the completion event of a `ShmPutImage` request for this window.
+    pub(super) fn handle_completion(&mut self, event: &shm::CompletionEvent) {
+        if matches!(&self.segment, Some(segment) if segment.id == event.shmseg) {
+            self.busy = false;
+        }
+    }
+
+    fn put_image(
+        &self,
+        conn: &XCBConnection,
+        rects: &[PixelRect],
+        data: &[u8],
+    ) -> Result<(), Error> {
+        // Leave room for the 24 byte header of a PutImage request.
+        let max_len = conn.maximum_request_bytes() - 24;
+        let mut offset = 0;
+        for rect in rects {
+            let stride = rect.width as usize * 4;
+            // Large rectangles are split into strips which fit into a request.
+            let rows_per_request = (max_len / stride).max(1) as u32;
+            let mut y = 0;
+            while y < rect.height {
+                let rows = rows_per_request.min(rect.height - y);
+                let len = rows as usize * stride;
+                conn.put_image(
+                    ImageFormat::Z_PIXMAP,
+                    self.window,
+                    self.gc,
+                    rect.width as u16,
+                    rows as u16,
+                    rect.x as i16,
+                    (rect.y + y) as i16,
+                    0,
+                    self.depth,
+                    &data[offset..offset + len],
+                )?;
+                offset += len;
+                y += rows;
+            }
+        }
+        conn.flush()?;
+        Ok(())
+    }
+
+    /// Free the server side resources.
+    pub(super) fn destroy(&mut self, conn: &XCBConnection) {
+        if let Some(segment) = self.segment.take() {
+            log_x11!(conn.shm_detach(segment.id));
+        }
+        log_x11!(conn.free_gc(self.gc));
+    }
+}
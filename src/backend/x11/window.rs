@@ -25,16 +25,24 @@ use std::time::Instant;
 
 use crate::backend::shared::xkb::{xkb_simulate_input, KeyEventsState};
 use crate::pointer::{
-    Angle, MouseInfo, PenInclination, PenInfo, PointerId, PointerType, TouchInfo,
+    Angle, Gesture, GestureEvent, GesturePhase, MouseInfo, PenInclination, PenInfo, PenTool,
+    PointerId, PointerSample, PointerType, ScrollInfo, ScrollSource, ScrollUnit, TouchInfo,
 };
 use crate::scale::Scalable;
 use anyhow::{anyhow, Context, Error};
 use tracing::{error, warn};
 use x11rb::connection::Connection;
 use x11rb::errors::ReplyOrIdError;
-use x11rb::properties::{WmHints, WmHintsState, WmSizeHints};
-use x11rb::protocol::render::Pictformat;
-use x11rb::protocol::xinput::{self, DeviceType, ModifierInfo, TouchEventFlags};
+use x11rb::properties::{AspectRatio, WmHints, WmHintsState, WmSizeHints};
+use x11rb::protocol::render::{self, ConnectionExt as _, Pictformat};
+use x11rb::protocol::shape::{self, ConnectionExt as _};
+use x11rb::protocol::shm;
+use x11rb::protocol::sync::{self, ConnectionExt as _};
+use x11rb::protocol::xfixes::{self, ConnectionExt as _};
+use x11rb::protocol::xinput::{
+    self, ConnectionExt as _, DeviceType, GesturePinchEventFlags, GestureSwipeEventFlags,
+    ModifierInfo, PointerEventFlags, TouchEventFlags, XIEventMask,
+};
 use x11rb::protocol::xproto::{
     self, AtomEnum, ChangeWindowAttributesAux, ColormapAlloc, ConfigureNotifyEvent,
     ConfigureWindowAux, ConnectionExt, EventMask, ImageOrder as X11ImageOrder, KeyButMask,
@@ -48,29 +56,68 @@ use raw_window_handle::{
     XcbWindowHandle,
 };
 
+use crate::backend::shared::linux;
 use crate::backend::shared::Timer;
-use crate::common_util::IdleCallback;
+use crate::clipboard::ClipboardFormat;
+use crate::common_util::{
+    ClickCounter, IdleCallback, MULTI_CLICK_INTERVAL, MULTI_CLICK_MAX_DISTANCE,
+};
 use crate::dialog::FileDialogOptions;
+use crate::dnd::{DragEvent, DragIcon, DropAction};
 use crate::error::Error as ShellError;
+use crate::icon::Icon;
 use crate::keyboard::{KeyState, Modifiers, ModifiersExt};
 use crate::kurbo::{Insets, Point, Rect, Size, Vec2};
 use crate::mouse::{Cursor, CursorDesc};
+use crate::pixels::PixelBuffer;
 use crate::region::Region;
 use crate::scale::Scale;
+use crate::screen::Monitor;
 use crate::text::Event;
 use crate::window::{
-    FileDialogToken, IdleToken, TextFieldToken, TimerToken, WinHandler, WindowLevel,
+    FileDialogToken, IdleToken, PointerGrab, ResizeEdge, TextFieldToken, TimerToken, WinHandler,
+    WindowLevel,
 };
 use crate::{window, PointerButton, PointerButtons, PointerEvent, ScaledArea};
 
 use super::application::Application;
 use super::dialog;
+use super::dnd::{self, DragInfo, DropData, DropStep, DropTarget, XdndMessage};
 use super::menu::Menu;
+use super::pointer::{DeviceKind, ValuatorInfo};
+use super::shm::Presenter;
 
-fn size_hints(resizable: bool, size: Size, min_size: Size) -> WmSizeHints {
+/// The limits on the size of a window, in display points.
+#[derive(Clone, Copy, Debug, Default)]
+struct SizeConstraints {
+    resizable: bool,
+    min_size: Size,
+    max_size: Option<Size>,
+    increments: Option<Size>,
+    aspect_ratio: Option<f64>,
+}
+
+/// `WM_NORMAL_HINTS` stores the aspect ratio as a fraction, with this denominator.
+const ASPECT_RATIO_DENOMINATOR: i32 = 10_000;
+
+/// The `WM_NORMAL_HINTS` for `constraints`.
+///
+/// `size` is the current size in pixels, which a window that isn't resizable is fixed to.
+fn size_hints(constraints: SizeConstraints, size: Size, scale: Scale) -> WmSizeHints {
+    let to_px = |size: Size| {
+        let size = size.to_px(scale);
+        (size.width.round() as i32, size.height.round() as i32)
+    };
     let mut size_hints = WmSizeHints::new();
-    if resizable {
-        size_hints.min_size = Some((min_size.width as i32, min_size.height as i32));
+    if constraints.resizable {
+        size_hints.min_size = Some(to_px(constraints.min_size));
+        size_hints.max_size = constraints.max_size.map(to_px);
+        size_hints.size_increment = constraints.increments.map(to_px);
+        size_hints.aspect = constraints.aspect_ratio.map(|ratio| {
+            let numerator = (ratio * ASPECT_RATIO_DENOMINATOR as f64).round() as i32;
+            let ratio = AspectRatio::new(numerator, ASPECT_RATIO_DENOMINATOR);
+            (ratio, ratio)
+        });
     } else {
         size_hints.min_size = Some((size.width as i32, size.height as i32));
         size_hints.max_size = Some((size.width as i32, size.height as i32));
@@ -78,10 +125,47 @@ fn size_hints(resizable: bool, size: Size, min_size: Size) -> WmSizeHints {
     size_hints
 }
 
+/// The `IconicState` of `WM_STATE` and `WM_CHANGE_STATE`.
+const ICONIC_STATE: u32 = 3;
+
+/// The direction of a `_NET_WM_MOVERESIZE` message which moves the window.
+const MOVERESIZE_MOVE: u32 = 8;
+
+/// The direction of a `_NET_WM_MOVERESIZE` message which resizes the window from `edge`.
+fn moveresize_direction(edge: ResizeEdge) -> u32 {
+    match edge {
+        ResizeEdge::TopLeft => 0,
+        ResizeEdge::Top => 1,
+        ResizeEdge::TopRight => 2,
+        ResizeEdge::Right => 3,
+        ResizeEdge::BottomRight => 4,
+        ResizeEdge::Bottom => 5,
+        ResizeEdge::BottomLeft => 6,
+        ResizeEdge::Left => 7,
+    }
+}
+
+/// A button press, which the window manager needs to take over the pointer from us.
+#[derive(Clone, Copy, Debug)]
+struct ButtonPress {
+    device: xinput::DeviceId,
+    time: xproto::Timestamp,
+    button: u32,
+    /// The position on the root window, in pixels.
+    root_x: i32,
+    root_y: i32,
+    /// The pointer and its position in the window, for the release which we report when the
+    /// window manager takes over.
+    pointer_id: PointerId,
+    is_primary: bool,
+    pos: Point,
+}
+
 pub(crate) struct WindowBuilder {
     app: Application,
     handler: Option<Box<dyn WinHandler>>,
     title: String,
+    icon: Option<Icon>,
     transparent: bool,
     position: Option<Point>,
     size: Size,
@@ -97,6 +181,7 @@ impl WindowBuilder {
             app,
             handler: None,
             title: String::new(),
+            icon: None,
             transparent: false,
             position: None,
             size: Size::new(500.0, 400.0),
@@ -163,6 +248,11 @@ impl WindowBuilder {
         self
     }
 
+    pub fn icon(mut self, icon: Icon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
     pub fn menu(self, _menu: Menu) -> Self {
         // TODO(x11/menus): implement WindowBuilder::set_menu (currently a no-op)
         warn!("WindowBuilder::menu is currently unimplemented for X11 backend.");
@@ -176,26 +266,7 @@ impl WindowBuilder {
         let id = conn.generate_id()?;
         let setup = conn.setup();
 
-        let scale_override = std::env::var("GLAZIER_OVERRIDE_SCALE")
-            .ok()
-            .map(|x| x.parse::<f64>());
-
-        let scale =
-            match scale_override.or_else(|| self.app.rdb.get_value("Xft.dpi", "").transpose()) {
-                Some(Ok(dpi)) => {
-                    let scale = dpi / 96.;
-                    Scale::new(scale, scale)
-                }
-                None => Scale::default(),
-                Some(Err(err)) => {
-                    let default = Scale::default();
-                    warn!(
-                        "Unable to parse dpi: {:?}, defaulting to {:?}",
-                        err, default
-                    );
-                    default
-                }
-            };
+        let scale = self.app.scale();
 
         let size_px = self.size.to_px(scale);
         let screen = setup
@@ -212,7 +283,11 @@ impl WindowBuilder {
             None => (false, self.app.root_visual_type()),
         };
         if transparent != self.transparent {
-            warn!("Windows with transparent backgrounds do not work");
+            if self.app.compositor_running() {
+                warn!("The X server has no 32 bit ARGB visual, the window will not be transparent");
+            } else {
+                warn!("No compositing manager is running, the window will not be transparent");
+            }
         }
 
         let mut cw_values = xproto::CreateWindowAux::new().event_mask(
@@ -221,7 +296,8 @@ impl WindowBuilder {
                 | EventMask::KEY_PRESS
                 | EventMask::KEY_RELEASE
                 | EventMask::FOCUS_CHANGE
-                | EventMask::LEAVE_WINDOW,
+                | EventMask::VISIBILITY_CHANGE
+                | EventMask::PROPERTY_CHANGE,
         );
         if transparent {
             let colormap = conn.generate_id()?;
@@ -231,16 +307,23 @@ impl WindowBuilder {
                 screen.root,
                 visual_type.visual_id,
             )?;
+            // A window with a non-default visual needs its own colormap, and its border pixel must
+            // be set explicitly, or creating the window fails with BadMatch. The background is
+            // fully transparent, so that exposed areas don't flash before the first paint.
             cw_values = cw_values
-                .border_pixel(screen.white_pixel)
+                .background_pixel(0)
+                .border_pixel(0)
                 .colormap(colormap);
         };
 
         let (parent, parent_origin) = match &self.level {
-            WindowLevel::AppWindow => (Weak::new(), Vec2::ZERO),
+            WindowLevel::AppWindow | WindowLevel::AlwaysOnTop | WindowLevel::Notification => {
+                (Weak::new(), Vec2::ZERO)
+            }
             WindowLevel::Tooltip(parent)
             | WindowLevel::DropDown(parent)
-            | WindowLevel::Modal(parent) => {
+            | WindowLevel::Modal(parent)
+            | WindowLevel::Utility(parent) => {
                 let handle = parent.0.unwrap_x11().window.clone();
                 let origin = handle
                     .upgrade()
@@ -260,8 +343,8 @@ impl WindowBuilder {
             depth,
             // The new window's ID
             id,
-            // Parent window of this new window
-            // TODO(#468): either `screen.root()` (no parent window) or pass parent here to attach
+            // Parent window of this new window. Windows with a parent `WindowHandle` are still
+            // top level windows, they are tied to it through `WM_TRANSIENT_FOR`.
             screen.root,
             // X-coordinate of the new window
             pos.x as _,
@@ -283,7 +366,7 @@ impl WindowBuilder {
         .check()
         .context("create window")?;
 
-        super::pointer::enable_window_pointers(conn, id)?;
+        super::pointer::enable_window_pointers(conn, id, self.app.gestures_supported())?;
 
         if let Some(colormap) = cw_values.colormap {
             conn.free_colormap(colormap)?;
@@ -341,8 +424,32 @@ impl WindowBuilder {
             // GTK (actually glib) goes fishing in /proc (platform_get_argv0()). We pass.
         }
 
+        // If we can, we use an XSync counter to tell the window manager when we are done painting
+        // after a resize.
+        let sync_counter = if self.app.sync_supported() {
+            let counter = conn.generate_id()?;
+            conn.sync_create_counter(counter, sync::Int64 { hi: 0, lo: 0 })?
+                .check()
+                .context("create sync counter")?;
+            conn.change_property32(
+                PropMode::REPLACE,
+                id,
+                atoms._NET_WM_SYNC_REQUEST_COUNTER,
+                AtomEnum::CARDINAL,
+                &[counter],
+            )?
+            .check()
+            .context("set _NET_WM_SYNC_REQUEST_COUNTER")?;
+            Some(counter)
+        } else {
+            None
+        };
+
         // Replace the window's WM_PROTOCOLS with the following.
-        let protocols = [atoms.WM_DELETE_WINDOW];
+        let mut protocols = vec![atoms.WM_DELETE_WINDOW];
+        if sync_counter.is_some() {
+            protocols.push(atoms._NET_WM_SYNC_REQUEST);
+        }
         conn.change_property32(
             PropMode::REPLACE,
             id,
@@ -353,29 +460,29 @@ impl WindowBuilder {
         .check()
         .context("set WM_PROTOCOLS")?;
 
-        let min_size = self.min_size.to_px(scale);
-        log_x11!(size_hints(self.resizable, size_px, min_size)
+        let size_constraints = SizeConstraints {
+            resizable: self.resizable,
+            min_size: self.min_size,
+            ..Default::default()
+        };
+        log_x11!(size_hints(size_constraints, size_px, scale)
             .set_normal_hints(conn, id)
             .context("set wm normal hints"));
 
-        // TODO: set _NET_WM_STATE
-        let mut hints = WmHints::new();
-        if let Some(state) = self.state {
-            hints.initial_state = Some(match state {
-                window::WindowState::Maximized => WmHintsState::Normal,
-                window::WindowState::Minimized => WmHintsState::Iconic,
-                window::WindowState::Restored => WmHintsState::Normal,
-            });
-        }
-        log_x11!(hints.set(conn, id).context("set wm hints"));
+        // The initial state is added by `set_window_state`, after the window is created.
+        log_x11!(WmHints::new().set(conn, id).context("set wm hints"));
 
         // set level
         {
             let window_type = match self.level {
-                WindowLevel::AppWindow => atoms._NET_WM_WINDOW_TYPE_NORMAL,
+                WindowLevel::AppWindow | WindowLevel::AlwaysOnTop => {
+                    atoms._NET_WM_WINDOW_TYPE_NORMAL
+                }
                 WindowLevel::Tooltip(_) => atoms._NET_WM_WINDOW_TYPE_TOOLTIP,
                 WindowLevel::Modal(_) => atoms._NET_WM_WINDOW_TYPE_DIALOG,
                 WindowLevel::DropDown(_) => atoms._NET_WM_WINDOW_TYPE_DROPDOWN_MENU,
+                WindowLevel::Utility(_) => atoms._NET_WM_WINDOW_TYPE_UTILITY,
+                WindowLevel::Notification => atoms._NET_WM_WINDOW_TYPE_NOTIFICATION,
             };
 
             let conn = self.app.connection();
@@ -386,9 +493,20 @@ impl WindowBuilder {
                 AtomEnum::ATOM,
                 &[window_type],
             ));
+            // This lets the window manager keep the window above its parent, and treat them as a
+            // group.
+            if let Some(parent) = parent.upgrade() {
+                log_x11!(conn.change_property32(
+                    xproto::PropMode::REPLACE,
+                    id,
+                    AtomEnum::WM_TRANSIENT_FOR,
+                    AtomEnum::WINDOW,
+                    &[parent.id],
+                ));
+            }
             if matches!(
                 self.level,
-                WindowLevel::DropDown(_) | WindowLevel::Modal(_) | WindowLevel::Tooltip(_)
+                WindowLevel::DropDown(_) | WindowLevel::Tooltip(_)
             ) {
                 log_x11!(conn.change_window_attributes(
                     id,
@@ -397,13 +515,27 @@ impl WindowBuilder {
             }
         }
 
+        let level_states = match self.level {
+            WindowLevel::AlwaysOnTop => vec![atoms._NET_WM_STATE_ABOVE],
+            WindowLevel::Modal(_) => vec![atoms._NET_WM_STATE_MODAL],
+            _ => vec![],
+        };
+        let modal = matches!(self.level, WindowLevel::Modal(_));
+        if modal {
+            if let Some(parent) = parent.upgrade() {
+                parent.modal_children.set(parent.modal_children.get() + 1);
+            }
+        }
+
         let window = Rc::new(Window {
             id,
             app: self.app.clone(),
             handler,
             area: Cell::new(ScaledArea::from_px(size_px, scale)),
             scale: Cell::new(scale),
-            min_size,
+            size_constraints: Cell::new(size_constraints),
+            input_region: RefCell::new(None),
+            opaque_region: RefCell::new(None),
             invalid: RefCell::new(Region::EMPTY),
             destroyed: Cell::new(false),
             timer_queue: Mutex::new(BinaryHeap::new()),
@@ -412,10 +544,47 @@ impl WindowBuilder {
             next_text_field: Cell::new(None),
             active_text_field: Cell::new(None),
             need_to_reset_compose: Cell::new(false),
+            click_counter: ClickCounter::default(),
+            sync_counter,
+            sync_request: Cell::new(None),
+            sync_after_paint: Cell::new(None),
+            drop_target: RefCell::new(DropTarget::default()),
             parent,
+            depth,
+            transparent,
+            presenter: RefCell::new(None),
+            fullscreen_monitor: Cell::new(None),
+            position_px: Cell::new(None),
+            reparented: Cell::new(false),
+            monitor_rect: Cell::new(None),
+            mapped: Cell::new(false),
+            obscured: Cell::new(false),
+            hidden: Cell::new(false),
+            visible: Cell::new(true),
+            level_states,
+            modal,
+            modal_children: Cell::new(0),
+            button_press: Cell::new(None),
+            pointer_grab: Cell::new(PointerGrab::None),
+            grabbed_device: Cell::new(None),
+            barriers: RefCell::new(Vec::new()),
+            pinch_scale: Cell::new(1.0),
+            cursor: RefCell::new(Cursor::Arrow),
         });
-
+        window.update_click_counter();
+
+        // This also sets the states that come from the level.
+        let state = self.state.unwrap_or(window::WindowState::Restored);
+        if let Err(err) = window.set_window_state(state) {
+            error!(
+                "Window::set_window_state - failed to set the initial state: {:#}",
+                err
+            );
+        }
         window.set_title(&self.title);
+        if let Some(icon) = &self.icon {
+            window.set_icon(Some(icon));
+        }
         if let Some(pos) = self.position {
             window.set_position(pos);
         }
@@ -452,8 +621,13 @@ pub(crate) struct Window {
     handler: RefCell<Box<dyn WinHandler>>,
     area: Cell<ScaledArea>,
     scale: Cell<Scale>,
-    // min size in px
-    min_size: Size,
+    /// The size limits, which are sent to the window manager in `WM_NORMAL_HINTS`.
+    size_constraints: Cell<SizeConstraints>,
+    /// The part of the window which receives pointer input, in display points, or `None` for the
+    /// whole window.
+    input_region: RefCell<Option<Region>>,
+    /// The part of the window which is fully opaque, in display points.
+    opaque_region: RefCell<Option<Region>>,
     /// We've told X11 to destroy this window, so don't so any more X requests with this window id.
     destroyed: Cell<bool>,
     /// The region that was invalidated since the last time we rendered.
@@ -466,7 +640,58 @@ pub(crate) struct Window {
     next_text_field: Cell<Option<TextFieldToken>>,
     active_text_field: Cell<Option<TextFieldToken>>,
     need_to_reset_compose: Cell<bool>,
+    click_counter: ClickCounter,
+    /// The counter for `_NET_WM_SYNC_REQUEST`, if the sync extension is supported.
+    sync_counter: Option<sync::Counter>,
+    /// The value of the last `_NET_WM_SYNC_REQUEST`, which applies to the next ConfigureNotify.
+    sync_request: Cell<Option<sync::Int64>>,
+    /// The value that the sync counter should be set to after the next paint.
+    sync_after_paint: Cell<Option<sync::Int64>>,
+    /// The XDND drag that is currently over this window, if any.
+    drop_target: RefCell<DropTarget>,
     parent: Weak<Window>,
+    /// The depth of the window's visual.
+    depth: u8,
+    /// Whether the window has an alpha channel.
+    transparent: bool,
+    /// Copies pixels into the window, created by the first call to `present_pixels`.
+    presenter: RefCell<Option<Presenter>>,
+    /// The monitor that was requested for the last `WindowState::Fullscreen`.
+    fullscreen_monitor: Cell<Option<Monitor>>,
+    /// The last reported position on the root window, in pixels.
+    position_px: Cell<Option<Point>>,
+    /// Whether the window manager has put the window into a frame, which makes the coordinates
+    /// of real `ConfigureNotify` events relative to the frame.
+    reparented: Cell<bool>,
+    /// The rectangle of the last reported monitor, in pixels.
+    monitor_rect: Cell<Option<Rect>>,
+    /// Whether the window is mapped. Window managers unmap minimized windows.
+    mapped: Cell<bool>,
+    /// Whether the window is fully covered, according to the last `VisibilityNotify`.
+    obscured: Cell<bool>,
+    /// Whether the window manager has set `_NET_WM_STATE_HIDDEN`.
+    hidden: Cell<bool>,
+    /// The last reported visibility.
+    visible: Cell<bool>,
+    /// The `_NET_WM_STATE` atoms that come from the window's level.
+    level_states: Vec<xproto::Atom>,
+    /// Whether this is a modal window, which blocks the input to its parent.
+    modal: bool,
+    /// The number of open modal windows whose parent is this window.
+    modal_children: Cell<usize>,
+    /// The button which is pressed in the window, if any.
+    button_press: Cell<Option<ButtonPress>>,
+    /// How the window holds on to the pointer.
+    pointer_grab: Cell<PointerGrab>,
+    /// The master pointer which is grabbed for `PointerGrab::Capture` or `PointerGrab::Lock`.
+    grabbed_device: Cell<Option<xinput::DeviceId>>,
+    /// The pointer barriers which hold the pointer for `PointerGrab::Confine` or
+    /// `PointerGrab::Lock`.
+    barriers: RefCell<Vec<xfixes::Barrier>>,
+    /// The scale of the current pinch gesture, to report its changes to `WinHandler::zoom`.
+    pinch_scale: Cell<f64>,
+    /// The cursor which was set last, to set it again when the cursor theme changes.
+    cursor: RefCell<Cursor>,
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -512,6 +737,14 @@ impl Window {
     pub fn destroy(&self) {
         if !self.destroyed() {
             self.destroyed.set(true);
+            if let Some(counter) = self.sync_counter {
+                log_x11!(self.app.connection().sync_destroy_counter(counter));
+            }
+            if let Some(mut presenter) = self.presenter.borrow_mut().take() {
+                presenter.destroy(self.app.connection());
+            }
+            // The barriers belong to the client rather than the window.
+            log_x11!(self.release_pointer_grab());
             log_x11!(self.app.connection().destroy_window(self.id));
         }
     }
@@ -543,6 +776,44 @@ impl Window {
         Ok(())
     }
 
+    /// Called when the scale from the XSETTINGS changes.
+    pub(crate) fn scale_changed(&self, scale: Scale) -> Result<(), Error> {
+        if scale == self.scale.get() {
+            return Ok(());
+        }
+        // The size in pixels stays the same, so the size in display points changes.
+        let size = self.area.get().size_px();
+        self.scale.set(scale);
+        self.area.set(ScaledArea::from_px(size, scale));
+        self.update_click_counter();
+        // The size constraints are in display points, so they change in pixels.
+        self.send_size_hints();
+        self.send_input_region();
+        self.send_opaque_region();
+        self.add_invalid_rect(size.to_dp(scale).to_rect())?;
+        self.with_handler(|h| h.scale(scale));
+        self.with_handler(|h| h.size(size.to_dp(scale)));
+        Ok(())
+    }
+
+    /// Sets the cursor again, after the cursor theme changed.
+    pub(crate) fn reload_cursor(&self) {
+        let cursor = self.cursor.borrow().clone();
+        self.set_cursor(&cursor);
+    }
+
+    /// Applies the double click settings from the XSETTINGS.
+    pub(crate) fn update_click_counter(&self) {
+        let (time, distance) = self.app.double_click_settings();
+        let time = time.unwrap_or(MULTI_CLICK_INTERVAL.as_millis() as u64);
+        // The distance is in pixels, but the click positions are in display points.
+        let distance = distance.map_or(MULTI_CLICK_MAX_DISTANCE, |distance| {
+            distance / self.scale.get().x()
+        });
+        self.click_counter.set_interval_ms(time);
+        self.click_counter.set_distance(distance);
+    }
+
     fn render(&self) -> Result<(), Error> {
         self.with_handler(|h| h.prepare_paint());
 
@@ -555,9 +826,20 @@ impl Window {
             handler.paint(&invalid);
         });
 
+        // Now that the content for the new size is painted, let the window manager show it.
+        if let Some(value) = self.sync_after_paint.take() {
+            self.update_sync_counter(value);
+        }
+
         Ok(())
     }
 
+    fn update_sync_counter(&self, value: sync::Int64) {
+        if let Some(counter) = self.sync_counter {
+            log_x11!(self.app.connection().sync_set_counter(counter, value));
+        }
+    }
+
     fn show(&self) {
         if !self.destroyed() {
             log_x11!(self.app.connection().map_window(self.id));
@@ -570,12 +852,134 @@ impl Window {
 
     /// Set whether the window should be resizable
     fn resizable(&self, resizable: bool) {
+        self.update_size_constraints(|constraints| constraints.resizable = resizable);
+    }
+
+    fn set_min_size(&self, size: Size) {
+        self.update_size_constraints(|constraints| constraints.min_size = size);
+    }
+
+    fn set_max_size(&self, size: Option<Size>) {
+        self.update_size_constraints(|constraints| constraints.max_size = size);
+    }
+
+    fn set_resize_increments(&self, increments: Option<Size>) {
+        self.update_size_constraints(|constraints| constraints.increments = increments);
+    }
+
+    fn set_aspect_ratio(&self, ratio: Option<f64>) {
+        self.update_size_constraints(|constraints| constraints.aspect_ratio = ratio);
+    }
+
+    fn update_size_constraints(&self, f: impl FnOnce(&mut SizeConstraints)) {
+        let mut constraints = self.size_constraints.get();
+        f(&mut constraints);
+        self.size_constraints.set(constraints);
+        self.send_size_hints();
+    }
+
+    /// Sends the size constraints to the window manager, in `WM_NORMAL_HINTS`.
+    fn send_size_hints(&self) {
+        if self.destroyed() {
+            return;
+        }
         let conn = self.app.connection();
-        log_x11!(size_hints(resizable, self.size().size_px(), self.min_size)
+        let hints = size_hints(
+            self.size_constraints.get(),
+            self.size().size_px(),
+            self.scale.get(),
+        );
+        log_x11!(hints
             .set_normal_hints(conn, self.id)
             .context("set normal hints"));
     }
 
+    fn set_input_region(&self, region: Option<Region>) {
+        if region.is_some() && !self.app.shape_supported() {
+            warn!("The SHAPE extension is unavailable, the input region of windows can't be set");
+        }
+        self.input_region.replace(region);
+        self.send_input_region();
+    }
+
+    fn set_opaque_region(&self, region: Option<Region>) {
+        self.opaque_region.replace(region);
+        self.send_opaque_region();
+    }
+
+    /// Shapes the input of the window with the SHAPE extension.
+    fn send_input_region(&self) {
+        if self.destroyed() || !self.app.shape_supported() {
+            return;
+        }
+        let conn = self.app.connection();
+        let scale = self.scale.get();
+        if let Some(region) = &*self.input_region.borrow() {
+            // Rounding outwards, so that no input is lost at the edges.
+            let rects: Vec<_> = region
+                .rects()
+                .iter()
+                .map(|rect| {
+                    let rect = rect.to_px(scale).expand();
+                    xproto::Rectangle {
+                        x: rect.x0 as i16,
+                        y: rect.y0 as i16,
+                        width: rect.width() as u16,
+                        height: rect.height() as u16,
+                    }
+                })
+                .collect();
+            log_x11!(conn.shape_rectangles(
+                shape::SO::SET,
+                shape::SK::INPUT,
+                xproto::ClipOrdering::UNSORTED,
+                self.id,
+                0,
+                0,
+                &rects,
+            ));
+        } else {
+            // Without a mask, the input shape is reset to the whole window.
+            log_x11!(conn.shape_mask(shape::SO::SET, shape::SK::INPUT, self.id, 0, 0, x11rb::NONE));
+        }
+    }
+
+    /// Sends the opaque region to the compositor, in `_NET_WM_OPAQUE_REGION`.
+    fn send_opaque_region(&self) {
+        if self.destroyed() {
+            return;
+        }
+        let conn = self.app.connection();
+        let atom = self.app.atoms()._NET_WM_OPAQUE_REGION;
+        let scale = self.scale.get();
+        if let Some(region) = &*self.opaque_region.borrow() {
+            // Rounding inwards, so that no transparent pixel is claimed to be opaque.
+            let values: Vec<u32> = region
+                .rects()
+                .iter()
+                .map(|rect| rect.to_px(scale).trunc())
+                .filter(|rect| rect.area() > 0.0)
+                .flat_map(|rect| {
+                    [
+                        rect.x0 as u32,
+                        rect.y0 as u32,
+                        rect.width() as u32,
+                        rect.height() as u32,
+                    ]
+                })
+                .collect();
+            log_x11!(conn.change_property32(
+                xproto::PropMode::REPLACE,
+                self.id,
+                atom,
+                AtomEnum::CARDINAL,
+                &values,
+            ));
+        } else {
+            log_x11!(conn.delete_property(self.id, atom));
+        }
+    }
+
     /// Set whether the window should show titlebar
     fn show_titlebar(&self, _show_titlebar: bool) {
         warn!("Window::show_titlebar is currently unimplemented for X11 backend.");
@@ -589,17 +993,24 @@ impl Window {
             .to_vec2()
     }
 
+    /// The position of the window's top left corner on the root window, in pixels.
+    fn root_position_px(&self) -> Result<Point, Error> {
+        let conn = self.app.connection();
+        let geom = conn.get_geometry(self.id)?.reply()?;
+        let cord = conn
+            .translate_coordinates(self.id, geom.root, 0, 0)?
+            .reply()?;
+        Ok(Point::new(cord.dst_x as _, cord.dst_y as _))
+    }
+
     fn get_position(&self) -> Point {
-        fn _get_position(window: &Window) -> Result<Point, Error> {
-            let conn = window.app.connection();
-            let scale = window.scale.get();
-            let geom = conn.get_geometry(window.id)?.reply()?;
-            let cord = conn
-                .translate_coordinates(window.id, geom.root, 0, 0)?
-                .reply()?;
-            Ok(Point::new(cord.dst_x as _, cord.dst_y as _).to_dp(scale))
-        }
-        let pos = _get_position(self);
+        let scale = self.scale.get();
+        // Once the window is shown, its position is kept up to date by `ConfigureNotify`.
+        let pos = match self.position_px.get() {
+            Some(pos) => Ok(pos),
+            None => self.root_position_px(),
+        };
+        let pos = pos.map(|pos| pos.to_dp(scale));
         log_x11!(&pos);
         pos.map(|pos| pos - self.parent_origin())
             .unwrap_or_default()
@@ -687,6 +1098,46 @@ impl Window {
         self.request_anim_frame();
     }
 
+    fn present_pixels(&self, buffer: &PixelBuffer, damage: Option<&Region>) -> Result<(), Error> {
+        if self.destroyed() {
+            return Ok(());
+        }
+
+        let conn = self.app.connection();
+        let rects = buffer.damage_rects(damage, self.scale.get());
+        let mut presenter = borrow_mut!(self.presenter)?;
+        if presenter.is_none() {
+            *presenter = Some(Presenter::new(
+                conn,
+                self.id,
+                self.depth,
+                !self.transparent,
+                self.app.shm_supported(),
+            )?);
+        }
+        presenter.as_mut().unwrap().present(conn, buffer, &rects)
+    }
+
+    fn set_drop_target(&self, enabled: bool) {
+        if self.destroyed() {
+            return;
+        }
+
+        let conn = self.app.connection();
+        let atoms = self.app.atoms();
+        if enabled {
+            log_x11!(conn.change_property32(
+                xproto::PropMode::REPLACE,
+                self.id,
+                atoms.XdndAware,
+                AtomEnum::ATOM,
+                &[dnd::XDND_VERSION],
+            ));
+        } else {
+            log_x11!(conn.delete_property(self.id, atoms.XdndAware));
+        }
+    }
+
     fn set_title(&self, title: &str) {
         if self.destroyed() {
             return;
@@ -713,28 +1164,390 @@ impl Window {
         ));
     }
 
+    /// Change the state of the window.
+    ///
+    /// Before the window manager manages the window, the state is set in the window's properties,
+    /// which the window manager reads when the window is mapped. Afterwards, the window manager is
+    /// asked to change the state with messages to the root window.
+    fn set_window_state(&self, state: window::WindowState) -> Result<(), Error> {
+        if self.destroyed() {
+            return Ok(());
+        }
+
+        let conn = self.app.connection();
+        let atoms = self.app.atoms();
+        let fullscreen_monitors = match state {
+            window::WindowState::Fullscreen(Some(monitor)) => {
+                let index = self.monitor_index(&monitor);
+                if index.is_none() {
+                    warn!("{:?} is not a monitor of the X server", monitor);
+                }
+                index
+            }
+            _ => None,
+        };
+        if let window::WindowState::Fullscreen(monitor) = state {
+            self.fullscreen_monitor.set(monitor);
+        }
+
+        let Some(wm_state) = self.wm_state()? else {
+            let mut net_wm_state = self.level_states.clone();
+            match state {
+                window::WindowState::Maximized => {
+                    net_wm_state.push(atoms._NET_WM_STATE_MAXIMIZED_VERT);
+                    net_wm_state.push(atoms._NET_WM_STATE_MAXIMIZED_HORZ);
+                }
+                window::WindowState::Fullscreen(_) => {
+                    net_wm_state.push(atoms._NET_WM_STATE_FULLSCREEN);
+                }
+                window::WindowState::Minimized | window::WindowState::Restored => {}
+            }
+            conn.change_property32(
+                PropMode::REPLACE,
+                self.id,
+                atoms._NET_WM_STATE,
+                AtomEnum::ATOM,
+                &net_wm_state,
+            )?;
+            if let Some(index) = fullscreen_monitors {
+                conn.change_property32(
+                    PropMode::REPLACE,
+                    self.id,
+                    atoms._NET_WM_FULLSCREEN_MONITORS,
+                    AtomEnum::CARDINAL,
+                    &[index; 4],
+                )?;
+            }
+            // Windows without WM_HINTS fail to parse, and start from empty hints.
+            let mut hints = WmHints::get(conn, self.id)?
+                .reply()
+                .unwrap_or_else(|_| WmHints::new());
+            hints.initial_state = Some(match state {
+                window::WindowState::Minimized => WmHintsState::Iconic,
+                _ => WmHintsState::Normal,
+            });
+            hints.set(conn, self.id)?;
+            return Ok(());
+        };
+
+        if state == window::WindowState::Minimized {
+            // ICCCM 4.1.4: iconify by sending WM_CHANGE_STATE with IconicState.
+            return self.send_wm_message(atoms.WM_CHANGE_STATE, [ICONIC_STATE, 0, 0, 0, 0]);
+        }
+        if wm_state == ICONIC_STATE {
+            // ICCCM 4.1.4: mapping an iconic window makes it normal again.
+            conn.map_window(self.id)?;
+        }
+        if let Some(index) = fullscreen_monitors {
+            // The fifth value says that the request comes from an application.
+            let data = [index, index, index, index, 1];
+            self.send_wm_message(atoms._NET_WM_FULLSCREEN_MONITORS, data)?;
+        }
+        let fullscreen = matches!(state, window::WindowState::Fullscreen(_));
+        let maximized = state == window::WindowState::Maximized;
+        self.send_wm_message(
+            atoms._NET_WM_STATE,
+            [fullscreen as u32, atoms._NET_WM_STATE_FULLSCREEN, 0, 1, 0],
+        )?;
+        self.send_wm_message(
+            atoms._NET_WM_STATE,
+            [
+                maximized as u32,
+                atoms._NET_WM_STATE_MAXIMIZED_VERT,
+                atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+                1,
+                0,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get_window_state(&self) -> Result<window::WindowState, Error> {
+        let atoms = self.app.atoms();
+        if self.wm_state()? == Some(ICONIC_STATE) {
+            return Ok(window::WindowState::Minimized);
+        }
+        let net_wm_state = self.net_wm_state()?;
+        let has = |atom| net_wm_state.contains(&atom);
+        Ok(if has(atoms._NET_WM_STATE_HIDDEN) {
+            window::WindowState::Minimized
+        } else if has(atoms._NET_WM_STATE_FULLSCREEN) {
+            window::WindowState::Fullscreen(self.fullscreen_monitor.get())
+        } else if has(atoms._NET_WM_STATE_MAXIMIZED_VERT) && has(atoms._NET_WM_STATE_MAXIMIZED_HORZ)
+        {
+            window::WindowState::Maximized
+        } else {
+            window::WindowState::Restored
+        })
+    }
+
+    /// The atoms in the window's `_NET_WM_STATE` property.
+    fn net_wm_state(&self) -> Result<Vec<u32>, Error> {
+        let conn = self.app.connection();
+        let atoms = self.app.atoms();
+        let reply = conn
+            .get_property(
+                false,
+                self.id,
+                atoms._NET_WM_STATE,
+                AtomEnum::ATOM,
+                0,
+                u32::MAX,
+            )?
+            .reply()?;
+        Ok(reply.value32().into_iter().flatten().collect())
+    }
+
+    /// The state in the window's `WM_STATE` property, or `None` if the window manager doesn't
+    /// manage the window (yet).
+    fn wm_state(&self) -> Result<Option<u32>, Error> {
+        let conn = self.app.connection();
+        let atoms = self.app.atoms();
+        let reply = conn
+            .get_property(false, self.id, atoms.WM_STATE, atoms.WM_STATE, 0, 2)?
+            .reply()?;
+        Ok(reply.value32().and_then(|mut value| value.next()))
+    }
+
+    /// Send a message about this window to the window manager.
+    fn send_wm_message(&self, message_type: xproto::Atom, data: [u32; 5]) -> Result<(), Error> {
+        let conn = self.app.connection();
+        let root = conn.setup().roots[self.app.screen_num()].root;
+        let event = xproto::ClientMessageEvent::new(32, self.id, message_type, data);
+        conn.send_event(
+            false,
+            root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )?;
+        Ok(())
+    }
+
+    /// Hands the pointer over to the window manager, which moves or resizes the window until the
+    /// button is released.
+    fn begin_move_resize(&self, direction: u32) -> Result<(), Error> {
+        let Some(press) = self.button_press.get() else {
+            warn!("No button is pressed in the window, it can't be moved or resized");
+            return Ok(());
+        };
+        let conn = self.app.connection();
+        // The window manager can't grab the pointer while the press grabs it for us.
+        conn.xinput_xi_ungrab_device(press.time, press.device)?;
+        self.send_wm_message(
+            self.app.atoms()._NET_WM_MOVERESIZE,
+            [
+                press.root_x as u32,
+                press.root_y as u32,
+                direction,
+                press.button,
+                // The source indication of normal applications.
+                1,
+            ],
+        )?;
+        conn.flush()?;
+        self.release_to_wm(press);
+        Ok(())
+    }
+
+    fn show_window_menu(&self, pos: Point) -> Result<(), Error> {
+        let Some(press) = self.button_press.get() else {
+            warn!("No button is pressed in the window, its menu can't be shown");
+            return Ok(());
+        };
+        let conn = self.app.connection();
+        let pos = self.root_position_px()? + pos.to_px(self.scale.get()).to_vec2();
+        conn.xinput_xi_ungrab_device(press.time, press.device)?;
+        self.send_wm_message(
+            self.app.atoms()._GTK_SHOW_WINDOW_MENU,
+            [press.device as u32, pos.x as u32, pos.y as u32, 0, 0],
+        )?;
+        conn.flush()?;
+        self.release_to_wm(press);
+        Ok(())
+    }
+
+    /// Forget `press` once the window manager has taken it over.
+    ///
+    /// The window manager receives the release of the button, so the handler is told about it
+    /// instead. This is usually called from the handler, so the release is reported from the
+    /// idle loop.
+    fn release_to_wm(&self, press: ButtonPress) {
+        self.button_press.set(None);
+        let pointer_ev = PointerEvent {
+            pointer_id: press.pointer_id,
+            is_primary: press.is_primary,
+            pos: press.pos,
+            modifiers: self.app.modifiers(),
+            button: pointer_button(press.button),
+            ..PointerEvent::default()
+        };
+        let idle = IdleHandle {
+            queue: Arc::clone(&self.idle_queue),
+            pipe: self.idle_pipe,
+        };
+        idle.add_idle_callback(move |h| h.pointer_up(pointer_ev));
+    }
+
+    fn set_pointer_grab(&self, grab: PointerGrab) -> Result<(), Error> {
+        if self.destroyed() {
+            return Ok(());
+        }
+        self.release_pointer_grab()?;
+        let conn = self.app.connection();
+        match grab {
+            PointerGrab::None => {}
+            PointerGrab::Capture => self.grab_pointer(None)?,
+            PointerGrab::Lock => {
+                let hidden = self.app.cursors.get(conn, self.id, &Cursor::Hidden);
+                self.grab_pointer(hidden)?;
+                // Barriers around the pointer hold it in place, but its motion is still reported
+                // by the raw events.
+                let pointer = conn.query_pointer(self.id)?.reply()?;
+                let pos = Point::new(pointer.root_x as f64, pointer.root_y as f64);
+                self.create_barriers(Rect::from_origin_size(pos, (1.0, 1.0)))?;
+                self.app.count_pointer_lock(true)?;
+            }
+            PointerGrab::Confine(rect) => {
+                let origin = match self.position_px.get() {
+                    Some(position) => position,
+                    None => self.root_position_px()?,
+                };
+                self.confine_pointer(rect, origin)?;
+            }
+        }
+        self.pointer_grab.set(grab);
+        conn.flush()?;
+        Ok(())
+    }
+
+    fn release_pointer_grab(&self) -> Result<(), Error> {
+        let conn = self.app.connection();
+        if let Some(device) = self.grabbed_device.take() {
+            conn.xinput_xi_ungrab_device(x11rb::CURRENT_TIME, device)?;
+        }
+        for barrier in borrow_mut!(self.barriers)?.drain(..) {
+            conn.xfixes_delete_pointer_barrier(barrier)?;
+        }
+        if self.pointer_grab.replace(PointerGrab::None) == PointerGrab::Lock {
+            self.app.count_pointer_lock(false)?;
+        }
+        Ok(())
+    }
+
+    /// Grabs the master pointer of the client, so that its events keep coming to this window.
+    fn grab_pointer(&self, cursor: Option<xproto::Cursor>) -> Result<(), Error> {
+        let conn = self.app.connection();
+        let device = conn
+            .xinput_xi_get_client_pointer(self.id)?
+            .reply()?
+            .deviceid;
+        let mask = XIEventMask::BUTTON_PRESS | XIEventMask::BUTTON_RELEASE | XIEventMask::MOTION;
+        let reply = conn
+            .xinput_xi_grab_device(
+                self.id,
+                x11rb::CURRENT_TIME,
+                cursor.unwrap_or(x11rb::NONE),
+                device,
+                xproto::GrabMode::ASYNC,
+                xproto::GrabMode::ASYNC,
+                xinput::GrabOwner::OWNER,
+                &[mask.into()],
+            )?
+            .reply()?;
+        if reply.status != xproto::GrabStatus::SUCCESS {
+            return Err(anyhow!("the pointer can't be grabbed: {:?}", reply.status));
+        }
+        self.grabbed_device.set(Some(device));
+        Ok(())
+    }
+
+    /// Puts the barriers of [`PointerGrab::Confine`] around `rect`, in display points, when the
+    /// window is at `origin` on the root window.
+    fn confine_pointer(&self, rect: Rect, origin: Point) -> Result<(), Error> {
+        let conn = self.app.connection();
+        for barrier in borrow_mut!(self.barriers)?.drain(..) {
+            conn.xfixes_delete_pointer_barrier(barrier)?;
+        }
+        // Rounding inwards, so that the pointer stays over the rectangle.
+        self.create_barriers(rect.to_px(self.scale.get()).trunc() + origin.to_vec2())
+    }
+
+    /// Keeps the pointer inside `rect`, in pixels of the root window, with a barrier along each
+    /// edge which can only be crossed inwards.
+    fn create_barriers(&self, rect: Rect) -> Result<(), Error> {
+        if !self.app.barriers_supported() {
+            warn!("XFixes pointer barriers are unavailable, the pointer can't be held");
+            return Ok(());
+        }
+        let conn = self.app.connection();
+        let (x0, y0, x1, y1) = (
+            rect.x0 as u16,
+            rect.y0 as u16,
+            rect.x1 as u16,
+            rect.y1 as u16,
+        );
+        let edges = [
+            (x0, y0, x0, y1, xfixes::BarrierDirections::POSITIVE_X),
+            (x1, y0, x1, y1, xfixes::BarrierDirections::NEGATIVE_X),
+            (x0, y0, x1, y0, xfixes::BarrierDirections::POSITIVE_Y),
+            (x0, y1, x1, y1, xfixes::BarrierDirections::NEGATIVE_Y),
+        ];
+        let mut barriers = borrow_mut!(self.barriers)?;
+        for (x0, y0, x1, y1, directions) in edges {
+            let barrier = conn.generate_id()?;
+            // Without devices, the barrier holds all of the master pointers.
+            conn.xfixes_create_pointer_barrier(barrier, self.id, x0, y0, x1, y1, directions, &[])?;
+            barriers.push(barrier);
+        }
+        Ok(())
+    }
+
+    fn root(&self) -> xproto::Window {
+        self.app.connection().setup().roots[self.app.screen_num()].root
+    }
+
+    /// The Xinerama index of `monitor`.
+    ///
+    /// This assumes that the X server lists its Xinerama screens in the same order as its RandR
+    /// monitors, which holds for servers where Xinerama is implemented on top of RandR.
+    fn monitor_index(&self, monitor: &Monitor) -> Option<u32> {
+        super::screen::get_monitors(&self.app)
+            .iter()
+            .position(|m| m.virtual_rect() == monitor.virtual_rect())
+            .map(|index| index as u32)
+    }
+
+    fn set_icon(&self, icon: Option<&Icon>) {
+        if self.destroyed() {
+            return;
+        }
+
+        let conn = self.app.connection();
+        let atom = self.app.atoms()._NET_WM_ICON;
+        if let Some(icon) = icon {
+            log_x11!(conn.change_property32(
+                xproto::PropMode::REPLACE,
+                self.id,
+                atom,
+                AtomEnum::CARDINAL,
+                &icon.to_net_wm_icon(),
+            ));
+        } else {
+            log_x11!(conn.delete_property(self.id, atom));
+        }
+    }
+
     fn set_cursor(&self, cursor: &Cursor) {
-        let cursors = &self.app.cursors;
-        #[allow(deprecated)]
+        *self.cursor.borrow_mut() = cursor.clone();
+        let conn = self.app.connection();
         let cursor = match cursor {
-            Cursor::Arrow => cursors.default,
-            Cursor::IBeam => cursors.text,
-            Cursor::Pointer => cursors.pointer,
-            Cursor::Crosshair => cursors.crosshair,
-            Cursor::OpenHand => {
-                warn!("Cursor::OpenHand not supported for x11 backend. using arrow cursor");
-                None
-            }
-            Cursor::NotAllowed => cursors.not_allowed,
-            Cursor::ResizeLeftRight => cursors.col_resize,
-            Cursor::ResizeUpDown => cursors.row_resize,
             Cursor::Custom(custom) => Some(custom.unwrap_x11().0),
+            cursor => self.app.cursors.get(conn, self.id, cursor),
         };
         if cursor.is_none() {
             warn!("Unable to load cursor {:?}", cursor);
             return;
         }
-        let conn = self.app.connection();
         let changes = ChangeWindowAttributesAux::new().cursor(cursor);
         if let Err(e) = conn.change_window_attributes(self.id, &changes) {
             error!("Changing cursor window attribute failed {}", e);
@@ -847,6 +1660,7 @@ impl Window {
         mods: ModifierInfo,
         detail: u32,
         src_id: u16,
+        time: xproto::Timestamp,
     ) -> PointerEvent {
         // In x11rb, xinput x and y coordinates are i32's but in the protocol they're fixed-precision FP1616s
         // https://github.com/psychon/x11rb/blob/dacfba5e2a8eef4b80df75d9bec9061c3d98d279/xcb-proto-1.15.2/src/xinput.xml#L2374
@@ -860,9 +1674,9 @@ impl Window {
         PointerEvent {
             pointer_id: PointerId(src_id as u64),
             is_primary: false,
-            pointer_type: PointerType::Mouse(MouseInfo {
-                wheel_delta: Default::default(),
-            }),
+            pointer_type: PointerType::Mouse(MouseInfo::default()),
+            time: linux::event_time(time),
+            coalesced: Vec::new(),
             pos: Point::new(ev_x, ev_y).to_dp(scale),
             buttons: pointer_buttons(mods),
             modifiers: key_mods(mods),
@@ -890,7 +1704,14 @@ impl Window {
             pointer_type,
             button,
             pointer_id: PointerId(ev.sourceid as u64 | (ev.detail as u64) << 32),
-            ..self.base_pointer_event(ev.event_x, ev.event_y, ev.mods, ev.detail, ev.sourceid)
+            ..self.base_pointer_event(
+                ev.event_x,
+                ev.event_y,
+                ev.mods,
+                ev.detail,
+                ev.sourceid,
+                ev.time,
+            )
         }
     }
 
@@ -908,52 +1729,64 @@ impl Window {
             false
         };
 
+        let base = self.base_pointer_event(
+            ev.event_x,
+            ev.event_y,
+            ev.mods,
+            ev.detail,
+            ev.sourceid,
+            ev.time,
+        );
         let pointer_type = if let Some(src_device) = src_device {
-            let pressure = src_device.valuators.pressure.as_ref().map_or(0.0, |val| {
-                let raw = val.read(&ev.axisvalues).unwrap_or(val.min);
-                // Scale to the range [0.0, 1.0].
-                (raw - val.min) / (val.max - val.min)
-            });
-            let x_tilt = src_device
-                .valuators
-                .x_tilt
-                .as_ref()
-                .map_or(0.0, |val| val.read(&ev.axisvalues).unwrap_or(0.0));
-            let y_tilt = src_device
-                .valuators
-                .y_tilt
-                .as_ref()
-                .map_or(0.0, |val| val.read(&ev.axisvalues).unwrap_or(0.0));
-
-            let inclination = PenInclination::from_tilt(x_tilt, y_tilt).unwrap_or_default();
-
-            let pen_info = PenInfo {
-                pressure,
-                tangential_pressure: 0.0,
+            let valuators = &src_device.valuators;
+            let (valuator_mask, axisvalues) = self.app.valuators(ev);
+            // Scaled to the range [0.0, 1.0].
+            let normalized = |val: &Option<ValuatorInfo>| {
+                val.as_ref()
+                    .and_then(|val| val.read_normalized(&valuator_mask, &axisvalues))
+            };
+            // The tilt valuators are in degrees.
+            let degrees = |val: &Option<ValuatorInfo>| {
+                val.as_ref().map_or(0.0, |val| {
+                    val.read(&valuator_mask, &axisvalues).unwrap_or(0.0)
+                })
+            };
+            let inclination =
+                PenInclination::from_tilt(degrees(&valuators.x_tilt), degrees(&valuators.y_tilt))
+                    .unwrap_or_default();
+            // Touching the tablet is the primary button, and the rest are on the barrel.
+            let mut barrel_buttons = base.buttons;
+            barrel_buttons.remove(PointerButton::Primary);
+
+            let pen_info = |tool| PenInfo {
+                pressure: normalized(&valuators.pressure).unwrap_or(0.0),
+                tangential_pressure: normalized(&valuators.throttle)
+                    .map_or(0.0, |throttle| throttle * 2.0 - 1.0),
                 inclination,
-                twist: Angle::degrees(0.0),
+                twist: Angle::degrees(
+                    normalized(&valuators.rotation).map_or(0.0, |rotation| rotation * 360.0)
+                        % 360.0,
+                ),
+                distance: normalized(&valuators.distance),
+                tool,
+                serial: None,
+                barrel_buttons,
             };
 
             match src_device.device_kind {
-                super::pointer::DeviceKind::Pen => PointerType::Pen(pen_info),
-                super::pointer::DeviceKind::Eraser => PointerType::Eraser(pen_info),
+                DeviceKind::Pen => PointerType::Pen(pen_info(PenTool::Pen)),
+                DeviceKind::Eraser => PointerType::Eraser(pen_info(PenTool::Eraser)),
                 // TODO: support touch
-                super::pointer::DeviceKind::Touch | super::pointer::DeviceKind::Mouse => {
-                    PointerType::Mouse(MouseInfo {
-                        wheel_delta: Vec2::ZERO,
-                    })
-                }
+                DeviceKind::Touch | DeviceKind::Mouse => PointerType::Mouse(MouseInfo::default()),
             }
         } else {
-            PointerType::Mouse(MouseInfo {
-                wheel_delta: Vec2::ZERO,
-            })
+            PointerType::Mouse(MouseInfo::default())
         };
 
         PointerEvent {
             is_primary,
             pointer_type,
-            ..self.base_pointer_event(ev.event_x, ev.event_y, ev.mods, ev.detail, ev.sourceid)
+            ..base
         }
     }
 
@@ -962,8 +1795,18 @@ impl Window {
         // The xcb state field doesn't include the newly pressed button, but
         // druid wants it to be included.
         pointer_ev.buttons = pointer_ev.buttons.with(pointer_ev.button);
-        // TODO: detect the count
-        pointer_ev.count = 1;
+        pointer_ev.count = self.click_counter.count_for_click(pointer_ev.pos);
+        // The coordinates are FP1616s, like in `base_pointer_event`.
+        self.button_press.set(Some(ButtonPress {
+            device: ev.deviceid,
+            time: ev.time,
+            button: ev.detail,
+            root_x: ev.root_x >> 16,
+            root_y: ev.root_y >> 16,
+            pointer_id: pointer_ev.pointer_id,
+            is_primary: pointer_ev.is_primary,
+            pos: pointer_ev.pos,
+        }));
         self.with_handler(|h| h.pointer_down(pointer_ev));
         Ok(())
     }
@@ -973,6 +1816,9 @@ impl Window {
         // The xcb state includes the newly released button, but druid
         // doesn't want it.
         pointer_ev.buttons = pointer_ev.buttons.without(pointer_ev.button);
+        if pointer_ev.buttons.is_empty() {
+            self.button_press.set(None);
+        }
         self.with_handler(|h| h.pointer_up(pointer_ev));
         Ok(())
     }
@@ -998,6 +1844,11 @@ impl Window {
     }
 
     pub fn handle_wheel(&self, ev: &xinput::ButtonPressEvent) -> Result<(), Error> {
+        // Devices with smooth scrolling also emulate the wheel buttons, but we use their
+        // valuators instead.
+        if (ev.flags | PointerEventFlags::POINTER_EMULATED) == ev.flags {
+            return Ok(());
+        }
         let mut pointer_ev = self.pointer_event(ev);
 
         // We use a delta of 120 per tick to match the behavior of Windows.
@@ -1011,8 +1862,21 @@ impl Window {
             7 => (120.0, 0.0),
             _ => return Err(anyhow!("unexpected mouse wheel button: {}", ev.detail)),
         };
+        let clicks = Vec2::from(delta) / 120.0;
         pointer_ev.pointer_type = PointerType::Mouse(MouseInfo {
             wheel_delta: delta.into(),
+            // X doesn't say how far a click scrolls
+            scroll: ScrollInfo {
+                delta: clicks,
+                unit: ScrollUnit::Lines,
+                clicks,
+                phase: None,
+                source: if ev.detail <= 5 {
+                    ScrollSource::Wheel
+                } else {
+                    ScrollSource::WheelTilt
+                },
+            },
         });
         pointer_ev.button = PointerButton::None;
 
@@ -1020,19 +1884,174 @@ impl Window {
         Ok(())
     }
 
-    pub fn handle_motion_notify(&self, ev: &xinput::ButtonPressEvent) -> Result<(), Error> {
+    /// Handle a motion event, with the earlier motion events which were merged into it.
+    pub fn handle_motion_notify(
+        &self,
+        ev: &xinput::ButtonPressEvent,
+        coalesced: &[xinput::ButtonPressEvent],
+    ) -> Result<(), Error> {
+        // Events which only scrolled don't say anything about the position. The merged events
+        // go first, as the valuators which an event leaves out keep their earlier values.
+        let mut samples: Vec<_> = coalesced
+            .iter()
+            .filter(|ev| pointer_moved(ev))
+            .map(|ev| self.pointer_event(ev))
+            .collect();
         let mut pointer_ev = self.pointer_event(ev);
         pointer_ev.button = PointerButton::None;
+
+        // Smooth scrolling is reported by the changes of the scroll valuators, in motion events.
+        let mut clicks = coalesced
+            .iter()
+            .chain([ev])
+            .fold(Vec2::ZERO, |clicks, ev| clicks + self.app.scroll_clicks(ev));
+        if clicks != Vec2::ZERO {
+            if pointer_ev.modifiers.shift() && clicks.x == 0.0 {
+                clicks = Vec2::new(clicks.y, 0.0);
+            }
+            let mut wheel_ev = pointer_ev.clone();
+            wheel_ev.pointer_type = PointerType::Mouse(MouseInfo {
+                // We use a delta of 120 per click, like the wheel buttons.
+                wheel_delta: clicks * 120.0,
+                // The valuators don't say which kind of device they belong to, but wheels only
+                // move in whole clicks.
+                scroll: ScrollInfo {
+                    delta: clicks,
+                    unit: ScrollUnit::Lines,
+                    clicks,
+                    phase: None,
+                    source: if clicks.x.fract() == 0.0 && clicks.y.fract() == 0.0 {
+                        ScrollSource::Wheel
+                    } else {
+                        ScrollSource::Continuous
+                    },
+                },
+            });
+            self.with_handler(|h| h.wheel(wheel_ev));
+        }
+        if clicks != Vec2::ZERO && !pointer_moved(ev) {
+            // The latest motion that was merged into this event takes its place.
+            let Some(latest) = samples.pop() else {
+                return Ok(());
+            };
+            pointer_ev = latest;
+            pointer_ev.button = PointerButton::None;
+        }
+        pointer_ev.coalesced = samples
+            .into_iter()
+            .map(|sample| PointerSample {
+                time: sample.time,
+                pos: sample.pos,
+                pointer_type: sample.pointer_type,
+            })
+            .collect();
         self.with_handler(|h| h.pointer_move(pointer_ev));
         Ok(())
     }
 
-    pub fn handle_leave_notify(
+    pub fn handle_gesture_pinch(&self, ev: &xinput::GesturePinchBeginEvent, phase: GesturePhase) {
+        let cancelled = (ev.flags | GesturePinchEventFlags::GESTURE_PINCH_CANCELLED) == ev.flags;
+        let phase = if cancelled {
+            GesturePhase::Cancel
+        } else {
+            phase
+        };
+        // Like the positions, the values are FP1616s.
+        let scale = ev.scale as f64 / 65536.0;
+        let zoom = scale - self.pinch_scale.replace(scale);
+        let gesture = Gesture::Pinch {
+            scale,
+            rotation: Angle::degrees(ev.delta_angle as f64 / 65536.0),
+            delta: self.gesture_delta(ev.delta_x, ev.delta_y),
+        };
+        let event = self.gesture_event(gesture, phase, ev.detail, ev.event_x, ev.event_y, ev.mods);
+        self.with_handler(|h| {
+            h.gesture(event);
+            if phase == GesturePhase::Update && zoom != 0.0 {
+                h.zoom(zoom);
+            }
+        });
+    }
+
+    pub fn handle_gesture_swipe(&self, ev: &xinput::GestureSwipeBeginEvent, phase: GesturePhase) {
+        let cancelled = (ev.flags | GestureSwipeEventFlags::GESTURE_SWIPE_CANCELLED) == ev.flags;
+        let phase = if cancelled {
+            GesturePhase::Cancel
+        } else {
+            phase
+        };
+        let gesture = Gesture::Swipe {
+            delta: self.gesture_delta(ev.delta_x, ev.delta_y),
+        };
+        let event = self.gesture_event(gesture, phase, ev.detail, ev.event_x, ev.event_y, ev.mods);
+        self.with_handler(|h| h.gesture(event));
+    }
+
+    /// The motion of a gesture in display points, from FP1616 pixels.
+    fn gesture_delta(&self, x: i32, y: i32) -> Vec2 {
+        let scale = self.scale.get();
+        Vec2::new(
+            x as f64 / 65536.0 / scale.x(),
+            y as f64 / 65536.0 / scale.y(),
+        )
+    }
+
+    fn gesture_event(
         &self,
-        _leave_notify: &xproto::LeaveNotifyEvent,
-    ) -> Result<(), Error> {
+        gesture: Gesture,
+        phase: GesturePhase,
+        fingers: u32,
+        x: i32,
+        y: i32,
+        mods: ModifierInfo,
+    ) -> GestureEvent {
+        let mods = mods.base | mods.locked | mods.latched;
+        GestureEvent {
+            gesture,
+            phase,
+            fingers,
+            pos: Point::new(x as f64 / 65536.0, y as f64 / 65536.0).to_dp(self.scale.get()),
+            modifiers: key_mods((mods as u16).into()),
+        }
+    }
+
+    pub fn handle_raw_motion(&self, ev: &xinput::RawMotionEvent) {
+        if self.pointer_grab.get() != PointerGrab::Lock {
+            return;
+        }
+        let delta = super::pointer::raw_motion_delta(&ev.valuator_mask, &ev.axisvalues);
+        if delta == Vec2::ZERO {
+            return;
+        }
+        let scale = self.scale.get();
+        let delta = Vec2::new(delta.x / scale.x(), delta.y / scale.y());
+        self.with_handler(|h| h.relative_pointer_move(delta));
+    }
+
+    pub fn handle_enter(&self, ev: &xinput::EnterEvent) {
+        if !pointer_crossed(ev) {
+            return;
+        }
+        // The scroll valuators may have changed while the pointer was elsewhere.
+        self.app.reset_scroll_positions();
+        let mut pointer_ev =
+            self.base_pointer_event(ev.event_x, ev.event_y, ev.mods, 0, ev.sourceid, ev.time);
+        pointer_ev.is_primary = self
+            .app
+            .pointer_device(ev.deviceid)
+            .map_or(false, |device| {
+                device.device_type == DeviceType::MASTER_POINTER
+            });
+        pointer_ev.buttons = xi_pointer_buttons(&ev.buttons);
+        self.with_handler(|h| h.pointer_enter(pointer_ev));
+    }
+
+    pub fn handle_leave(&self, ev: &xinput::LeaveEvent) {
+        if !pointer_crossed(ev) {
+            return;
+        }
+        self.app.reset_scroll_positions();
         self.with_handler(|h| h.pointer_leave());
-        Ok(())
     }
 
     pub fn handle_got_focus(&self) {
@@ -1070,17 +2089,255 @@ impl Window {
             let protocol = client_message.data.as_data32()[0];
             if protocol == atoms.WM_DELETE_WINDOW {
                 self.with_handler(|h| h.request_close());
+            } else if protocol == atoms._NET_WM_SYNC_REQUEST {
+                // The window manager is about to resize us, and will wait for us to set the
+                // counter to this value before showing the next frame.
+                // https://specifications.freedesktop.org/wm-spec/wm-spec-1.3.html#idm45805407916448
+                let data = client_message.data.as_data32();
+                self.sync_request.set(Some(sync::Int64 {
+                    lo: data[2],
+                    hi: data[3] as i32,
+                }));
+            }
+        } else if let Some(message) = XdndMessage::parse(atoms, client_message) {
+            log_x11!(self
+                .handle_xdnd_message(client_message, message)
+                .context("XDND message"));
+        }
+    }
+
+    fn handle_xdnd_message(
+        &self,
+        client_message: &xproto::ClientMessageEvent,
+        message: XdndMessage,
+    ) -> Result<(), Error> {
+        let (source, step) = match message {
+            XdndMessage::Enter { source, version } => {
+                if version > dnd::XDND_VERSION {
+                    // The source should have used the version from our XdndAware property.
+                    warn!("Ignoring drag with unsupported XDND version {}", version);
+                    return Ok(());
+                }
+                let formats =
+                    dnd::offered_formats(self.app.connection(), self.app.atoms(), client_message)?;
+                let data = DropData::new(formats, self.app.xdnd_selection());
+                if borrow_mut!(self.drop_target)?.enter(source, data) {
+                    self.with_handler(|h| h.drag_leave());
+                }
+                return Ok(());
+            }
+            XdndMessage::Position {
+                source,
+                root_pos,
+                time,
+                action,
+            } => {
+                let action = dnd::drop_action(self.app.atoms(), action);
+                let step = borrow_mut!(self.drop_target)?.position(source, root_pos, action);
+                if let DropStep::Enter(drag) | DropStep::Over(drag) = &step {
+                    drag.data.set_timestamp(time);
+                }
+                (source, step)
+            }
+            XdndMessage::Leave { source } => (source, borrow_mut!(self.drop_target)?.leave(source)),
+            XdndMessage::Drop { source, time } => {
+                let step = borrow_mut!(self.drop_target)?.drop(source);
+                if let DropStep::Drop(drag) = &step {
+                    drag.data.set_timestamp(time);
+                }
+                (source, step)
+            }
+        };
+
+        let (conn, atoms) = (self.app.connection(), self.app.atoms());
+        match step {
+            DropStep::Ignore => {}
+            DropStep::Enter(drag) => {
+                let event = self.drag_event(drag);
+                let action = self.with_handler(|h| h.drag_enter(&event));
+                self.reply_xdnd_status(source, action.unwrap_or_default())?;
+            }
+            DropStep::Over(drag) => {
+                let event = self.drag_event(drag);
+                let action = self.with_handler(|h| h.drag_over(&event));
+                self.reply_xdnd_status(source, action.unwrap_or_default())?;
+            }
+            DropStep::Leave => {
+                self.with_handler(|h| h.drag_leave());
+            }
+            DropStep::Drop(drag) => {
+                let event = self.drag_event(drag);
+                let action = self.with_handler(|h| h.drag_drop(&event));
+                dnd::send_finished(conn, atoms, self.id, source, action.unwrap_or_default())?;
+            }
+            DropStep::Refuse { leave } => {
+                if leave {
+                    self.with_handler(|h| h.drag_leave());
+                }
+                dnd::send_finished(conn, atoms, self.id, source, DropAction::None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remembers the action that the handler chose, and tells it to the source.
+    fn reply_xdnd_status(&self, source: xproto::Window, action: DropAction) -> Result<(), Error> {
+        borrow_mut!(self.drop_target)?.set_action(source, action);
+        dnd::send_status(
+            self.app.connection(),
+            self.app.atoms(),
+            self.id,
+            source,
+            action,
+        )?;
+        Ok(())
+    }
+
+    /// Creates the event for an XDND drag.
+    fn drag_event(&self, drag: DragInfo<DropData>) -> DragEvent {
+        // Our position on the root window is known from the last ConfigureNotify.
+        let origin = match self.position_px.get() {
+            Some(origin) => origin,
+            None => {
+                let origin = self.root_position_px();
+                log_x11!(&origin);
+                origin.unwrap_or_default()
             }
+        };
+        let root_pos = Point::new(drag.root_pos.0 as f64, drag.root_pos.1 as f64);
+        DragEvent {
+            pos: (root_pos - origin.to_vec2()).to_dp(self.scale.get()),
+            // XDND messages don't contain the modifiers, but XKB keeps us up to date.
+            modifiers: self.app.modifiers(),
+            action: drag.action,
+            data: crate::dnd::DropData(crate::backend::dnd::DropData::X11(drag.data)),
         }
     }
 
     #[allow(clippy::trivially_copy_pass_by_ref)]
     pub fn handle_destroy_notify(&self, _destroy_notify: &xproto::DestroyNotifyEvent) {
+        if self.modal {
+            if let Some(parent) = self.parent.upgrade() {
+                parent
+                    .modal_children
+                    .set(parent.modal_children.get().saturating_sub(1));
+            }
+        }
         self.with_handler(|h| h.destroy());
     }
 
     pub fn handle_configure_notify(&self, event: &ConfigureNotifyEvent) -> Result<(), Error> {
-        self.size_changed(Size::new(event.width as f64, event.height as f64))
+        let size = Size::new(event.width as f64, event.height as f64);
+        let resized = size != self.size().size_px();
+        self.size_changed(size)?;
+
+        if let Some(value) = self.sync_request.take() {
+            if resized {
+                // Update the counter once the new size is painted.
+                self.sync_after_paint.set(Some(value));
+                self.request_anim_frame();
+            } else {
+                // There's nothing new to paint, so the window manager doesn't need to wait.
+                self.update_sync_counter(value);
+            }
+        }
+
+        // Window managers send synthetic events in root coordinates when they move the window
+        // (ICCCM 4.1.5), but the coordinates of real events are relative to the parent. That is
+        // the root window until the window manager puts the window into a frame, and the
+        // position within the frame doesn't tell us anything.
+        let synthetic = event.response_type & 0x80 != 0;
+        let position = if synthetic || !self.reparented.get() {
+            Point::new(event.x as f64, event.y as f64)
+        } else {
+            match self.position_px.get() {
+                Some(position) => position,
+                // We'll know once the window manager moves the window.
+                None => return Ok(()),
+            }
+        };
+        let moved = self.position_px.replace(Some(position)) != Some(position);
+        if moved {
+            let position = position.to_dp(self.scale.get()) - self.parent_origin();
+            self.with_handler(|h| h.position(position));
+        }
+        if moved || resized {
+            self.update_monitor(Rect::from_origin_size(position, size));
+        }
+        if moved {
+            if let PointerGrab::Confine(rect) = self.pointer_grab.get() {
+                // The barriers are on the root window, so they need to follow the window.
+                self.confine_pointer(rect, position)?;
+                self.app.connection().flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn handle_reparent_notify(&self, event: &xproto::ReparentNotifyEvent) {
+        self.reparented.set(event.parent != self.root());
+    }
+
+    /// Reports the monitor of the window again, after the monitors have changed.
+    pub fn handle_monitors_changed(&self) {
+        if let Some(position) = self.position_px.get() {
+            self.update_monitor(Rect::from_origin_size(position, self.size().size_px()));
+        }
+    }
+
+    /// Reports the monitor that shows the largest part of `rect`, if it has changed.
+    fn update_monitor(&self, rect: Rect) {
+        let monitor_rect = super::screen::monitor_rect_at(&self.app, rect);
+        if self.monitor_rect.replace(monitor_rect) == monitor_rect {
+            return;
+        }
+        let monitor = monitor_rect.and_then(|monitor_rect| {
+            super::screen::get_monitors(&self.app)
+                .into_iter()
+                .find(|monitor| monitor.virtual_rect() == monitor_rect)
+        });
+        self.with_handler(|h| h.monitor(monitor));
+    }
+
+    /// Whether a modal window blocks the input to this window.
+    pub fn has_modal_child(&self) -> bool {
+        self.modal_children.get() > 0
+    }
+
+    pub fn handle_map_notify(&self, mapped: bool) {
+        self.mapped.set(mapped);
+        self.update_visibility();
+    }
+
+    pub fn handle_shm_completion(&self, event: &shm::CompletionEvent) -> Result<(), Error> {
+        if let Some(presenter) = borrow_mut!(self.presenter)?.as_mut() {
+            presenter.handle_completion(event);
+        }
+        Ok(())
+    }
+
+    pub fn handle_visibility_notify(&self, event: xproto::VisibilityNotifyEvent) {
+        // Under a compositing manager, windows are never reported as obscured.
+        self.obscured
+            .set(event.state == xproto::Visibility::FULLY_OBSCURED);
+        self.update_visibility();
+    }
+
+    pub fn handle_property_notify(&self, event: &xproto::PropertyNotifyEvent) -> Result<(), Error> {
+        let atoms = self.app.atoms();
+        if event.atom == atoms._NET_WM_STATE && !self.destroyed() {
+            let hidden = self.net_wm_state()?.contains(&atoms._NET_WM_STATE_HIDDEN);
+            self.hidden.set(hidden);
+            self.update_visibility();
+        }
+        Ok(())
+    }
+
+    fn update_visibility(&self) {
+        let visible = self.mapped.get() && !self.obscured.get() && !self.hidden.get();
+        if self.visible.replace(visible) != visible {
+            self.with_handler(|h| h.visibility(visible));
+        }
     }
 
     pub(crate) fn run_idle(&self) {
@@ -1169,6 +2426,38 @@ fn pointer_buttons(mods: KeyButMask) -> PointerButtons {
     buttons
 }
 
+/// Whether a motion event moved the pointer, rather than only scrolling.
+fn pointer_moved(ev: &xinput::ButtonPressEvent) -> bool {
+    let axis = |idx| super::pointer::valuator_value(&ev.valuator_mask, &ev.axisvalues, idx);
+    axis(0).is_some() || axis(1).is_some()
+}
+
+/// Whether an enter or leave event is about the pointer crossing the edge of the window.
+///
+/// Crossing events are also sent when a grab starts or ends, and when the pointer moves over a
+/// child window, but the pointer stays over the window in both cases.
+fn pointer_crossed(ev: &xinput::EnterEvent) -> bool {
+    let grab = [
+        xinput::NotifyMode::GRAB,
+        xinput::NotifyMode::UNGRAB,
+        xinput::NotifyMode::PASSIVE_GRAB,
+        xinput::NotifyMode::PASSIVE_UNGRAB,
+    ];
+    !grab.contains(&ev.mode) && ev.detail != xinput::NotifyDetail::INFERIOR
+}
+
+// Extracts the pointer buttons from the button mask of XInput events, which unlike the
+// modifiers also has the X1/X2 buttons.
+fn xi_pointer_buttons(mask: &[u32]) -> PointerButtons {
+    let mut buttons = PointerButtons::new();
+    for code in [1, 2, 3, 8, 9] {
+        if mask.first().map_or(false, |bits| bits & (1 << code) != 0) {
+            buttons.insert(pointer_button(code));
+        }
+    }
+    buttons
+}
+
 // Extracts the keyboard modifiers from, e.g., the `state` field of
 // `xcb::xproto::ButtonPressEvent`
 fn key_mods(mods: KeyButMask) -> Modifiers {
@@ -1292,6 +2581,54 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_min_size(&self, size: Size) {
+        if let Some(w) = self.window.upgrade() {
+            w.set_min_size(size);
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn set_max_size(&self, size: Option<Size>) {
+        if let Some(w) = self.window.upgrade() {
+            w.set_max_size(size);
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn set_resize_increments(&self, increments: Option<Size>) {
+        if let Some(w) = self.window.upgrade() {
+            w.set_resize_increments(increments);
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn set_aspect_ratio(&self, ratio: Option<f64>) {
+        if let Some(w) = self.window.upgrade() {
+            w.set_aspect_ratio(ratio);
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn set_input_region(&self, region: Option<Region>) {
+        if let Some(w) = self.window.upgrade() {
+            w.set_input_region(region);
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn set_opaque_region(&self, region: Option<Region>) {
+        if let Some(w) = self.window.upgrade() {
+            w.set_opaque_region(region);
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
     pub fn show_titlebar(&self, show_titlebar: bool) {
         if let Some(w) = self.window.upgrade() {
             w.show_titlebar(show_titlebar);
@@ -1339,19 +2676,93 @@ impl WindowHandle {
         }
     }
 
-    pub fn set_window_state(&self, _state: window::WindowState) {
-        warn!("WindowHandle::set_window_state is currently unimplemented for X11 backend.");
+    pub fn set_window_state(&self, state: window::WindowState) {
+        if let Some(w) = self.window.upgrade() {
+            if let Err(err) = w.set_window_state(state) {
+                error!(
+                    "Window::set_window_state - failed to set the state: {:#}",
+                    err
+                );
+            }
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
     }
 
     pub fn get_window_state(&self) -> window::WindowState {
-        warn!("WindowHandle::get_window_state is currently unimplemented for X11 backend.");
-        window::WindowState::Restored
+        if let Some(w) = self.window.upgrade() {
+            match w.get_window_state() {
+                Ok(state) => state,
+                Err(err) => {
+                    error!(
+                        "Window::get_window_state - failed to get the state: {:#}",
+                        err
+                    );
+                    window::WindowState::Restored
+                }
+            }
+        } else {
+            error!("Window {} has already been dropped", self.id);
+            window::WindowState::Restored
+        }
     }
 
     pub fn handle_titlebar(&self, _val: bool) {
         warn!("WindowHandle::handle_titlebar is currently unimplemented for X11 backend.");
     }
 
+    pub fn begin_move_drag(&self) {
+        if let Some(w) = self.window.upgrade() {
+            if let Err(err) = w.begin_move_resize(MOVERESIZE_MOVE) {
+                error!(
+                    "Window::begin_move_drag - failed to start the move: {:#}",
+                    err
+                );
+            }
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn begin_resize_drag(&self, edge: ResizeEdge) {
+        if let Some(w) = self.window.upgrade() {
+            if let Err(err) = w.begin_move_resize(moveresize_direction(edge)) {
+                error!(
+                    "Window::begin_resize_drag - failed to start the resize: {:#}",
+                    err
+                );
+            }
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn show_window_menu(&self, pos: Point) {
+        if let Some(w) = self.window.upgrade() {
+            if let Err(err) = w.show_window_menu(pos) {
+                error!(
+                    "Window::show_window_menu - failed to show the menu: {:#}",
+                    err
+                );
+            }
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn set_pointer_grab(&self, grab: PointerGrab) {
+        if let Some(w) = self.window.upgrade() {
+            if let Err(err) = w.set_pointer_grab(grab) {
+                error!(
+                    "Window::set_pointer_grab - failed to grab the pointer: {:#}",
+                    err
+                );
+            }
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
     pub fn bring_to_front_and_focus(&self) {
         if let Some(w) = self.window.upgrade() {
             w.bring_to_front_and_focus();
@@ -1392,6 +2803,42 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_icon(&self, icon: Option<&Icon>) {
+        if let Some(w) = self.window.upgrade() {
+            w.set_icon(icon);
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn present_pixels(&self, buffer: &PixelBuffer, damage: Option<&Region>) {
+        if let Some(w) = self.window.upgrade() {
+            if let Err(err) = w.present_pixels(buffer, damage) {
+                error!("Window::present_pixels - failed to present: {:#}", err);
+            }
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn set_drop_target(&self, enabled: bool) {
+        if let Some(w) = self.window.upgrade() {
+            w.set_drop_target(enabled);
+        } else {
+            error!("Window {} has already been dropped", self.id);
+        }
+    }
+
+    pub fn start_drag(
+        &self,
+        _formats: &[ClipboardFormat],
+        _icon: Option<DragIcon>,
+        _actions: &[DropAction],
+    ) {
+        // We only implement the target side of XDND.
+        warn!("WindowHandle::start_drag is currently unimplemented for X11 backend.");
+    }
+
     pub fn set_menu(&self, menu: Menu) {
         if let Some(w) = self.window.upgrade() {
             w.set_menu(menu);
@@ -1462,7 +2909,15 @@ impl WindowHandle {
                     let conn = w.app.connection();
                     let setup = &conn.setup();
                     let screen = &setup.roots[w.app.screen_num()];
-                    match make_cursor(conn, setup.image_byte_order, screen.root, format, desc) {
+                    let scale = w.scale.get();
+                    match make_cursor(
+                        conn,
+                        setup.image_byte_order,
+                        screen.root,
+                        format,
+                        desc,
+                        scale,
+                    ) {
                         // TODO: We 'leak' the cursor - nothing ever calls render_free_cursor
                         Ok(cursor) => Some(cursor),
                         Err(err) => {
@@ -1548,6 +3003,7 @@ unsafe impl HasRawDisplayHandle for WindowHandle {
         let mut handle = XcbDisplayHandle::empty();
         if let Some(window) = self.window.upgrade() {
             handle.connection = window.app.connection().get_raw_xcb_connection();
+            handle.screen = window.app.screen_num() as i32;
         } else {
             // Documentation for HasRawWindowHandle encourages filling in all fields possible,
             // leaving those empty that cannot be derived.
@@ -1557,11 +3013,51 @@ unsafe impl HasRawDisplayHandle for WindowHandle {
     }
 }
 fn make_cursor(
-    _conn: &XCBConnection,
-    _byte_order: X11ImageOrder,
-    _root_window: u32,
-    _argb32_format: Pictformat,
-    _desc: &CursorDesc,
+    conn: &XCBConnection,
+    byte_order: X11ImageOrder,
+    root_window: u32,
+    argb32_format: Pictformat,
+    desc: &CursorDesc,
+    scale: Scale,
 ) -> Result<Cursor, ReplyOrIdError> {
-    Ok(Cursor::Arrow)
+    // X11 cursors are in physical pixels, so the image is resized to the scale of the window.
+    let (image, hot) = desc.image_for_scale(scale.x());
+    let mut data = image.to_premultiplied_bgra();
+    if byte_order == X11ImageOrder::MSB_FIRST {
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.reverse();
+        }
+    }
+
+    // BEGIN: Lots of code just to get the image into a RENDER Picture
+    let (width, height) = (image.width() as u16, image.height() as u16);
+    let pixmap = xproto::PixmapWrapper::create_pixmap(conn, 32, root_window, width, height)?;
+    let gc = xproto::GcontextWrapper::create_gc(conn, pixmap.pixmap(), &Default::default())?;
+    conn.put_image(
+        xproto::ImageFormat::Z_PIXMAP,
+        pixmap.pixmap(),
+        gc.gcontext(),
+        width,
+        height,
+        0,
+        0,
+        0,
+        32,
+        &data,
+    )?;
+    let picture = render::PictureWrapper::create_picture(
+        conn,
+        pixmap.pixmap(),
+        argb32_format,
+        &Default::default(),
+    )?;
+    // END: Lots of code just to get the image into a RENDER Picture
+
+    let cursor = conn.generate_id()?;
+    let hot_x = hot.x.round().clamp(0.0, (width.max(1) - 1) as f64) as u16;
+    let hot_y = hot.y.round().clamp(0.0, (height.max(1) - 1) as f64) as u16;
+    conn.render_create_cursor(cursor, picture.picture(), hot_x, hot_y)?;
+    Ok(Cursor::Custom(crate::backend::window::CustomCursor::X11(
+        CustomCursor(cursor),
+    )))
 }
@@ -0,0 +1,665 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A client for the XSETTINGS protocol.
+//!
+//! Desktop environments (or a standalone daemon like `xsettingsd`) own the `_XSETTINGS_Sn`
+//! selection and publish settings such as the DPI and the double click time in the
+//! `_XSETTINGS_SETTINGS` property of the owner window.
+//!
+//! https://specifications.freedesktop.org/xsettings-spec/xsettings-latest.html
+
+use std::convert::TryInto;
+
+use x11rb::connection::Connection;
+use x11rb::errors::{ReplyError, ReplyOrIdError};
+use x11rb::protocol::xproto::{
+    Atom, ChangeWindowAttributesAux, ConnectionExt as _, EventMask, Window,
+};
+use x11rb::protocol::Event;
+
+use super::application::AppAtoms;
+
+/// The settings that we care about.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct XSettings {
+    /// `Xft/DPI`, in dots per inch.
+    pub dpi: Option<f64>,
+    /// `Gdk/WindowScalingFactor`, the integer scale used by GTK.
+    pub window_scaling_factor: Option<i32>,
+    /// `Net/DoubleClickTime`, in milliseconds.
+    pub double_click_time: Option<u64>,
+    /// `Net/DoubleClickDistance`, in pixels.
+    pub double_click_distance: Option<f64>,
+    /// `Net/CursorBlinkTime`, in milliseconds.
+    pub cursor_blink_time: Option<u64>,
+    /// `Gtk/CursorThemeName`
+    pub cursor_theme_name: Option<String>,
+    /// `Gtk/CursorThemeSize`
+    pub cursor_theme_size: Option<i32>,
+    /// `Net/ThemeName`
+    pub theme_name: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Integer(i32),
+    String(String),
+    Color,
+}
+
+impl XSettings {
+    /// Parses the contents of the `_XSETTINGS_SETTINGS` property.
+    ///
+    /// Returns `None` if the data is malformed.
+    pub fn parse(data: &[u8]) -> Option<XSettings> {
+        let mut reader = Reader::new(data)?;
+        let _serial = reader.u32()?;
+        let count = reader.u32()?;
+
+        let mut settings = XSettings::default();
+        for _ in 0..count {
+            let (name, value) = reader.setting()?;
+            match (name, value) {
+                // Xft/DPI is stored as 1024 * dpi, and -1 means "use the default".
+                ("Xft/DPI", Value::Integer(dpi)) if dpi > 0 => {
+                    settings.dpi = Some(dpi as f64 / 1024.)
+                }
+                ("Gdk/WindowScalingFactor", Value::Integer(factor)) if factor > 0 => {
+                    settings.window_scaling_factor = Some(factor)
+                }
+                ("Net/DoubleClickTime", Value::Integer(time)) if time > 0 => {
+                    settings.double_click_time = Some(time as u64)
+                }
+                ("Net/DoubleClickDistance", Value::Integer(distance)) if distance >= 0 => {
+                    settings.double_click_distance = Some(distance as f64)
+                }
+                ("Net/CursorBlinkTime", Value::Integer(time)) if time > 0 => {
+                    settings.cursor_blink_time = Some(time as u64)
+                }
+                ("Gtk/CursorThemeName", Value::String(name)) if !name.is_empty() => {
+                    settings.cursor_theme_name = Some(name)
+                }
+                ("Gtk/CursorThemeSize", Value::Integer(size)) if size > 0 => {
+                    settings.cursor_theme_size = Some(size)
+                }
+                ("Net/ThemeName", Value::String(name)) if !name.is_empty() => {
+                    settings.theme_name = Some(name)
+                }
+                _ => {}
+            }
+        }
+        Some(settings)
+    }
+}
+
+/// Reads the fields of the XSETTINGS wire format, in the byte order given by its first byte.
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Option<Reader<'a>> {
+        let big_endian = match data.first()? {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        let mut reader = Reader { data, big_endian };
+        // The byte order is followed by three bytes of padding.
+        reader.bytes(4)?;
+        Some(reader)
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(bytes)
+    }
+
+    /// Reads `len` bytes, followed by padding up to a multiple of four bytes.
+    fn padded_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes(len)?;
+        self.bytes((4 - len % 4) % 4)?;
+        Some(bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.bytes(2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.bytes(4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn setting(&mut self) -> Option<(&'a str, Value)> {
+        let kind = self.bytes(2)?[0];
+        let name_len = self.u16()? as usize;
+        let name = std::str::from_utf8(self.padded_bytes(name_len)?).ok()?;
+        let _last_change_serial = self.u32()?;
+        let value = match kind {
+            0 => Value::Integer(self.u32()? as i32),
+            1 => {
+                let len = self.u32()? as usize;
+                let value = self.padded_bytes(len)?;
+                Value::String(String::from_utf8_lossy(value).into_owned())
+            }
+            2 => {
+                // Red, green, blue and alpha, 16 bits each.
+                self.bytes(8)?;
+                Value::Color
+            }
+            _ => return None,
+        };
+        Some((name, value))
+    }
+}
+
+/// Tracks the owner of the `_XSETTINGS_Sn` selection and the settings it publishes.
+pub(crate) struct XSettingsClient {
+    root: Window,
+    /// The `_XSETTINGS_Sn` selection for our screen, or `NONE` if we couldn't watch it.
+    selection: Atom,
+    /// The window that currently owns `selection`, if any.
+    owner: Option<Window>,
+    settings: XSettings,
+}
+
+/// What an event means for the XSETTINGS client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Action {
+    /// The event isn't about the settings manager.
+    NotOurs,
+    /// The event is about the settings manager, but doesn't change anything.
+    Ignore,
+    /// The selection may have a new owner.
+    UpdateOwner,
+    /// The owner changed its settings.
+    Fetch(Window),
+}
+
+/// Ungrabs the server when dropped, so that errors while it is grabbed don't leave it grabbed.
+struct ServerGrab<'a, C: Connection>(&'a C);
+
+impl<'a, C: Connection> ServerGrab<'a, C> {
+    fn new(conn: &'a C) -> Result<Self, ReplyOrIdError> {
+        conn.grab_server()?;
+        Ok(ServerGrab(conn))
+    }
+}
+
+impl<C: Connection> Drop for ServerGrab<'_, C> {
+    fn drop(&mut self) {
+        if let Err(err) = self.0.ungrab_server().map(|_| self.0.flush()) {
+            tracing::error!("Failed to ungrab the server: {}", err);
+        }
+    }
+}
+
+impl XSettingsClient {
+    pub fn new(
+        conn: &impl Connection,
+        screen_num: usize,
+        atoms: &AppAtoms,
+    ) -> Result<XSettingsClient, ReplyOrIdError> {
+        let selection = format!("_XSETTINGS_S{}", screen_num);
+        let selection = conn.intern_atom(false, selection.as_bytes())?.reply()?.atom;
+
+        // A new settings manager announces itself with a MANAGER client message to the root
+        // window, which is sent with the StructureNotify event mask.
+        let root = conn.setup().roots[screen_num].root;
+        conn.change_window_attributes(
+            root,
+            &ChangeWindowAttributesAux::new().event_mask(EventMask::STRUCTURE_NOTIFY),
+        )?;
+
+        let mut client = XSettingsClient {
+            root,
+            selection,
+            owner: None,
+            settings: XSettings::default(),
+        };
+        client.update_owner(conn, atoms)?;
+        Ok(client)
+    }
+
+    /// A client which never finds a settings manager, for when watching the selection failed.
+    pub fn without_manager(conn: &impl Connection, screen_num: usize) -> XSettingsClient {
+        XSettingsClient {
+            root: conn.setup().roots[screen_num].root,
+            selection: x11rb::NONE,
+            owner: None,
+            settings: XSettings::default(),
+        }
+    }
+
+    /// The current settings.
+    pub fn settings(&self) -> &XSettings {
+        &self.settings
+    }
+
+    /// Finds the current owner of the selection, and reads its settings.
+    fn update_owner(
+        &mut self,
+        conn: &impl Connection,
+        atoms: &AppAtoms,
+    ) -> Result<(), ReplyOrIdError> {
+        if self.selection == x11rb::NONE {
+            return Ok(());
+        }
+        // Grab the server, so that the owner can't disappear between us asking for it and
+        // selecting events on it.
+        let grab = ServerGrab::new(conn)?;
+        let owner = conn.get_selection_owner(self.selection)?.reply();
+        let owner = match owner {
+            Ok(reply) if reply.owner != x11rb::NONE => {
+                let selected = conn
+                    .change_window_attributes(
+                        reply.owner,
+                        &ChangeWindowAttributesAux::new()
+                            .event_mask(EventMask::PROPERTY_CHANGE | EventMask::STRUCTURE_NOTIFY),
+                    )
+                    .map(|cookie| cookie.check());
+                match selected {
+                    Ok(Ok(())) => Some(reply.owner),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        drop(grab);
+
+        self.owner = owner;
+        self.settings = match owner {
+            Some(owner) => fetch(conn, atoms, owner),
+            None => XSettings::default(),
+        };
+        Ok(())
+    }
+
+    /// Decides what to do about `ev`, given the `MANAGER` and `_XSETTINGS_SETTINGS` atoms.
+    fn action(&self, manager: Atom, settings: Atom, ev: &Event) -> Action {
+        match ev {
+            Event::ClientMessage(ev) if ev.type_ == manager => {
+                if self.selection != x11rb::NONE && ev.data.as_data32()[1] == self.selection {
+                    Action::UpdateOwner
+                } else {
+                    Action::Ignore
+                }
+            }
+            Event::PropertyNotify(ev) if Some(ev.window) == self.owner && ev.atom == settings => {
+                Action::Fetch(ev.window)
+            }
+            // Maybe someone else took over already.
+            Event::DestroyNotify(ev) if Some(ev.window) == self.owner => Action::UpdateOwner,
+            // We only select events on the root window for the MANAGER messages.
+            Event::ConfigureNotify(ev) if ev.window == self.root => Action::Ignore,
+            _ => Action::NotOurs,
+        }
+    }
+
+    /// Handles events that are about the settings manager.
+    ///
+    /// Returns `None` if the event wasn't meant for us, otherwise returns whether the settings
+    /// changed.
+    pub fn handle_event(
+        &mut self,
+        conn: &impl Connection,
+        atoms: &AppAtoms,
+        ev: &Event,
+    ) -> Result<Option<bool>, ReplyOrIdError> {
+        let old_settings = self.settings.clone();
+        match self.action(atoms.MANAGER, atoms._XSETTINGS_SETTINGS, ev) {
+            Action::NotOurs => return Ok(None),
+            Action::Ignore => return Ok(Some(false)),
+            Action::UpdateOwner => self.update_owner(conn, atoms)?,
+            Action::Fetch(owner) => self.settings = fetch(conn, atoms, owner),
+        }
+        Ok(Some(self.settings != old_settings))
+    }
+}
+
+/// Reads the settings of `owner`.
+///
+/// The owner can disappear at any time, in which case there are no settings until the next
+/// owner announces itself.
+fn fetch(conn: &impl Connection, atoms: &AppAtoms, owner: Window) -> XSettings {
+    let reply = conn
+        .get_property(
+            false,
+            owner,
+            atoms._XSETTINGS_SETTINGS,
+            atoms._XSETTINGS_SETTINGS,
+            0,
+            u32::MAX,
+        )
+        .map_err(ReplyError::from)
+        .and_then(|cookie| cookie.reply());
+    let reply = match reply {
+        Ok(reply) => reply,
+        Err(err) => {
+            tracing::debug!("Failed to read the XSETTINGS of window {}: {}", owner, err);
+            return XSettings::default();
+        }
+    };
+    if reply.format != 8 {
+        return XSettings::default();
+    }
+    XSettings::parse(&reply.value).unwrap_or_else(|| {
+        tracing::warn!("Failed to parse the XSETTINGS of window {}", owner);
+        XSettings::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use x11rb::protocol::xproto::{
+        ClientMessageEvent, CreateWindowAux, DestroyNotifyEvent, PropMode, Property,
+        PropertyNotifyEvent, WindowClass, DESTROY_NOTIFY_EVENT, PROPERTY_NOTIFY_EVENT,
+    };
+    use x11rb::wrapper::ConnectionExt as _;
+    use x11rb::xcb_ffi::XCBConnection;
+    use x11rb::CURRENT_TIME;
+
+    use super::super::application::AppInner;
+    use super::*;
+    use crate::Scale;
+
+    fn setting(kind: u8, name: &str, value: &[u8]) -> Vec<u8> {
+        let mut data = vec![kind, 0];
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.resize((data.len() + 3) / 4 * 4, 0);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(value);
+        data
+    }
+
+    fn integer(name: &str, value: i32) -> Vec<u8> {
+        setting(0, name, &value.to_le_bytes())
+    }
+
+    fn string(name: &str, value: &str) -> Vec<u8> {
+        let mut data = (value.len() as u32).to_le_bytes().to_vec();
+        data.extend_from_slice(value.as_bytes());
+        data.resize((data.len() + 3) / 4 * 4, 0);
+        setting(1, name, &data)
+    }
+
+    fn settings(settings: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0, 0, 0, 0];
+        data.extend_from_slice(&7u32.to_le_bytes());
+        data.extend_from_slice(&(settings.len() as u32).to_le_bytes());
+        for setting in settings {
+            data.extend_from_slice(setting);
+        }
+        data
+    }
+
+    #[test]
+    fn parse_settings() {
+        let data = settings(&[
+            integer("Xft/DPI", 192 * 1024),
+            integer("Gdk/WindowScalingFactor", 2),
+            setting(2, "Gtk/ColorPalette", &[0; 8]),
+            integer("Net/DoubleClickTime", 400),
+            integer("Net/DoubleClickDistance", 5),
+            integer("Net/CursorBlinkTime", 1200),
+            string("Gtk/CursorThemeName", "Adwaita"),
+            integer("Gtk/CursorThemeSize", 48),
+            string("Net/ThemeName", "Adwaita-dark"),
+        ]);
+        assert_eq!(
+            XSettings::parse(&data),
+            Some(XSettings {
+                dpi: Some(192.),
+                window_scaling_factor: Some(2),
+                double_click_time: Some(400),
+                double_click_distance: Some(5.),
+                cursor_blink_time: Some(1200),
+                cursor_theme_name: Some("Adwaita".into()),
+                cursor_theme_size: Some(48),
+                theme_name: Some("Adwaita-dark".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_big_endian() {
+        let mut data = vec![1, 0, 0, 0];
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&7u16.to_be_bytes());
+        data.extend_from_slice(b"Xft/DPI\0");
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(&(96 * 1024u32).to_be_bytes());
+        assert_eq!(XSettings::parse(&data).unwrap().dpi, Some(96.));
+    }
+
+    #[test]
+    fn parse_truncated() {
+        let mut data = settings(&[string("Net/ThemeName", "Adwaita")]);
+        data.truncate(data.len() - 4);
+        assert_eq!(XSettings::parse(&data), None);
+        assert_eq!(XSettings::parse(&[]), None);
+    }
+
+    const ROOT: Window = 1;
+    const OWNER: Window = 2;
+    const SELECTION: Atom = 10;
+    const MANAGER: Atom = 11;
+    const SETTINGS: Atom = 12;
+
+    fn client(owner: Option<Window>) -> XSettingsClient {
+        XSettingsClient {
+            root: ROOT,
+            selection: SELECTION,
+            owner,
+            settings: XSettings::default(),
+        }
+    }
+
+    fn manager_message(selection: Atom) -> Event {
+        Event::ClientMessage(ClientMessageEvent::new(
+            32,
+            ROOT,
+            MANAGER,
+            [0, selection, OWNER, 0, 0],
+        ))
+    }
+
+    fn property_notify(window: Window, atom: Atom) -> Event {
+        Event::PropertyNotify(PropertyNotifyEvent {
+            response_type: PROPERTY_NOTIFY_EVENT,
+            sequence: 0,
+            window,
+            atom,
+            time: 0,
+            state: Property::NEW_VALUE,
+        })
+    }
+
+    fn destroy_notify(window: Window) -> Event {
+        Event::DestroyNotify(DestroyNotifyEvent {
+            response_type: DESTROY_NOTIFY_EVENT,
+            sequence: 0,
+            event: window,
+            window,
+        })
+    }
+
+    #[test]
+    fn new_manager_is_watched() {
+        let client = client(None);
+        assert_eq!(
+            client.action(MANAGER, SETTINGS, &manager_message(SELECTION)),
+            Action::UpdateOwner
+        );
+        // The manager of another screen, or of another kind of selection
+        assert_eq!(
+            client.action(MANAGER, SETTINGS, &manager_message(SELECTION + 1)),
+            Action::Ignore
+        );
+    }
+
+    #[test]
+    fn owner_events() {
+        let client = client(Some(OWNER));
+        assert_eq!(
+            client.action(MANAGER, SETTINGS, &property_notify(OWNER, SETTINGS)),
+            Action::Fetch(OWNER)
+        );
+        assert_eq!(
+            client.action(MANAGER, SETTINGS, &property_notify(OWNER, SETTINGS + 1)),
+            Action::NotOurs
+        );
+        assert_eq!(
+            client.action(MANAGER, SETTINGS, &destroy_notify(OWNER)),
+            Action::UpdateOwner
+        );
+        // Our own windows
+        assert_eq!(
+            client.action(MANAGER, SETTINGS, &property_notify(OWNER + 1, SETTINGS)),
+            Action::NotOurs
+        );
+        assert_eq!(
+            client.action(MANAGER, SETTINGS, &destroy_notify(OWNER + 1)),
+            Action::NotOurs
+        );
+    }
+
+    #[test]
+    fn without_manager_ignores_managers() {
+        let mut client = client(None);
+        client.selection = x11rb::NONE;
+        assert_eq!(
+            client.action(MANAGER, SETTINGS, &manager_message(x11rb::NONE)),
+            Action::Ignore
+        );
+    }
+
+    #[test]
+    fn default_dpi_is_ignored() {
+        let data = settings(&[integer("Xft/DPI", -1)]);
+        assert_eq!(XSettings::parse(&data), Some(XSettings::default()));
+    }
+
+    /// Publish `data` as the settings of the manager window `owner`.
+    fn publish(conn: &XCBConnection, atoms: &AppAtoms, owner: Window, data: &[u8]) {
+        conn.change_property8(
+            PropMode::REPLACE,
+            owner,
+            atoms._XSETTINGS_SETTINGS,
+            atoms._XSETTINGS_SETTINGS,
+            data,
+        )
+        .unwrap();
+        // Wait for the server, so that the change is seen by the other connections.
+        conn.get_input_focus().unwrap().reply().unwrap();
+    }
+
+    /// Acts as the settings manager of the X server in `DISPLAY`, and is skipped without one,
+    /// or if there already is a settings manager.
+    #[test]
+    fn app_follows_the_manager() {
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+        let (conn, screen_num) = XCBConnection::connect(None).unwrap();
+        let atoms = AppAtoms::new(&conn).unwrap().reply().unwrap();
+        let selection = format!("_XSETTINGS_S{}", screen_num);
+        let selection = conn
+            .intern_atom(false, selection.as_bytes())
+            .unwrap()
+            .reply()
+            .unwrap()
+            .atom;
+        if conn
+            .get_selection_owner(selection)
+            .unwrap()
+            .reply()
+            .unwrap()
+            .owner
+            != x11rb::NONE
+        {
+            return;
+        }
+
+        let root = conn.setup().roots[screen_num].root;
+        let owner = conn.generate_id().unwrap();
+        conn.create_window(
+            0,
+            owner,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new(),
+        )
+        .unwrap();
+        let data = settings(&[
+            integer("Xft/DPI", 96 * 1024),
+            integer("Net/DoubleClickTime", 400),
+        ]);
+        publish(&conn, &atoms, owner, &data);
+        conn.set_selection_owner(owner, selection, CURRENT_TIME)
+            .unwrap();
+        let manager = ClientMessageEvent::new(
+            32,
+            root,
+            atoms.MANAGER,
+            [CURRENT_TIME, selection, owner, 0, 0],
+        );
+        conn.send_event(false, root, EventMask::STRUCTURE_NOTIFY, manager)
+            .unwrap();
+        conn.get_input_focus().unwrap().reply().unwrap();
+
+        let app = AppInner::new().unwrap();
+        assert_eq!(app.scale(), Scale::new(1., 1.));
+        assert_eq!(app.double_click_settings().0, Some(400));
+
+        let data = settings(&[
+            integer("Xft/DPI", 192 * 1024),
+            integer("Net/DoubleClickTime", 250),
+        ]);
+        publish(&conn, &atoms, owner, &data);
+        // A round trip makes sure that the PropertyNotify has arrived.
+        let app_conn = app.connection();
+        app_conn.get_input_focus().unwrap().reply().unwrap();
+        while let Some(ev) = app_conn.poll_for_event().unwrap() {
+            app.handle_event(&ev, &[]).unwrap();
+        }
+        assert_eq!(app.scale(), Scale::new(2., 2.));
+        assert_eq!(app.double_click_settings().0, Some(250));
+    }
+}
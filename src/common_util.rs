@@ -27,9 +27,9 @@ use crate::kurbo::Point;
 use crate::WinHandler;
 
 // This is the default timing on windows.
-const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+pub(crate) const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
 // the max distance between two clicks for them to count as a multi-click
-const MULTI_CLICK_MAX_DISTANCE: f64 = 5.0;
+pub(crate) const MULTI_CLICK_MAX_DISTANCE: f64 = 5.0;
 
 /// Strip the access keys from the menu string.
 ///
@@ -52,6 +52,14 @@ pub fn strip_access_key(raw_menu_text: &str) -> String {
     result
 }
 
+/// The instant of an event which happened `age` ago, to convert the timestamps of the
+/// platforms' clocks.
+#[cfg(not(feature = "headless"))]
+pub(crate) fn instant_from_age(age: Duration) -> Instant {
+    let now = Instant::now();
+    now.checked_sub(age).unwrap_or(now)
+}
+
 #[cfg_attr(feature = "wayland", allow(unused))]
 pub(crate) type IdleCallback = Box<dyn for<'a> FnOnce(&'a mut dyn WinHandler) + Send>;
 
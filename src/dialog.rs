@@ -37,9 +37,12 @@ pub struct FileInfo {
 }
 
 /// Type of file dialog.
-#[cfg(not(all(
-    any(feature = "x11", feature = "wayland"),
-    any(target_os = "freebsd", target_os = "linux", target_os = "openbsd")
+#[cfg(not(any(
+    feature = "headless",
+    all(
+        any(feature = "x11", feature = "wayland"),
+        any(target_os = "freebsd", target_os = "linux", target_os = "openbsd")
+    )
 )))]
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum FileDialogType {
@@ -0,0 +1,149 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Drag and drop.
+
+use crate::backend::dnd as backend;
+use crate::clipboard::FormatId;
+use crate::kurbo::Point;
+use crate::Modifiers;
+
+/// What happens to the data when it is dropped.
+///
+/// A drop target returns the action it would perform from [`WinHandler::drag_enter`] and
+/// [`WinHandler::drag_over`], which lets the source of the drag show the appropriate feedback.
+///
+/// [`WinHandler::drag_enter`]: crate::WinHandler::drag_enter
+/// [`WinHandler::drag_over`]: crate::WinHandler::drag_over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropAction {
+    /// The drop is not accepted.
+    #[default]
+    None,
+    /// The data is copied to the target.
+    Copy,
+    /// The data is moved to the target, the source will delete it after the drop.
+    Move,
+    /// The target creates a link to the data.
+    Link,
+}
+
+/// A drag that is moving over, or was dropped onto, a window.
+#[derive(Debug, Clone)]
+pub struct DragEvent {
+    /// The position of the drag, in display points relative to the window.
+    pub pos: Point,
+    /// The keyboard modifiers at the time of the event.
+    pub modifiers: Modifiers,
+    /// The action that the source of the drag asks for, usually chosen from the modifiers.
+    ///
+    /// The target can return a different action. This is [`DropAction::None`] if the source
+    /// doesn't say.
+    pub action: DropAction,
+    /// The data that is being dragged.
+    pub data: DropData,
+}
+
+/// The data offered by the source of a drag.
+///
+/// This works just like the [`Clipboard`]: the data is available in a number of formats,
+/// identified by [`FormatId`]s, and it is only transferred from the source when it is asked for.
+///
+/// Reading the data is only possible while the drag is in progress, so it should be done
+/// from [`WinHandler::drag_drop`]. Some platforms also allow it in [`WinHandler::drag_over`],
+/// but that is expensive if the data is large.
+///
+/// [`Clipboard`]: crate::Clipboard
+/// [`WinHandler::drag_drop`]: crate::WinHandler::drag_drop
+/// [`WinHandler::drag_over`]: crate::WinHandler::drag_over
+#[derive(Debug, Clone)]
+pub struct DropData(pub(crate) backend::DropData);
+
+impl DropData {
+    /// Get the dragged data as a string, if it is available as text.
+    pub fn get_string(&self) -> Option<String> {
+        self.0.get_string()
+    }
+
+    /// Given a list of supported types, returns the first one that the source offers, or `None`
+    /// if no types are supported.
+    pub fn preferred_format(&self, formats: &[FormatId]) -> Option<FormatId> {
+        self.0.preferred_format(formats)
+    }
+
+    /// Return the data in a given format, if available.
+    ///
+    /// It is recommended that the [`FormatId`] argument be a format returned by
+    /// [`DropData::preferred_format()`].
+    pub fn get_format(&self, format: FormatId) -> Option<Vec<u8>> {
+        self.0.get_format(format)
+    }
+
+    /// The identifiers of all the formats offered by the source.
+    pub fn available_type_names(&self) -> Vec<String> {
+        self.0.available_type_names()
+    }
+}
+
+impl From<backend::DropData> for DropData {
+    fn from(src: backend::DropData) -> DropData {
+        DropData(src)
+    }
+}
+
+/// An image that follows the pointer during a drag started with [`WindowHandle::start_drag`].
+///
+/// [`WindowHandle::start_drag`]: crate::WindowHandle::start_drag
+#[derive(Debug, Clone)]
+// Only the Wayland backend can start drags so far.
+#[cfg_attr(
+    not(all(
+        feature = "wayland",
+        not(feature = "headless"),
+        any(target_os = "freebsd", target_os = "linux", target_os = "openbsd")
+    )),
+    allow(dead_code)
+)]
+pub struct DragIcon {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) data: Vec<u8>,
+    pub(crate) hot_spot: Point,
+}
+
+impl DragIcon {
+    /// Create a new `DragIcon` from RGBA pixels.
+    ///
+    /// `data` must contain `width * height` pixels, row by row, with four bytes per pixel that
+    /// are not premultiplied by alpha. The pixels are in physical pixels, and `hot_spot` is the
+    /// point of the image, in display points, that is under the pointer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` has the wrong length.
+    pub fn new(width: u32, height: u32, data: impl Into<Vec<u8>>, hot_spot: Point) -> DragIcon {
+        let data = data.into();
+        assert_eq!(
+            data.len(),
+            width as usize * height as usize * 4,
+            "DragIcon data has the wrong length"
+        );
+        DragIcon {
+            width,
+            height,
+            data,
+            hot_spot,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Icons for windows.
+
+/// An icon for a window, which the platform shows in places like the task bar and the window
+/// switcher.
+///
+/// An icon has images at one or more sizes, and the platform picks the ones that fit best.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Icon {
+    images: Vec<IconImage>,
+}
+
+/// One of the sizes of an [`Icon`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IconImage {
+    pub width: u32,
+    pub height: u32,
+    /// Four bytes per pixel, which are not premultiplied by alpha.
+    pub rgba: Vec<u8>,
+}
+
+impl Icon {
+    /// Create an icon from an image.
+    ///
+    /// Each pixel of `rgba` has four bytes, which are not premultiplied by alpha, and there is
+    /// no padding between rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rgba` doesn't have four bytes for each pixel.
+    pub fn from_rgba(width: u32, height: u32, rgba: impl Into<Vec<u8>>) -> Icon {
+        Icon { images: vec![] }.with_rgba(width, height, rgba)
+    }
+
+    /// Add an image at another size, in the same format as [`Icon::from_rgba`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rgba` doesn't have four bytes for each pixel.
+    pub fn with_rgba(mut self, width: u32, height: u32, rgba: impl Into<Vec<u8>>) -> Icon {
+        let rgba = rgba.into();
+        assert_eq!(
+            rgba.len(),
+            width as usize * height as usize * 4,
+            "the icon data doesn't match the size of the image"
+        );
+        self.images.push(IconImage {
+            width,
+            height,
+            rgba,
+        });
+        self
+    }
+
+    /// The sizes of the icon, in the order that they were added.
+    #[allow(dead_code)] // Not yet used on all platforms.
+    pub(crate) fn images(&self) -> &[IconImage] {
+        &self.images
+    }
+
+    /// The icon in the format of the `_NET_WM_ICON` property.
+    ///
+    /// Each image is its width and height, followed by its pixels as ARGB.
+    #[allow(dead_code)] // Not yet used on all platforms.
+    pub(crate) fn to_net_wm_icon(&self) -> Vec<u32> {
+        let mut out = vec![];
+        for image in &self.images {
+            out.push(image.width);
+            out.push(image.height);
+            out.extend(
+                image
+                    .rgba
+                    .chunks_exact(4)
+                    .map(|p| u32::from_be_bytes([p[3], p[0], p[1], p[2]])),
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn net_wm_icon() {
+        let small = [0x11, 0x22, 0x33, 0x44];
+        let large = [0xff, 0, 0, 0xff, 0, 0, 0xff, 0x80];
+        let icon = Icon::from_rgba(1, 1, small).with_rgba(2, 1, large);
+        assert_eq!(
+            icon.to_net_wm_icon(),
+            [1, 1, 0x44112233, 2, 1, 0xffff0000, 0x800000ff]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_length() {
+        Icon::from_rgba(2, 2, vec![0; 12]);
+    }
+}
@@ -27,6 +27,7 @@ pub type KbKey = keyboard_types::Key;
 /// [`KeyboardEvent`]: keyboard_types::KeyboardEvent
 #[non_exhaustive]
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyEvent {
     /// Whether the key is pressed or released.
     pub state: KeyState,
@@ -48,39 +48,51 @@ mod backend;
 mod clipboard;
 mod common_util;
 mod dialog;
+mod dnd;
 mod error;
 mod hotkey;
+mod icon;
 mod keyboard;
 mod menu;
 mod mouse;
+mod pixels;
 mod pointer;
 mod region;
 mod scale;
 mod screen;
+#[cfg(test)]
+#[allow(dead_code)] // Each backend's tests only use some of the helpers.
+mod test_util;
 mod window;
 
 pub mod platform;
+#[cfg(feature = "serde")]
+pub mod record;
 pub mod text;
 
 pub use application::{AppHandle, AppHandler, Application};
 pub use clipboard::{Clipboard, ClipboardFormat, FormatId};
 pub use common_util::Counter;
 pub use dialog::{FileDialogOptions, FileInfo, FileSpec};
+pub use dnd::{DragEvent, DragIcon, DropAction, DropData};
 pub use error::Error;
 pub use hotkey::{HotKey, RawMods, SysMods};
+pub use icon::Icon;
 pub use keyboard::{Code, IntoKey, KbKey, KeyEvent, KeyState, Location, Modifiers, ModifiersExt};
 pub use menu::Menu;
-pub use mouse::{Cursor, CursorDesc};
+pub use mouse::{Cursor, CursorDesc, CursorImage};
+pub use pixels::{PixelBuffer, PixelFormat};
 pub use pointer::{
-    MouseInfo, PenInclination, PenInfo, PointerButton, PointerButtons, PointerEvent, PointerId,
-    PointerType, TouchInfo,
+    Gesture, GestureEvent, GesturePhase, MouseInfo, PenInclination, PenInfo, PenTool,
+    PointerButton, PointerButtons, PointerEvent, PointerId, PointerSample, PointerType, ScrollInfo,
+    ScrollPhase, ScrollSource, ScrollUnit, TouchInfo,
 };
 pub use region::Region;
 pub use scale::{Scalable, Scale, ScaledArea};
 pub use screen::{Monitor, Screen};
 pub use window::{
-    FileDialogToken, IdleHandle, IdleToken, TextFieldToken, TimerToken, WinHandler, WindowBuilder,
-    WindowHandle, WindowLevel, WindowState,
+    FileDialogToken, IdleHandle, IdleToken, PointerGrab, ResizeEdge, TextFieldToken, TimerToken,
+    WinHandler, WindowBuilder, WindowHandle, WindowLevel, WindowState,
 };
 
 pub use keyboard_types;
@@ -16,11 +16,11 @@
 
 use crate::backend;
 use crate::kurbo::Point;
-// use crate::piet::ImageBuf;
 
-//NOTE: this currently only contains cursors that are included by default on
-//both Windows and macOS. We may want to provide polyfills for various additional cursors.
 /// Mouse cursors.
+///
+/// These follow the cursors of CSS. Platforms which don't have a cursor use a similar one, or
+/// the arrow if there is none.
 #[derive(Clone, PartialEq, Eq)]
 pub enum Cursor {
     /// The default arrow cursor.
@@ -30,23 +30,161 @@ pub enum Cursor {
     Pointer,
     Crosshair,
 
-    #[deprecated(note = "this will be removed in future because it is not available on windows")]
+    #[deprecated(note = "use `Cursor::Grab` instead")]
     OpenHand,
     NotAllowed,
+    /// Resizing horizontally in both directions.
     ResizeLeftRight,
+    /// Resizing vertically in both directions.
     ResizeUpDown,
+    /// The program is busy, and the user can't interact with it.
+    Wait,
+    /// The program is busy in the background, but the user can still interact with it.
+    Progress,
+    /// Help is available.
+    Help,
+    /// Something can be moved.
+    Move,
+    /// Something can be grabbed, to drag or pan it.
+    Grab,
+    /// Something is being grabbed.
+    Grabbing,
+    /// Moving the top edge of something.
+    ResizeUp,
+    /// Moving the bottom edge of something.
+    ResizeDown,
+    /// Moving the left edge of something.
+    ResizeLeft,
+    /// Moving the right edge of something.
+    ResizeRight,
+    /// Moving the top left corner of something.
+    ResizeUpLeft,
+    /// Moving the top right corner of something.
+    ResizeUpRight,
+    /// Moving the bottom left corner of something.
+    ResizeDownLeft,
+    /// Moving the bottom right corner of something.
+    ResizeDownRight,
+    /// Resizing diagonally, from the top left to the bottom right.
+    ResizeUpLeftDownRight,
+    /// Resizing diagonally, from the top right to the bottom left.
+    ResizeUpRightDownLeft,
+    /// Resizing a column, for example with a splitter between two panes.
+    ColResize,
+    /// Resizing a row, for example with a splitter between two panes.
+    RowResize,
+    ZoomIn,
+    ZoomOut,
+    /// Inserting vertical text.
+    VerticalText,
+    /// Dropping copies the dragged data.
+    Copy,
+    /// Dropping creates a link to the dragged data.
+    Alias,
+    /// A context menu is available.
+    ContextMenu,
+    /// The dragged data can't be dropped here.
+    NoDrop,
+    /// No cursor is shown.
+    Hidden,
     // The platform cursor should be small. Any image data that it uses should be shared (i.e.
     // behind an `Arc` or using a platform API that does the sharing).
     Custom(backend::window::CustomCursor),
 }
 
+/// The pixels of a custom cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorImage {
+    width: u32,
+    height: u32,
+    stride: usize,
+    data: Vec<u8>,
+}
+
+impl CursorImage {
+    /// Creates a new `CursorImage` from RGBA pixels.
+    ///
+    /// Each pixel has four bytes, which are not premultiplied by alpha. Rows start `stride`
+    /// bytes apart, so `stride` must be at least `width * 4`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stride is too small, or if `data` is too short.
+    pub fn new(width: u32, height: u32, stride: usize, data: impl Into<Vec<u8>>) -> CursorImage {
+        let data = data.into();
+        let row_len = width as usize * 4;
+        assert!(stride >= row_len, "CursorImage stride is too small");
+        if height > 0 {
+            assert!(
+                data.len() >= stride * (height as usize - 1) + row_len,
+                "CursorImage data is too short"
+            );
+        }
+        CursorImage {
+            width,
+            height,
+            stride,
+            data,
+        }
+    }
+
+    /// The width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of bytes between the starts of two rows.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The RGBA value of the pixel at `(x, y)`.
+    #[allow(dead_code)] // Not yet used on all platforms.
+    pub(crate) fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = y as usize * self.stride + x as usize * 4;
+        [
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]
+    }
+
+    /// The pixels as premultiplied BGRA, without padding between rows.
+    ///
+    /// This is the little endian ARGB32 format used by X11 and Wayland.
+    #[allow(dead_code)] // Not yet used on all platforms.
+    pub(crate) fn to_premultiplied_bgra(&self) -> Vec<u8> {
+        let premultiply = |c: u8, a: u8| ((c as u16 * a as u16 + 127) / 255) as u8;
+        let mut out = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let [r, g, b, a] = self.pixel(x, y);
+                out.extend([premultiply(b, a), premultiply(g, a), premultiply(r, a), a]);
+            }
+        }
+        out
+    }
+}
+
 /// A platform-independent description of a custom cursor.
 #[derive(Clone)]
 pub struct CursorDesc {
-    // #[allow(dead_code)] // Not yet used on all platforms.
-    // pub(crate) image: ImageBuf,
+    #[allow(dead_code)] // Not yet used on all platforms.
+    pub(crate) image: CursorImage,
     #[allow(dead_code)] // Not yet used on all platforms.
     pub(crate) hot: Point,
+    #[allow(dead_code)] // Not yet used on all platforms.
+    pub(crate) scale: f64,
 }
 
 impl CursorDesc {
@@ -56,30 +194,121 @@ impl CursorDesc {
     /// `(0, 0)` at the top left. The hot spot is the logical position of the mouse cursor within
     /// the image. For example, if the image is a picture of a arrow, the hot spot might be the
     /// coordinates of the arrow's tip.
-    pub fn new(
-        //image: ImageBuf,
-        hot: impl Into<Point>,
-    ) -> CursorDesc {
+    pub fn new(image: CursorImage, hot: impl Into<Point>) -> CursorDesc {
         CursorDesc {
-            //image,
+            image,
             hot: hot.into(),
+            scale: 1.0,
+        }
+    }
+
+    /// Set the scale that the image was drawn for.
+    ///
+    /// An image with a scale of 2.0 has twice as many pixels as the cursor has display points.
+    /// When the cursor is shown at a different scale, the image is resized. The default is 1.0.
+    pub fn scale(mut self, scale: f64) -> CursorDesc {
+        self.scale = scale;
+        self
+    }
+
+    /// The image and the hot spot for showing the cursor at `scale`.
+    ///
+    /// The image is resized with nearest neighbour sampling, so that cursors with sharp edges
+    /// stay sharp.
+    #[allow(dead_code)] // Not yet used on all platforms.
+    pub(crate) fn image_for_scale(&self, scale: f64) -> (CursorImage, Point) {
+        let factor = scale / self.scale;
+        if (factor - 1.0).abs() < 1e-6 || self.image.width == 0 || self.image.height == 0 {
+            return (self.image.clone(), self.hot);
         }
+        let width = ((self.image.width as f64 * factor).round() as u32).max(1);
+        let height = ((self.image.height as f64 * factor).round() as u32).max(1);
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            let src_y = ((y as f64 + 0.5) / factor) as u32;
+            for x in 0..width {
+                let src_x = ((x as f64 + 0.5) / factor) as u32;
+                data.extend(self.image.pixel(
+                    src_x.min(self.image.width - 1),
+                    src_y.min(self.image.height - 1),
+                ));
+            }
+        }
+        let hot = Point::new(self.hot.x * factor, self.hot.y * factor);
+        (
+            CursorImage::new(width, height, width as usize * 4, data),
+            hot,
+        )
     }
 }
 
 impl std::fmt::Debug for Cursor {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         #[allow(deprecated)]
-        match self {
-            Cursor::Arrow => write!(f, "Cursor::Arrow"),
-            Cursor::IBeam => write!(f, "Cursor::IBeam"),
-            Cursor::Pointer => write!(f, "Cursor::Pointer"),
-            Cursor::Crosshair => write!(f, "Cursor::Crosshair"),
-            Cursor::OpenHand => write!(f, "Cursor::OpenHand"),
-            Cursor::NotAllowed => write!(f, "Cursor::NotAllowed"),
-            Cursor::ResizeLeftRight => write!(f, "Cursor::ResizeLeftRight"),
-            Cursor::ResizeUpDown => write!(f, "Cursor::ResizeUpDown"),
-            Cursor::Custom(_) => write!(f, "Cursor::Custom"),
-        }
+        let name = match self {
+            Cursor::Arrow => "Arrow",
+            Cursor::IBeam => "IBeam",
+            Cursor::Pointer => "Pointer",
+            Cursor::Crosshair => "Crosshair",
+            Cursor::OpenHand => "OpenHand",
+            Cursor::NotAllowed => "NotAllowed",
+            Cursor::ResizeLeftRight => "ResizeLeftRight",
+            Cursor::ResizeUpDown => "ResizeUpDown",
+            Cursor::Wait => "Wait",
+            Cursor::Progress => "Progress",
+            Cursor::Help => "Help",
+            Cursor::Move => "Move",
+            Cursor::Grab => "Grab",
+            Cursor::Grabbing => "Grabbing",
+            Cursor::ResizeUp => "ResizeUp",
+            Cursor::ResizeDown => "ResizeDown",
+            Cursor::ResizeLeft => "ResizeLeft",
+            Cursor::ResizeRight => "ResizeRight",
+            Cursor::ResizeUpLeft => "ResizeUpLeft",
+            Cursor::ResizeUpRight => "ResizeUpRight",
+            Cursor::ResizeDownLeft => "ResizeDownLeft",
+            Cursor::ResizeDownRight => "ResizeDownRight",
+            Cursor::ResizeUpLeftDownRight => "ResizeUpLeftDownRight",
+            Cursor::ResizeUpRightDownLeft => "ResizeUpRightDownLeft",
+            Cursor::ColResize => "ColResize",
+            Cursor::RowResize => "RowResize",
+            Cursor::ZoomIn => "ZoomIn",
+            Cursor::ZoomOut => "ZoomOut",
+            Cursor::VerticalText => "VerticalText",
+            Cursor::Copy => "Copy",
+            Cursor::Alias => "Alias",
+            Cursor::ContextMenu => "ContextMenu",
+            Cursor::NoDrop => "NoDrop",
+            Cursor::Hidden => "Hidden",
+            Cursor::Custom(_) => "Custom",
+        };
+        write!(f, "Cursor::{}", name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cursor_image_is_resized_for_scale() {
+        #[rustfmt::skip]
+        let data = [
+            1, 0, 0, 255, 2, 0, 0, 255, 0, 0,
+            3, 0, 0, 255, 4, 0, 0, 255, 0, 0,
+        ];
+        let image = CursorImage::new(2, 2, 10, data);
+        let desc = CursorDesc::new(image, (1.0, 1.0));
+        let (image, hot) = desc.image_for_scale(2.0);
+        assert_eq!((image.width(), image.height()), (4, 4));
+        assert_eq!(hot, Point::new(2.0, 2.0));
+        let reds: Vec<u8> = image.data().chunks(4).map(|px| px[0]).collect();
+        assert_eq!(reds, [1, 1, 2, 2, 1, 1, 2, 2, 3, 3, 4, 4, 3, 3, 4, 4]);
+    }
+
+    #[test]
+    fn cursor_image_is_premultiplied() {
+        let image = CursorImage::new(1, 1, 4, [255, 128, 0, 128]);
+        assert_eq!(image.to_premultiplied_bgra(), [0, 64, 128, 128]);
     }
 }
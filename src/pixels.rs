@@ -0,0 +1,222 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Images in memory, which can be shown without a GPU.
+
+use crate::kurbo::Rect;
+use crate::region::Region;
+use crate::scale::{Scalable, Scale};
+
+/// The order of the bytes of each pixel in a [`PixelBuffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Red, green, blue, then alpha.
+    Rgba8,
+    /// Blue, green, red, then alpha.
+    ///
+    /// This is the layout that X11 and Wayland use, so it is presented without conversion.
+    Bgra8,
+}
+
+/// An image, which can be shown in a window with [`WindowHandle::present_pixels`].
+///
+/// Each pixel has four bytes, and there is no padding between rows. The color channels are
+/// premultiplied by the alpha, which is ignored unless the window is transparent.
+///
+/// [`WindowHandle::present_pixels`]: crate::WindowHandle::present_pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PixelBuffer {
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    data: Vec<u8>,
+}
+
+impl PixelBuffer {
+    /// Create a buffer, where all pixels are transparent black.
+    pub fn new(width: u32, height: u32, format: PixelFormat) -> PixelBuffer {
+        let len = width as usize * height as usize * 4;
+        PixelBuffer::from_vec(width, height, format, vec![0; len])
+    }
+
+    /// Create a buffer from existing pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` doesn't have four bytes for each pixel.
+    pub fn from_vec(width: u32, height: u32, format: PixelFormat, data: Vec<u8>) -> PixelBuffer {
+        assert_eq!(
+            data.len(),
+            width as usize * height as usize * 4,
+            "the pixel data doesn't match the size of the buffer"
+        );
+        PixelBuffer {
+            width,
+            height,
+            format,
+            data,
+        }
+    }
+
+    /// The width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// The pixels, row by row from the top.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Copy the pixels in `rect` into `out` as BGRA, with `stride` bytes per row.
+    ///
+    /// If `opaque` is true, the alpha is set to 255.
+    #[allow(dead_code)]
+    pub(crate) fn copy_bgra(&self, rect: PixelRect, out: &mut [u8], stride: usize, opaque: bool) {
+        let row_len = rect.width as usize * 4;
+        for row in 0..rect.height as usize {
+            let start = ((rect.y as usize + row) * self.width as usize + rect.x as usize) * 4;
+            let src = &self.data[start..start + row_len];
+            let dst = &mut out[row * stride..row * stride + row_len];
+            dst.copy_from_slice(src);
+            if self.format == PixelFormat::Rgba8 {
+                for pixel in dst.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+            }
+            if opaque {
+                for pixel in dst.chunks_exact_mut(4) {
+                    pixel[3] = 255;
+                }
+            }
+        }
+    }
+
+    /// The parts of the buffer which are covered by `damage`, or the whole buffer if there is no
+    /// damage.
+    ///
+    /// The damage is in display points, and the rectangles are expanded to whole pixels.
+    #[allow(dead_code)]
+    pub(crate) fn damage_rects(&self, damage: Option<&Region>, scale: Scale) -> Vec<PixelRect> {
+        let bounds = Rect::new(0.0, 0.0, self.width as f64, self.height as f64);
+        let Some(damage) = damage else {
+            return vec![PixelRect::from_rect(bounds)];
+        };
+        damage
+            .rects()
+            .iter()
+            .map(|rect| rect.to_px(scale).expand().intersect(bounds))
+            .filter(|rect| rect.area() > 0.0)
+            .map(PixelRect::from_rect)
+            .collect()
+    }
+}
+
+/// A rectangle of pixels in a [`PixelBuffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelRect {
+    /// Convert a rectangle which has already been expanded to whole pixels.
+    fn from_rect(rect: Rect) -> PixelRect {
+        PixelRect {
+            x: rect.x0 as u32,
+            y: rect.y0 as u32,
+            width: rect.width() as u32,
+            height: rect.height() as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn copy_converts_to_bgra() {
+        let buffer = PixelBuffer::from_vec(
+            2,
+            2,
+            PixelFormat::Rgba8,
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16],
+        );
+        let rect = PixelRect {
+            x: 1,
+            y: 0,
+            width: 1,
+            height: 2,
+        };
+        let mut out = [0; 8];
+        buffer.copy_bgra(rect, &mut out, 4, false);
+        assert_eq!(out, [7, 6, 5, 8, 15, 14, 13, 16]);
+        buffer.copy_bgra(rect, &mut out, 4, true);
+        assert_eq!(out, [7, 6, 5, 255, 15, 14, 13, 255]);
+    }
+
+    #[test]
+    fn damage_is_scaled_and_clipped() {
+        let buffer = PixelBuffer::new(20, 20, PixelFormat::Bgra8);
+        let scale = Scale::new(2.0, 2.0);
+        let mut damage = Region::EMPTY;
+        damage.add_rect(Rect::new(0.5, 1.0, 2.0, 3.0));
+        damage.add_rect(Rect::new(8.0, 8.0, 12.0, 12.0));
+        damage.add_rect(Rect::new(30.0, 30.0, 40.0, 40.0));
+        let rects = buffer.damage_rects(Some(&damage), scale);
+        assert_eq!(
+            rects,
+            [
+                PixelRect {
+                    x: 1,
+                    y: 2,
+                    width: 3,
+                    height: 4
+                },
+                PixelRect {
+                    x: 16,
+                    y: 16,
+                    width: 4,
+                    height: 4
+                },
+            ]
+        );
+        let all = buffer.damage_rects(None, scale);
+        assert_eq!(
+            all,
+            [PixelRect {
+                x: 0,
+                y: 0,
+                width: 20,
+                height: 20
+            }]
+        );
+    }
+}
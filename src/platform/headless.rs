@@ -0,0 +1,301 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Driving the headless backend, which is enabled by the `headless` feature.
+//!
+//! The headless backend has no display. Windows only exist in memory, and nothing happens
+//! unless it is requested through these traits. This makes it possible to test applications
+//! deterministically, for example:
+//!
+//! ```ignore
+//! use glazier::platform::headless::{ApplicationExt, InjectedEvent, WindowHandleExt};
+//!
+//! let app = Application::new().unwrap();
+//! let window = WindowBuilder::new(app.clone()).handler(handler).build().unwrap();
+//! window.show();
+//! window.inject(InjectedEvent::Focus(true));
+//! // Deliver the focus change, then paint the window.
+//! while app.step() {}
+//! // Fire the timers which are due in the next second.
+//! app.advance_time(Duration::from_secs(1));
+//! while app.step() {}
+//! ```
+//!
+//! [`Application::run`](crate::Application::run) keeps running until
+//! [`Application::quit`](crate::Application::quit) is called, as on the other platforms. Whenever
+//! there is no work left, the clock jumps to the next timer.
+//!
+//! Only one [`Application`](crate::Application) can exist at a time, so tests which create
+//! one should not run in parallel, for example by using `cargo test -- --test-threads=1`.
+
+use std::time::Duration;
+
+use instant::Instant;
+
+use crate::kurbo::{Point, Size};
+use crate::{KeyEvent, Monitor, PixelBuffer, PointerEvent, Scale};
+
+/// An event which is delivered to a window as if it came from the platform.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum InjectedEvent {
+    /// A key press, which is passed through the focused text field if it isn't handled.
+    KeyDown(KeyEvent),
+    KeyUp(KeyEvent),
+    PointerDown(PointerEvent),
+    PointerUp(PointerEvent),
+    PointerMove(PointerEvent),
+    PointerEnter(PointerEvent),
+    PointerLeave,
+    Wheel(PointerEvent),
+    Zoom(f64),
+    /// The window was resized to this size, in display points.
+    Resize(Size),
+    /// The window was moved to this position, in display points.
+    Move(Point),
+    /// The window gained or lost the focus.
+    ///
+    /// Focusing a window takes the focus from any other window.
+    Focus(bool),
+    /// An input method changed the text being composed in the focused text field.
+    ///
+    /// The text replaces the current composition, or the selection if there isn't one. An
+    /// empty string cancels the composition.
+    CompositionUpdate(String),
+    /// An input method finished composing, and inserted this text in the focused text field.
+    CompositionCommit(String),
+    /// The user asked to close the window.
+    RequestClose,
+}
+
+/// Headless specific extensions to [`Application`](crate::Application).
+pub trait ApplicationExt {
+    /// Do one round of pending work.
+    ///
+    /// This runs the callbacks from [`AppHandle::run_on_main`], delivers the injected events,
+    /// runs the idle callbacks, fires the timers which are due, and paints the invalid parts
+    /// of the visible windows, in that order. Work which is scheduled while doing this waits
+    /// for the next step.
+    ///
+    /// Returns `true` if anything was done.
+    ///
+    /// [`AppHandle::run_on_main`]: crate::AppHandle::run_on_main
+    fn step(&self) -> bool;
+
+    /// Move the clock forward. Timers which become due fire on the next [`step`].
+    ///
+    /// The headless backend has its own clock, which only moves when this is called.
+    /// A timer requested with [`WindowHandle::request_timer`] fires once the clock has been
+    /// moved on by the requested duration.
+    ///
+    /// [`step`]: ApplicationExt::step
+    /// [`WindowHandle::request_timer`]: crate::WindowHandle::request_timer
+    fn advance_time(&self, duration: Duration);
+
+    /// Move the clock forward to the deadline of the next timer.
+    ///
+    /// Returns `false` if there are no timers.
+    fn advance_to_next_timer(&self) -> bool;
+
+    /// The current time of the backend's clock.
+    fn now(&self) -> Instant;
+
+    /// Replace the monitors returned by [`Screen::get_monitors`].
+    ///
+    /// By default, there is a single 1920x1080 monitor.
+    ///
+    /// [`Screen::get_monitors`]: crate::Screen::get_monitors
+    fn set_monitors(&self, monitors: Vec<Monitor>);
+
+    /// Change the scale of all windows, including the ones that are created later.
+    ///
+    /// The default scale is 1.0.
+    fn set_scale(&self, scale: Scale);
+}
+
+/// Headless specific extensions to [`WindowHandle`](crate::WindowHandle).
+pub trait WindowHandleExt {
+    /// Queue an event, which is delivered to the window's handler on the next
+    /// [`step`](ApplicationExt::step).
+    fn inject(&self, event: InjectedEvent);
+
+    /// The contents of the window, as shown by
+    /// [`present_pixels`](crate::WindowHandle::present_pixels).
+    ///
+    /// The pixels are always in [`PixelFormat::Bgra8`](crate::PixelFormat::Bgra8). Returns
+    /// `None` if nothing has been presented yet.
+    fn presented_pixels(&self) -> Option<PixelBuffer>;
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::Mutex;
+
+    use crate::test_util::{Log, Received, RecordingHandler};
+    use crate::{AppHandler, Application, WindowBuilder, WindowHandle};
+
+    use super::*;
+    use static_assertions as sa;
+    sa::assert_impl_all!(Application: ApplicationExt);
+    sa::assert_impl_all!(WindowHandle: WindowHandleExt);
+
+    /// Only one application can exist at a time, so the tests which create one take turns.
+    static APP_LOCK: Mutex<()> = Mutex::new(());
+
+    /// The commands that the app handler received.
+    struct AppLog(Rc<RefCell<Vec<u32>>>);
+
+    impl AppHandler for AppLog {
+        fn command(&mut self, id: u32) {
+            self.0.borrow_mut().push(id);
+        }
+    }
+
+    #[test]
+    fn injected_events_reach_the_handler() {
+        let _lock = APP_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let app = Application::new().unwrap();
+        let log = Log::default();
+        let window = WindowBuilder::new(app.clone())
+            .handler(Box::new(RecordingHandler::new(&log)))
+            .size(Size::new(200.0, 100.0))
+            .build()
+            .unwrap();
+        assert_eq!(
+            log.take(),
+            [Received::Connect, Received::Size(Size::new(200.0, 100.0))]
+        );
+
+        // Hidden windows are not painted.
+        assert!(!app.step());
+        window.show();
+        assert!(app.step());
+        assert_eq!(log.take_names(), ["paint"]);
+
+        window.inject(InjectedEvent::Focus(true));
+        let event = PointerEvent {
+            pos: Point::new(10.0, 20.0),
+            ..PointerEvent::default()
+        };
+        window.inject(InjectedEvent::PointerDown(event));
+        window.inject(InjectedEvent::Resize(Size::new(300.0, 150.0)));
+        assert!(app.step());
+        let received = log.take();
+        let names: Vec<_> = received.iter().map(Received::name).collect();
+        assert_eq!(names, ["got_focus", "pointer_down", "size", "paint"]);
+        assert_eq!(
+            received[1].pointer_event().unwrap().pos,
+            Point::new(10.0, 20.0)
+        );
+        assert_eq!(received[2], Received::Size(Size::new(300.0, 150.0)));
+        assert!(!app.step());
+
+        app.quit();
+        app.run(None);
+        assert_eq!(log.take_names(), ["destroy"]);
+    }
+
+    #[test]
+    fn focus_moves_between_windows() {
+        let _lock = APP_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let app = Application::new().unwrap();
+        let (log1, log2) = (Log::default(), Log::default());
+        let window1 = WindowBuilder::new(app.clone())
+            .handler(Box::new(RecordingHandler::new(&log1)))
+            .build()
+            .unwrap();
+        let window2 = WindowBuilder::new(app.clone())
+            .handler(Box::new(RecordingHandler::new(&log2)))
+            .build()
+            .unwrap();
+        log1.take();
+        log2.take();
+
+        window1.inject(InjectedEvent::Focus(true));
+        while app.step() {}
+        window2.inject(InjectedEvent::Focus(true));
+        while app.step() {}
+        assert_eq!(log1.take_names(), ["got_focus", "lost_focus"]);
+        assert_eq!(log2.take_names(), ["got_focus"]);
+
+        app.quit();
+        app.run(None);
+    }
+
+    #[test]
+    fn timers_follow_the_backend_clock() {
+        let _lock = APP_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let app = Application::new().unwrap();
+        let log = Log::default();
+        let mut handler = RecordingHandler::new(&log);
+        handler.timer = Some(Duration::from_secs(10));
+        let _window = WindowBuilder::new(app.clone())
+            .handler(Box::new(handler))
+            .build()
+            .unwrap();
+        log.take();
+
+        let start = app.now();
+        app.advance_time(Duration::from_secs(5));
+        assert!(!app.step());
+        assert!(log.take().is_empty());
+
+        assert!(app.advance_to_next_timer());
+        assert!(app.now() - start <= Duration::from_secs(10));
+        assert!(app.step());
+        assert_eq!(log.take_names(), ["timer"]);
+        assert!(!app.advance_to_next_timer());
+
+        app.quit();
+        app.run(None);
+    }
+
+    #[test]
+    fn run_waits_for_quit() {
+        let _lock = APP_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let app = Application::new().unwrap();
+        let log = Log::default();
+        let mut handler = RecordingHandler::new(&log);
+        handler.timer = Some(Duration::from_secs(10));
+        let _window = WindowBuilder::new(app.clone())
+            .handler(Box::new(handler))
+            .build()
+            .unwrap();
+        log.take();
+
+        let handle = app.get_handle().unwrap();
+        let quitter = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            handle.run_on_main(|_| Application::global().quit());
+        });
+        app.run(None);
+        quitter.join().unwrap();
+        assert_eq!(log.take_names(), ["timer", "destroy"]);
+    }
+
+    #[test]
+    fn run_on_main_gets_the_app_handler() {
+        let _lock = APP_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let app = Application::new().unwrap();
+        let commands = Rc::new(RefCell::new(Vec::new()));
+        let handle = app.get_handle().unwrap();
+        handle.run_on_main(|handler| handler.unwrap().command(1));
+        handle.run_on_main(|handler| handler.unwrap().command(2));
+        handle.run_on_main(|_| Application::global().quit());
+        app.run(Some(Box::new(AppLog(commands.clone()))));
+        assert_eq!(commands.take(), [1, 2]);
+    }
+}
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 //! Linux specific extensions.
+use std::time::Duration;
+
 use crate::Clipboard;
 
 /// Linux specific extensions to [`Application`]
@@ -23,6 +25,17 @@ pub trait ApplicationExt {
     ///
     /// This is useful for middle mouse paste.
     fn primary_clipboard(&self) -> Clipboard;
+
+    /// Returns the interval between blinks of the text cursor, if the desktop configured it.
+    ///
+    /// This comes from the XSETTINGS on X11, and is always `None` on Wayland.
+    fn cursor_blink_interval(&self) -> Option<Duration>;
+
+    /// Returns the name of the desktop theme, such as `Adwaita-dark`, if the desktop
+    /// configured it.
+    ///
+    /// This comes from the XSETTINGS on X11, and is always `None` on Wayland.
+    fn theme_name(&self) -> Option<String>;
 }
 
 #[cfg(test)]
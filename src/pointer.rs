@@ -1,3 +1,5 @@
+use instant::Instant;
+
 use crate::kurbo::{Point, Size, Vec2};
 use crate::Modifiers;
 
@@ -13,6 +15,7 @@ use crate::Modifiers;
 // There is a small conversion cost, but azimuth/altitude is more accurate for large tilts so it's the better
 // base representation.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PenInclination {
     pub azimuth: Angle,
     pub altitude: Angle,
@@ -39,6 +42,7 @@ pub struct PenInclinationTilt {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Angle {
     radians: f64,
 }
@@ -139,6 +143,7 @@ impl PenInclination {
 ///
 /// [PointerEvents]: (https://www.w3.org/TR/pointerevents3)
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PenInfo {
     /// The pressure of the pen against the tablet ranging from `0.0` (no pressure) to `1.0` (maximum pressure).
     pub pressure: f64,
@@ -149,10 +154,33 @@ pub struct PenInfo {
     pub inclination: PenInclination,
     /// How much has the pen been twisted around its axis. In the range `[0, 2π)` radians.
     pub twist: Angle,
+    /// The distance of the pen from the tablet while it hovers, ranging from `0.0` (touching)
+    /// to `1.0` (the furthest it can be detected), if the device reports it.
+    pub distance: Option<f64>,
+    /// The kind of tool, which usually depends on the end of the pen which is used.
+    pub tool: PenTool,
+    /// The serial number of the tool, which tells apart several pens of the same kind,
+    /// if the device reports it.
+    pub serial: Option<u64>,
+    /// The buttons on the barrel of the pen which are held. These are also in
+    /// [`PointerEvent::buttons`], where touching the tablet is the primary button.
+    pub barrel_buttons: PointerButtons,
 }
 
 impl PenInfo {}
 
+/// The kind of tool of a pen event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PenTool {
+    #[default]
+    Pen,
+    Eraser,
+    Brush,
+    Pencil,
+    Airbrush,
+}
+
 /// Various properties of a touch event.
 ///
 /// These follow the web [PointerEvents] specification fairly closely, so see those
@@ -160,6 +188,7 @@ impl PenInfo {}
 ///
 /// [PointerEvents]: (https://www.w3.org/TR/pointerevents3)
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TouchInfo {
     pub contact_geometry: Size,
     pub pressure: f32,
@@ -167,9 +196,77 @@ pub struct TouchInfo {
 }
 
 /// Various properties of a mouse event.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MouseInfo {
+    /// The scroll of a wheel event, with 120 per wheel click or roughly one per display point
+    /// for precise scrolling.
     pub wheel_delta: Vec2,
+    /// The details of the scroll of a wheel event.
+    pub scroll: ScrollInfo,
+}
+
+/// The details of a scroll, as reported by the platform.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScrollInfo {
+    /// The scroll, in `unit`s.
+    pub delta: Vec2,
+    pub unit: ScrollUnit,
+    /// The wheel clicks, which can be fractional for high-resolution wheels. This is zero for
+    /// sources without clicks, like touchpads.
+    pub clicks: Vec2,
+    /// The phase of the scroll, for sources which report when the fingers touch and leave
+    /// the device.
+    pub phase: Option<ScrollPhase>,
+    pub source: ScrollSource,
+}
+
+/// The units of [`ScrollInfo::delta`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScrollUnit {
+    /// Display points.
+    #[default]
+    Pixels,
+    /// Lines of text. Platforms which don't say how far a wheel click scrolls report one line
+    /// per click.
+    Lines,
+    /// Whole pages.
+    Pages,
+}
+
+/// The phase of a scroll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScrollPhase {
+    /// The fingers touched the device and started scrolling.
+    Began,
+    /// The fingers moved.
+    Changed,
+    /// The fingers were lifted, or the momentum ran out.
+    Ended,
+    /// The scroll is continuing from the momentum of the fingers. Only some platforms
+    /// simulate momentum; on the others, applications may add their own kinetic scrolling
+    /// after the scroll ends.
+    Momentum,
+}
+
+/// The kind of device which produced a scroll.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScrollSource {
+    /// The platform doesn't say.
+    #[default]
+    Unknown,
+    /// A wheel which turns in clicks.
+    Wheel,
+    /// Fingers on a touchpad or touchscreen.
+    Finger,
+    /// A continuous device, like a free-spinning wheel or a trackball in scroll mode.
+    Continuous,
+    /// A wheel which is tilted sideways.
+    WheelTilt,
 }
 
 impl Default for PenInfo {
@@ -182,6 +279,10 @@ impl Default for PenInfo {
                 altitude: Angle::degrees(90.0),
                 azimuth: Angle::degrees(0.0),
             },
+            distance: None,
+            tool: PenTool::Pen,
+            serial: None,
+            barrel_buttons: PointerButtons::new(),
         }
     }
 }
@@ -196,6 +297,7 @@ impl Default for TouchInfo {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PointerType {
     Mouse(MouseInfo),
     Pen(PenInfo),
@@ -207,6 +309,7 @@ pub enum PointerType {
 
 /// An indicator of which pointer button was pressed.
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PointerButton {
     /// No mouse button.
@@ -258,6 +361,7 @@ impl PointerButton {
 
 /// A set of [`PointerButton`]s.
 #[derive(PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointerButtons(u8);
 
 fn button_bit(button: PointerButton) -> u8 {
@@ -384,15 +488,27 @@ impl std::fmt::Debug for PointerButtons {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointerId(pub(crate) u64);
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointerEvent {
     pub pointer_id: PointerId,
     pub is_primary: bool,
     pub pointer_type: PointerType,
 
-    // TODO: figure out timestamps.
+    /// When the event happened, if the platform reported it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub time: Option<Instant>,
+    /// The earlier states of the pointer since the previous event, oldest first, which were
+    /// merged into this one.
+    ///
+    /// Drawing applications can use these to follow fast strokes more closely.
+    ///
+    /// Only X11 merges motion events so far. The other platforms, including Wayland, report
+    /// every motion in its own event and always leave this empty.
+    pub coalesced: Vec<PointerSample>,
     pub pos: Point,
     pub buttons: PointerButtons,
     pub modifiers: Modifiers,
@@ -409,6 +525,17 @@ pub struct PointerEvent {
     pub count: u8,
 }
 
+/// An intermediate state of a pointer, which was merged into a later [`PointerEvent`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointerSample {
+    /// When the pointer was in this state, if the platform reported it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub time: Option<Instant>,
+    pub pos: Point,
+    pub pointer_type: PointerType,
+}
+
 // Do we need a way of getting at maxTouchPoints?
 
 impl Default for PointerEvent {
@@ -422,9 +549,9 @@ impl Default for PointerEvent {
             count: 0,
             pointer_id: PointerId(0),
             is_primary: true,
-            pointer_type: PointerType::Mouse(MouseInfo {
-                wheel_delta: Vec2::ZERO,
-            }),
+            pointer_type: PointerType::Mouse(MouseInfo::default()),
+            time: None,
+            coalesced: Vec::new(),
         }
     }
 }
@@ -445,6 +572,59 @@ impl PointerEvent {
     }
 }
 
+/// The phase of a touchpad gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GesturePhase {
+    /// The fingers started the gesture.
+    Begin,
+    /// The fingers moved.
+    Update,
+    /// The fingers were lifted, completing the gesture.
+    End,
+    /// The gesture was cancelled, for example because another finger touched the touchpad.
+    /// Its effects should be undone.
+    Cancel,
+}
+
+/// A kind of touchpad gesture, with its change since the previous event.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Gesture {
+    /// Fingers moving towards or away from each other, and rotating around their center.
+    Pinch {
+        /// The distance between the fingers, relative to the start of the gesture.
+        scale: f64,
+        /// The clockwise rotation since the previous event.
+        rotation: Angle,
+        /// The motion of the center of the fingers, in display points.
+        delta: Vec2,
+    },
+    /// Fingers moving together in the same direction.
+    Swipe {
+        /// The motion of the center of the fingers, in display points.
+        delta: Vec2,
+    },
+    /// Fingers resting on the touchpad, for example to stop kinetic scrolling. It has no
+    /// updates.
+    Hold,
+}
+
+/// A touchpad gesture, reported to [`WinHandler::gesture`].
+///
+/// [`WinHandler::gesture`]: crate::WinHandler::gesture
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GestureEvent {
+    pub gesture: Gesture,
+    pub phase: GesturePhase,
+    /// The number of fingers on the touchpad.
+    pub fingers: u32,
+    /// The position of the pointer, in display points.
+    pub pos: Point,
+    pub modifiers: Modifiers,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,660 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recording the callbacks that windows receive, to reproduce bugs.
+//!
+//! A [`Recorder`] wraps the [`WinHandler`]s of windows, and writes the input they receive to a
+//! file. The file can be read back with a [`Replayer`], which feeds the recorded callbacks into
+//! another handler:
+//!
+//! ```no_run
+//! # use glazier::{record::Recorder, WinHandler, WindowBuilder};
+//! # fn build(builder: WindowBuilder, handler: Box<dyn WinHandler>) -> std::io::Result<()> {
+//! let recorder = Recorder::create("input.recording")?;
+//! let window = builder.handler(recorder.record(handler)).build();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ```no_run
+//! # use glazier::{record::Replayer, WinHandler};
+//! # fn replay(handler: &mut dyn WinHandler) -> std::io::Result<()> {
+//! let replayer = Replayer::open("input.recording")?;
+//! replayer.replay(0, handler);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Recordings are written as one JSON object per line, starting with a header which holds the
+//! version of the format. This is only available with the `serde` feature.
+
+use std::any::Any;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+use instant::Instant;
+use serde::{Deserialize, Serialize};
+
+use crate::dialog::FileInfo;
+use crate::dnd::{DragEvent, DropAction};
+use crate::kurbo::{Point, Rect, Size, Vec2};
+use crate::region::Region;
+use crate::screen::Monitor;
+use crate::text::{Action, Affinity, HitTestPoint, InputHandler, Selection};
+use crate::{
+    FileDialogToken, GestureEvent, IdleToken, KeyEvent, PointerEvent, Scale, TextFieldToken,
+    TimerToken, WinHandler, WindowHandle,
+};
+
+/// The name of the format, in the header of recordings.
+const FORMAT: &str = "glazier-recording";
+
+/// The version of the format, which is increased whenever it changes incompatibly.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+}
+
+/// A callback received by a recorded window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// The time since the recording was started.
+    pub time: Duration,
+    /// The window which received the callback.
+    ///
+    /// Windows are numbered from 0, in the order they were passed to [`Recorder::record`].
+    pub window: u32,
+    pub callback: Callback,
+}
+
+/// The recorded [`WinHandler`] and [`InputHandler`] calls.
+///
+/// The input handler calls are only recorded between the [`Callback::AcquireInputLock`] and
+/// [`Callback::ReleaseInputLock`] which surround them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum Callback {
+    Size(Size),
+    Scale(Scale),
+    Position(Point),
+    Monitor(Option<Monitor>),
+    Visibility(bool),
+    KeyDown(KeyEvent),
+    KeyUp(KeyEvent),
+    PointerDown(PointerEvent),
+    PointerUp(PointerEvent),
+    PointerMove(PointerEvent),
+    PointerEnter(PointerEvent),
+    PointerLeave,
+    RelativePointerMove(Vec2),
+    Wheel(PointerEvent),
+    Zoom(f64),
+    Gesture(GestureEvent),
+    GotFocus,
+    LostFocus,
+    /// A timer fired. The token is only meaningful within the recording, since a replayed
+    /// handler will have requested its timers with different tokens.
+    Timer(TimerToken),
+    AcquireInputLock {
+        token: TextFieldToken,
+        mutable: bool,
+    },
+    ReleaseInputLock(TextFieldToken),
+    SetSelection(Selection),
+    SetCompositionRange(Option<Range<usize>>),
+    ReplaceRange {
+        range: Range<usize>,
+        text: String,
+    },
+    HandleAction(Action),
+}
+
+/// Writes the callbacks received by windows to a recording.
+///
+/// This can be cloned, to record several windows into the same recording.
+#[derive(Clone)]
+pub struct Recorder(Rc<RecorderState>);
+
+struct RecorderState {
+    writer: RefCell<Box<dyn Write>>,
+    start: Instant,
+    next_window: Cell<u32>,
+    /// Whether writing has failed, so that the error is only logged once.
+    failed: Cell<bool>,
+}
+
+impl Recorder {
+    /// Start a recording in a new file, replacing the file if it exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Recorder> {
+        Recorder::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Start a recording, which is written to `writer`.
+    pub fn new(writer: impl Write + 'static) -> io::Result<Recorder> {
+        let mut writer: Box<dyn Write> = Box::new(writer);
+        let header = Header {
+            format: FORMAT.into(),
+            version: FORMAT_VERSION,
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writeln!(writer)?;
+        writer.flush()?;
+        Ok(Recorder(Rc::new(RecorderState {
+            writer: RefCell::new(writer),
+            start: Instant::now(),
+            next_window: Cell::new(0),
+            failed: Cell::new(false),
+        })))
+    }
+
+    /// Wrap the handler of a window, so that the callbacks it receives are recorded.
+    pub fn record(&self, handler: Box<dyn WinHandler>) -> Box<dyn WinHandler> {
+        let window = self.0.next_window.get();
+        self.0.next_window.set(window + 1);
+        Box::new(RecordingHandler {
+            inner: handler,
+            recorder: self.clone(),
+            window,
+        })
+    }
+
+    fn write(&self, window: u32, callback: Callback) {
+        let event = RecordedEvent {
+            time: self.0.start.elapsed(),
+            window,
+            callback,
+        };
+        let mut writer = self.0.writer.borrow_mut();
+        // Each event is flushed, so that the recording survives a crash.
+        let result = serde_json::to_writer(&mut *writer, &event)
+            .map_err(io::Error::from)
+            .and_then(|()| writeln!(writer))
+            .and_then(|()| writer.flush());
+        if let Err(err) = result {
+            if !self.0.failed.replace(true) {
+                tracing::error!("failed to write to the recording: {err}");
+            }
+        }
+    }
+}
+
+struct RecordingHandler {
+    inner: Box<dyn WinHandler>,
+    recorder: Recorder,
+    window: u32,
+}
+
+impl RecordingHandler {
+    fn record(&self, callback: Callback) {
+        self.recorder.write(self.window, callback);
+    }
+}
+
+impl WinHandler for RecordingHandler {
+    fn connect(&mut self, handle: &WindowHandle) {
+        self.inner.connect(handle)
+    }
+
+    fn size(&mut self, size: Size) {
+        self.record(Callback::Size(size));
+        self.inner.size(size)
+    }
+
+    fn scale(&mut self, scale: Scale) {
+        self.record(Callback::Scale(scale));
+        self.inner.scale(scale)
+    }
+
+    fn position(&mut self, position: Point) {
+        self.record(Callback::Position(position));
+        self.inner.position(position)
+    }
+
+    fn monitor(&mut self, monitor: Option<Monitor>) {
+        self.record(Callback::Monitor(monitor));
+        self.inner.monitor(monitor)
+    }
+
+    fn visibility(&mut self, visible: bool) {
+        self.record(Callback::Visibility(visible));
+        self.inner.visibility(visible)
+    }
+
+    fn prepare_paint(&mut self) {
+        self.inner.prepare_paint()
+    }
+
+    fn paint(&mut self, invalid: &Region) {
+        self.inner.paint(invalid)
+    }
+
+    #[cfg(feature = "accesskit")]
+    fn accesskit_tree(&mut self) -> accesskit::TreeUpdate {
+        self.inner.accesskit_tree()
+    }
+
+    fn rebuild_resources(&mut self) {
+        self.inner.rebuild_resources()
+    }
+
+    fn command(&mut self, id: u32) {
+        self.inner.command(id)
+    }
+
+    fn save_as(&mut self, token: FileDialogToken, file: Option<FileInfo>) {
+        self.inner.save_as(token, file)
+    }
+
+    fn open_file(&mut self, token: FileDialogToken, file: Option<FileInfo>) {
+        self.inner.open_file(token, file)
+    }
+
+    fn open_files(&mut self, token: FileDialogToken, files: Vec<FileInfo>) {
+        self.inner.open_files(token, files)
+    }
+
+    fn key_down(&mut self, event: KeyEvent) -> bool {
+        self.record(Callback::KeyDown(event.clone()));
+        self.inner.key_down(event)
+    }
+
+    fn key_up(&mut self, event: KeyEvent) {
+        self.record(Callback::KeyUp(event.clone()));
+        self.inner.key_up(event)
+    }
+
+    fn acquire_input_lock(
+        &mut self,
+        token: TextFieldToken,
+        mutable: bool,
+    ) -> Box<dyn InputHandler> {
+        self.record(Callback::AcquireInputLock { token, mutable });
+        Box::new(RecordingInputHandler {
+            inner: self.inner.acquire_input_lock(token, mutable),
+            recorder: self.recorder.clone(),
+            window: self.window,
+        })
+    }
+
+    fn release_input_lock(&mut self, token: TextFieldToken) {
+        self.record(Callback::ReleaseInputLock(token));
+        self.inner.release_input_lock(token)
+    }
+
+    fn zoom(&mut self, delta: f64) {
+        self.record(Callback::Zoom(delta));
+        self.inner.zoom(delta)
+    }
+
+    fn gesture(&mut self, event: GestureEvent) {
+        self.record(Callback::Gesture(event.clone()));
+        self.inner.gesture(event)
+    }
+
+    fn wheel(&mut self, event: PointerEvent) {
+        self.record(Callback::Wheel(event.clone()));
+        self.inner.wheel(event)
+    }
+
+    fn pointer_move(&mut self, event: PointerEvent) {
+        self.record(Callback::PointerMove(event.clone()));
+        self.inner.pointer_move(event)
+    }
+
+    fn pointer_down(&mut self, event: PointerEvent) {
+        self.record(Callback::PointerDown(event.clone()));
+        self.inner.pointer_down(event)
+    }
+
+    fn pointer_up(&mut self, event: PointerEvent) {
+        self.record(Callback::PointerUp(event.clone()));
+        self.inner.pointer_up(event)
+    }
+
+    fn pointer_enter(&mut self, event: PointerEvent) {
+        self.record(Callback::PointerEnter(event.clone()));
+        self.inner.pointer_enter(event)
+    }
+
+    fn pointer_leave(&mut self) {
+        self.record(Callback::PointerLeave);
+        self.inner.pointer_leave()
+    }
+
+    fn relative_pointer_move(&mut self, delta: Vec2) {
+        self.record(Callback::RelativePointerMove(delta));
+        self.inner.relative_pointer_move(delta)
+    }
+
+    fn drag_enter(&mut self, event: &DragEvent) -> DropAction {
+        self.inner.drag_enter(event)
+    }
+
+    fn drag_over(&mut self, event: &DragEvent) -> DropAction {
+        self.inner.drag_over(event)
+    }
+
+    fn drag_leave(&mut self) {
+        self.inner.drag_leave()
+    }
+
+    fn drag_drop(&mut self, event: &DragEvent) -> DropAction {
+        self.inner.drag_drop(event)
+    }
+
+    fn dnd_finished(&mut self, action: DropAction) {
+        self.inner.dnd_finished(action)
+    }
+
+    fn timer(&mut self, token: TimerToken) {
+        self.record(Callback::Timer(token));
+        self.inner.timer(token)
+    }
+
+    fn got_focus(&mut self) {
+        self.record(Callback::GotFocus);
+        self.inner.got_focus()
+    }
+
+    fn lost_focus(&mut self) {
+        self.record(Callback::LostFocus);
+        self.inner.lost_focus()
+    }
+
+    fn request_close(&mut self) {
+        self.inner.request_close()
+    }
+
+    fn destroy(&mut self) {
+        self.inner.destroy()
+    }
+
+    fn idle(&mut self, token: IdleToken) {
+        self.inner.idle(token)
+    }
+
+    #[cfg(feature = "accesskit")]
+    fn accesskit_action(&mut self, request: accesskit::ActionRequest) {
+        self.inner.accesskit_action(request)
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        // Idle callbacks downcast this to the application's handler.
+        self.inner.as_any()
+    }
+}
+
+struct RecordingInputHandler {
+    inner: Box<dyn InputHandler>,
+    recorder: Recorder,
+    window: u32,
+}
+
+impl RecordingInputHandler {
+    fn record(&self, callback: Callback) {
+        self.recorder.write(self.window, callback);
+    }
+}
+
+impl InputHandler for RecordingInputHandler {
+    fn selection(&self) -> Selection {
+        self.inner.selection()
+    }
+
+    fn set_selection(&mut self, selection: Selection) {
+        self.record(Callback::SetSelection(selection));
+        self.inner.set_selection(selection)
+    }
+
+    fn composition_range(&self) -> Option<Range<usize>> {
+        self.inner.composition_range()
+    }
+
+    fn set_composition_range(&mut self, range: Option<Range<usize>>) {
+        self.record(Callback::SetCompositionRange(range.clone()));
+        self.inner.set_composition_range(range)
+    }
+
+    fn is_char_boundary(&self, i: usize) -> bool {
+        self.inner.is_char_boundary(i)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    fn slice(&self, range: Range<usize>) -> Cow<str> {
+        self.inner.slice(range)
+    }
+
+    fn utf8_to_utf16(&self, utf8_range: Range<usize>) -> usize {
+        self.inner.utf8_to_utf16(utf8_range)
+    }
+
+    fn utf16_to_utf8(&self, utf16_range: Range<usize>) -> usize {
+        self.inner.utf16_to_utf8(utf16_range)
+    }
+
+    fn replace_range(&mut self, range: Range<usize>, text: &str) {
+        self.record(Callback::ReplaceRange {
+            range: range.clone(),
+            text: text.into(),
+        });
+        self.inner.replace_range(range, text)
+    }
+
+    fn hit_test_point(&self, point: Point) -> HitTestPoint {
+        self.inner.hit_test_point(point)
+    }
+
+    fn line_range(&self, index: usize, affinity: Affinity) -> Range<usize> {
+        self.inner.line_range(index, affinity)
+    }
+
+    fn bounding_box(&self) -> Option<Rect> {
+        self.inner.bounding_box()
+    }
+
+    fn slice_bounding_box(&self, range: Range<usize>) -> Option<Rect> {
+        self.inner.slice_bounding_box(range)
+    }
+
+    fn handle_action(&mut self, action: Action) {
+        self.record(Callback::HandleAction(action));
+        self.inner.handle_action(action)
+    }
+}
+
+/// Reads a recording, to feed it into a handler.
+pub struct Replayer {
+    events: Vec<RecordedEvent>,
+}
+
+impl Replayer {
+    /// Read a recording from a file.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Replayer> {
+        Replayer::new(BufReader::new(File::open(path)?))
+    }
+
+    /// Read a recording.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the recording can't be read, or was written with a different version of the
+    /// format.
+    pub fn new(reader: impl BufRead) -> io::Result<Replayer> {
+        let mut lines = reader.lines();
+        let header = lines
+            .next()
+            .ok_or_else(|| invalid_data("the recording is empty"))??;
+        let header: Header = serde_json::from_str(&header)?;
+        if header.format != FORMAT {
+            return Err(invalid_data("this is not a glazier recording"));
+        }
+        if header.version != FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "recordings of version {} are not supported, only version {FORMAT_VERSION}",
+                header.version
+            )));
+        }
+        let mut events = Vec::new();
+        for line in lines {
+            let line = line?;
+            if !line.is_empty() {
+                events.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Replayer { events })
+    }
+
+    /// The events of all of the windows, in the order they were recorded.
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Call the methods of `handler` with the recorded callbacks of a window.
+    ///
+    /// The handler should already be connected to a window. The callbacks are made
+    /// straight away, in the order they were recorded.
+    pub fn replay(&self, window: u32, handler: &mut dyn WinHandler) {
+        let mut input_handler: Option<Box<dyn InputHandler>> = None;
+        for event in self.events.iter().filter(|it| it.window == window) {
+            match event.callback.clone() {
+                Callback::Size(size) => handler.size(size),
+                Callback::Scale(scale) => handler.scale(scale),
+                Callback::Position(position) => handler.position(position),
+                Callback::Monitor(monitor) => handler.monitor(monitor),
+                Callback::Visibility(visible) => handler.visibility(visible),
+                Callback::KeyDown(event) => {
+                    handler.key_down(event);
+                }
+                Callback::KeyUp(event) => handler.key_up(event),
+                Callback::PointerDown(event) => handler.pointer_down(event),
+                Callback::PointerUp(event) => handler.pointer_up(event),
+                Callback::PointerMove(event) => handler.pointer_move(event),
+                Callback::PointerEnter(event) => handler.pointer_enter(event),
+                Callback::PointerLeave => handler.pointer_leave(),
+                Callback::RelativePointerMove(delta) => handler.relative_pointer_move(delta),
+                Callback::Wheel(event) => handler.wheel(event),
+                Callback::Zoom(delta) => handler.zoom(delta),
+                Callback::Gesture(event) => handler.gesture(event),
+                Callback::GotFocus => handler.got_focus(),
+                Callback::LostFocus => handler.lost_focus(),
+                Callback::Timer(token) => handler.timer(token),
+                Callback::AcquireInputLock { token, mutable } => {
+                    input_handler = Some(handler.acquire_input_lock(token, mutable));
+                }
+                Callback::ReleaseInputLock(token) => {
+                    input_handler = None;
+                    handler.release_input_lock(token);
+                }
+                Callback::SetSelection(selection) => {
+                    if let Some(input_handler) = input_handler.as_mut() {
+                        input_handler.set_selection(selection);
+                    }
+                }
+                Callback::SetCompositionRange(range) => {
+                    if let Some(input_handler) = input_handler.as_mut() {
+                        input_handler.set_composition_range(range);
+                    }
+                }
+                Callback::ReplaceRange { range, text } => {
+                    if let Some(input_handler) = input_handler.as_mut() {
+                        input_handler.replace_range(range, &text);
+                    }
+                }
+                Callback::HandleAction(action) => {
+                    if let Some(input_handler) = input_handler.as_mut() {
+                        input_handler.handle_action(action);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{Log, Received, RecordingHandler};
+    use crate::Modifiers;
+
+    /// A writer which can be read after it is given to the recorder.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let buffer = SharedBuffer::default();
+        let recorder = Recorder::new(buffer.clone()).unwrap();
+        let mut first = recorder.record(Box::<RecordingHandler>::default());
+        let mut second = recorder.record(Box::<RecordingHandler>::default());
+        let key = KeyEvent::for_test(Modifiers::empty(), "a");
+        first.size(Size::new(100.0, 50.0));
+        second.got_focus();
+        first.key_down(key.clone());
+
+        let data = buffer.0.borrow().clone();
+        let replayer = Replayer::new(&data[..]).unwrap();
+        let windows: Vec<_> = replayer.events().iter().map(|it| it.window).collect();
+        assert_eq!(windows, [0, 1, 0]);
+
+        let log = Log::default();
+        replayer.replay(0, &mut RecordingHandler::new(&log));
+        assert_eq!(
+            log.take(),
+            [
+                Received::Size(Size::new(100.0, 50.0)),
+                Received::KeyDown(key)
+            ]
+        );
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let data = format!(
+            "{{\"format\":\"{FORMAT}\",\"version\":{}}}\n",
+            FORMAT_VERSION + 1
+        );
+        let err = Replayer::new(data.as_bytes()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -42,6 +42,7 @@ use crate::kurbo::{Insets, Line, Point, Rect, Size, Vec2};
 ///
 /// [in the Druid book]: https://linebender.org/druid/07_resolution_independence.html
 #[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Scale {
     /// The scale factor on the x axis.
     x: f64,
@@ -22,7 +22,8 @@ use std::fmt::Display;
 /// Monitor struct containing data about a monitor on the system
 ///
 /// Use [`Screen::get_monitors()`] to return a `Vec<Monitor>` of all the monitors on the system
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Monitor {
     primary: bool,
     rect: Rect,
@@ -30,13 +31,16 @@ pub struct Monitor {
     // https://developer.apple.com/documentation/appkit/nsscreen/1388369-visibleframe
     // https://developer.gnome.org/gdk3/stable/GdkMonitor.html#gdk-monitor-get-workarea
     // https://docs.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-monitorinfo
-    // Unsure about x11
+    // https://specifications.freedesktop.org/wm-spec/wm-spec-1.3.html#idm45805408014032
     work_rect: Rect,
 }
 
 impl Monitor {
-    #[allow(dead_code)]
-    pub(crate) fn new(primary: bool, rect: Rect, work_rect: Rect) -> Self {
+    /// Create a new `Monitor`.
+    ///
+    /// This is only needed to describe the virtual monitors of the headless backend;
+    /// the monitors of the system are returned by [`Screen::get_monitors()`].
+    pub fn new(primary: bool, rect: Rect, work_rect: Rect) -> Self {
         Monitor {
             primary,
             rect,
@@ -61,6 +65,9 @@ impl Monitor {
     }
 }
 
+// The rectangles of monitors are never NaN.
+impl Eq for Monitor {}
+
 impl Display for Monitor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.primary {
@@ -0,0 +1,182 @@
+// Copyright 2023 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers which are shared by the tests.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::kurbo::Size;
+use crate::{KeyEvent, PointerEvent, Region, TimerToken, WinHandler, WindowHandle};
+
+/// A callback that a [`RecordingHandler`] received.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Received {
+    Connect,
+    Size(Size),
+    Paint,
+    KeyDown(KeyEvent),
+    PointerEnter(PointerEvent),
+    PointerMove(PointerEvent),
+    PointerDown(PointerEvent),
+    PointerUp(PointerEvent),
+    PointerLeave,
+    GotFocus,
+    LostFocus,
+    Timer(TimerToken),
+    Destroy,
+}
+
+impl Received {
+    /// The name of the `WinHandler` method.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Received::Connect => "connect",
+            Received::Size(_) => "size",
+            Received::Paint => "paint",
+            Received::KeyDown(_) => "key_down",
+            Received::PointerEnter(_) => "pointer_enter",
+            Received::PointerMove(_) => "pointer_move",
+            Received::PointerDown(_) => "pointer_down",
+            Received::PointerUp(_) => "pointer_up",
+            Received::PointerLeave => "pointer_leave",
+            Received::GotFocus => "got_focus",
+            Received::LostFocus => "lost_focus",
+            Received::Timer(_) => "timer",
+            Received::Destroy => "destroy",
+        }
+    }
+
+    /// The event of a pointer callback.
+    pub(crate) fn pointer_event(&self) -> Option<&PointerEvent> {
+        match self {
+            Received::PointerEnter(event)
+            | Received::PointerMove(event)
+            | Received::PointerDown(event)
+            | Received::PointerUp(event) => Some(event),
+            _ => None,
+        }
+    }
+}
+
+/// The callbacks that a [`RecordingHandler`] received, which can still be read after the
+/// handler was given to a window.
+#[derive(Clone, Default)]
+pub(crate) struct Log(Rc<RefCell<Vec<Received>>>);
+
+impl Log {
+    /// The callbacks since the last call, oldest first.
+    pub(crate) fn take(&self) -> Vec<Received> {
+        self.0.take()
+    }
+
+    /// The names of the callbacks since the last call.
+    pub(crate) fn take_names(&self) -> Vec<&'static str> {
+        self.take().iter().map(Received::name).collect()
+    }
+}
+
+/// A window handler which records the callbacks it receives in a [`Log`].
+#[derive(Default)]
+pub(crate) struct RecordingHandler {
+    log: Log,
+    /// A timer to request when the window is connected.
+    pub(crate) timer: Option<Duration>,
+    handle: WindowHandle,
+}
+
+impl RecordingHandler {
+    pub(crate) fn new(log: &Log) -> RecordingHandler {
+        RecordingHandler {
+            log: log.clone(),
+            ..RecordingHandler::default()
+        }
+    }
+
+    fn push(&self, callback: Received) {
+        self.log.0.borrow_mut().push(callback);
+    }
+}
+
+impl WinHandler for RecordingHandler {
+    fn connect(&mut self, handle: &WindowHandle) {
+        self.push(Received::Connect);
+        self.handle = handle.clone();
+        if let Some(delay) = self.timer {
+            self.handle.request_timer(delay);
+        }
+    }
+
+    fn size(&mut self, size: Size) {
+        self.push(Received::Size(size));
+    }
+
+    fn prepare_paint(&mut self) {}
+
+    fn paint(&mut self, _: &Region) {
+        self.push(Received::Paint);
+    }
+
+    #[cfg(feature = "accesskit")]
+    fn accesskit_tree(&mut self) -> accesskit::TreeUpdate {
+        accesskit::TreeUpdate::default()
+    }
+
+    fn key_down(&mut self, event: KeyEvent) -> bool {
+        self.push(Received::KeyDown(event));
+        true
+    }
+
+    fn pointer_enter(&mut self, event: PointerEvent) {
+        self.push(Received::PointerEnter(event));
+    }
+
+    fn pointer_move(&mut self, event: PointerEvent) {
+        self.push(Received::PointerMove(event));
+    }
+
+    fn pointer_down(&mut self, event: PointerEvent) {
+        self.push(Received::PointerDown(event));
+    }
+
+    fn pointer_up(&mut self, event: PointerEvent) {
+        self.push(Received::PointerUp(event));
+    }
+
+    fn pointer_leave(&mut self) {
+        self.push(Received::PointerLeave);
+    }
+
+    fn got_focus(&mut self) {
+        self.push(Received::GotFocus);
+    }
+
+    fn lost_focus(&mut self) {
+        self.push(Received::LostFocus);
+    }
+
+    fn timer(&mut self, token: TimerToken) {
+        self.push(Received::Timer(token));
+    }
+
+    fn destroy(&mut self) {
+        self.push(Received::Destroy);
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
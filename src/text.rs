@@ -143,6 +143,7 @@ pub enum Event {
 /// word `hello` to be selected, the `anchor` to still be `0`, and the `active`
 /// to now be `5`.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct Selection {
     /// The 'anchor' end of the selection.
@@ -738,6 +739,7 @@ pub(crate) fn simulate_single_input(
 /// `ParagraphStart`.
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Movement {
     /// A movement that stops when it reaches an extended grapheme cluster boundary.
     ///
@@ -779,6 +781,7 @@ pub enum Movement {
 
 /// Indicates a horizontal direction in the text.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     /// The direction visually to the left.
     ///
@@ -835,6 +838,7 @@ pub enum Affinity {
 
 /// Indicates a horizontal direction for writing text.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WritingDirection {
     LeftToRight,
     RightToLeft,
@@ -846,6 +850,7 @@ pub enum WritingDirection {
 /// Indicates a vertical movement in a text document.
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VerticalMovement {
     LineUp,
     LineDown,
@@ -858,6 +863,7 @@ pub enum VerticalMovement {
 /// A special text editing command sent from the platform to the application.
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action {
     /// Moves the selection.
     ///
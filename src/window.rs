@@ -20,17 +20,22 @@ use std::time::Duration;
 
 use crate::application::Application;
 use crate::backend::window as backend;
+use crate::clipboard::ClipboardFormat;
 use crate::common_util::Counter;
 use crate::dialog::{FileDialogOptions, FileInfo};
+use crate::dnd::{DragEvent, DragIcon, DropAction};
 use crate::error::Error;
+use crate::icon::Icon;
 use crate::keyboard::KeyEvent;
-use crate::kurbo::{Insets, Point, Rect, Size};
+use crate::kurbo::{Insets, Point, Rect, Size, Vec2};
 use crate::menu::Menu;
 use crate::mouse::{Cursor, CursorDesc};
+use crate::pixels::PixelBuffer;
 use crate::region::Region;
 use crate::scale::Scale;
+use crate::screen::Monitor;
 use crate::text::{Event, InputHandler};
-use crate::PointerEvent;
+use crate::{GestureEvent, PointerEvent};
 
 use raw_window_handle::{
     HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
@@ -38,6 +43,7 @@ use raw_window_handle::{
 
 /// A token that uniquely identifies a running timer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimerToken(u64);
 
 impl TimerToken {
@@ -63,6 +69,7 @@ impl TimerToken {
 
 /// Uniquely identifies a text input field inside a window.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextFieldToken(u64);
 
 impl TextFieldToken {
@@ -159,8 +166,17 @@ pub enum WindowLevel {
     Tooltip(WindowHandle),
     /// A user interface element such as a dropdown menu or combo box
     DropDown(WindowHandle),
-    /// A modal dialog
+    /// A modal dialog, which blocks the input to its parent while it is open
+    ///
+    /// The input to the parent is blocked on X11, Wayland and Windows. On macOS, the parent
+    /// keeps receiving input, so the application has to ignore it itself.
     Modal(WindowHandle),
+    /// An app window that stays above the other app windows, like a floating video player
+    AlwaysOnTop,
+    /// A small window with tools for its parent, like a palette or a toolbox
+    Utility(WindowHandle),
+    /// A notification that pops up over the other windows, like a message bubble
+    Notification,
 }
 
 impl fmt::Debug for WindowLevel {
@@ -170,6 +186,9 @@ impl fmt::Debug for WindowLevel {
             WindowLevel::Tooltip(_) => write!(f, "Tooltip"),
             WindowLevel::DropDown(_) => write!(f, "DropDown"),
             WindowLevel::Modal(_) => write!(f, "Modal"),
+            WindowLevel::AlwaysOnTop => write!(f, "AlwaysOnTop"),
+            WindowLevel::Utility(_) => write!(f, "Utility"),
+            WindowLevel::Notification => write!(f, "Notification"),
         }
     }
 }
@@ -180,6 +199,45 @@ pub enum WindowState {
     Maximized,
     Minimized,
     Restored,
+    /// The window covers a whole monitor, without decorations.
+    ///
+    /// With `None`, the platform picks the monitor, which is usually the one that the window is
+    /// on. The monitors are returned by [`Screen::get_monitors`].
+    ///
+    /// [`Screen::get_monitors`]: crate::Screen::get_monitors
+    Fullscreen(Option<Monitor>),
+}
+
+/// The edge or corner of a window which the user drags to resize it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResizeEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// How a window holds on to the pointer, see [`WindowHandle::set_pointer_grab`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointerGrab {
+    /// The pointer is free to leave the window.
+    None,
+    /// The window keeps receiving the pointer events after the pointer leaves it.
+    Capture,
+    /// The cursor is hidden and stays in place. The motion of the pointer is reported to
+    /// [`WinHandler::relative_pointer_move`] instead, without being limited by the edges of the
+    /// window or screen.
+    ///
+    /// This is only supported on X11 and Wayland. On Windows the grab is ignored.
+    Lock,
+    /// The pointer can't leave a rectangle of the window, in [display points].
+    ///
+    /// [display points]: crate::Scale
+    Confine(Rect),
 }
 
 /// A handle to a platform window object.
@@ -235,6 +293,46 @@ impl WindowHandle {
         self.0.handle_titlebar(val);
     }
 
+    /// Let the user move the window by dragging the pointer, as if they dragged the titlebar.
+    ///
+    /// This should be called from [`WinHandler::pointer_down`], as the platform takes over the
+    /// pointer until the button that was pressed is released. It does nothing if no button is
+    /// pressed in the window.
+    pub fn begin_move_drag(&self) {
+        self.0.begin_move_drag()
+    }
+
+    /// Let the user resize the window by dragging the pointer, as if they dragged its `edge`.
+    ///
+    /// Like [`begin_move_drag`](WindowHandle::begin_move_drag), this should be called from
+    /// [`WinHandler::pointer_down`].
+    pub fn begin_resize_drag(&self, edge: ResizeEdge) {
+        self.0.begin_resize_drag(edge)
+    }
+
+    /// Show the platform's menu for the window, with items such as minimize and close, at `pos`
+    /// in [display points], relative to the window.
+    ///
+    /// This is the menu that is usually shown when the titlebar is right-clicked, so it should be
+    /// called from [`WinHandler::pointer_down`].
+    ///
+    /// [display points]: crate::Scale
+    pub fn show_window_menu(&self, pos: Point) {
+        self.0.show_window_menu(pos)
+    }
+
+    /// Capture, lock or confine the pointer, or release it with [`PointerGrab::None`].
+    ///
+    /// This should be called while the window is focused, usually from
+    /// [`WinHandler::pointer_down`]. The platform can refuse the grab, and can end it at any
+    /// time, for example when the window loses focus.
+    ///
+    /// This is not implemented on macOS yet, and [`PointerGrab::Lock`] is not supported on
+    /// Windows.
+    pub fn set_pointer_grab(&self, grab: PointerGrab) {
+        self.0.set_pointer_grab(grab)
+    }
+
     /// Set whether the window should show titlebar.
     pub fn show_titlebar(&self, show_titlebar: bool) {
         self.0.show_titlebar(show_titlebar)
@@ -297,6 +395,59 @@ impl WindowHandle {
         self.0.get_size()
     }
 
+    /// Set the window's minimum size in [display points].
+    ///
+    /// This replaces the size given to [`WindowBuilder::min_size`].
+    ///
+    /// [display points]: crate::Scale
+    pub fn set_min_size(&self, size: impl Into<Size>) {
+        self.0.set_min_size(size.into())
+    }
+
+    /// Set the window's maximum size in [display points], or remove the limit with `None`.
+    ///
+    /// [display points]: crate::Scale
+    pub fn set_max_size(&self, size: Option<Size>) {
+        self.0.set_max_size(size)
+    }
+
+    /// Make the user resize the window in steps of `increments`, in [display points], or in any
+    /// step with `None`.
+    ///
+    /// Terminals and other grid based applications use this to keep their size a whole number of
+    /// cells. The steps count from the minimum size.
+    ///
+    /// [display points]: crate::Scale
+    pub fn set_resize_increments(&self, increments: Option<Size>) {
+        self.0.set_resize_increments(increments)
+    }
+
+    /// Keep the ratio of the window's width to its height at `ratio` while the user resizes it,
+    /// or allow any ratio with `None`.
+    pub fn set_aspect_ratio(&self, ratio: Option<f64>) {
+        self.0.set_aspect_ratio(ratio)
+    }
+
+    /// Set the part of the window which receives pointer input, in [display points].
+    ///
+    /// Pointer input outside of the region goes to whatever is below the window, which lets
+    /// shadows and overlays be clicked through. With `None`, the whole window receives input.
+    ///
+    /// [display points]: crate::Scale
+    pub fn set_input_region(&self, region: Option<Region>) {
+        self.0.set_input_region(region)
+    }
+
+    /// Tell the platform which part of the window is fully opaque, in [display points].
+    ///
+    /// This is only an optimization for transparent windows: the platform doesn't need to draw
+    /// what is behind the opaque region. With `None`, no part of the window is known to be opaque.
+    ///
+    /// [display points]: crate::Scale
+    pub fn set_opaque_region(&self, region: Option<Region>) {
+        self.0.set_opaque_region(region)
+    }
+
     /// Bring this window to the front of the window stack and give it focus.
     pub fn bring_to_front_and_focus(&self) {
         self.0.bring_to_front_and_focus()
@@ -324,11 +475,30 @@ impl WindowHandle {
         self.0.invalidate_rect(rect);
     }
 
+    /// Show an image in the window, without using the GPU.
+    ///
+    /// The buffer should have the physical size of the window, which is its size in display
+    /// points multiplied by its [`Scale`]. Only the parts of the buffer in `damage`, which is
+    /// in display points, are updated; `None` updates the whole window.
+    ///
+    /// This should not be combined with another way of drawing to the same window.
+    pub fn present_pixels(&self, buffer: &PixelBuffer, damage: Option<&Region>) {
+        self.0.present_pixels(buffer, damage)
+    }
+
     /// Set the title for this menu.
     pub fn set_title(&self, title: &str) {
         self.0.set_title(title)
     }
 
+    /// Set the icon of the window, or remove it with `None`.
+    ///
+    /// This is currently only implemented on X11, and on Wayland compositors which support the
+    /// xdg-toplevel-icon protocol. Elsewhere it does nothing.
+    pub fn set_icon(&self, icon: Option<&Icon>) {
+        self.0.set_icon(icon)
+    }
+
     /// Set the top-level menu for this window.
     pub fn set_menu(&self, menu: Menu) {
         self.0.set_menu(menu.into_inner())
@@ -421,6 +591,34 @@ impl WindowHandle {
         self.0.show_context_menu(menu.into_inner(), pos)
     }
 
+    /// Set whether the window accepts drops.
+    ///
+    /// When this is enabled, the drag and drop methods of the [`WinHandler`] are called
+    /// when something is dragged over the window. It is disabled by default.
+    pub fn set_drop_target(&self, enabled: bool) {
+        self.0.set_drop_target(enabled)
+    }
+
+    /// Start dragging data out of the window.
+    ///
+    /// This must be called while a pointer button is held down, usually from
+    /// [`WinHandler::pointer_down`] or [`WinHandler::pointer_move`]. The data is offered in all
+    /// of the given `formats`, and the target can choose any of the allowed `actions`. The `icon`
+    /// follows the pointer until the drag ends.
+    ///
+    /// When the drag ends, [`WinHandler::dnd_finished`] is called with the action chosen by the
+    /// target.
+    ///
+    /// This is currently only implemented on Wayland.
+    pub fn start_drag(
+        &self,
+        formats: &[ClipboardFormat],
+        icon: Option<DragIcon>,
+        actions: &[DropAction],
+    ) {
+        self.0.start_drag(formats, icon, actions)
+    }
+
     /// Get a handle that can be used to schedule an idle task.
     pub fn get_idle_handle(&self) -> Option<IdleHandle> {
         self.0.get_idle_handle().map(IdleHandle)
@@ -549,6 +747,14 @@ impl WindowBuilder {
         self
     }
 
+    /// Set the window's initial icon.
+    ///
+    /// See [`WindowHandle::set_icon`] for the platforms that support icons.
+    pub fn icon(mut self, icon: Icon) -> Self {
+        self.0 = self.0.icon(icon);
+        self
+    }
+
     /// Set the window's menu.
     pub fn menu(mut self, menu: Menu) -> Self {
         self.0 = self.0.menu(menu.into_inner());
@@ -592,6 +798,29 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn scale(&mut self, scale: Scale) {}
 
+    /// Called when the window has moved.
+    ///
+    /// The `position` parameter is the new position of the window's top left corner, in
+    /// [display points](crate::Scale), in the same coordinates as
+    /// [`WindowHandle::get_position`].
+    #[allow(unused_variables)]
+    fn position(&mut self, position: Point) {}
+
+    /// Called when the monitor which shows the window has changed.
+    ///
+    /// When the window spans several monitors, this is the monitor which shows the largest part
+    /// of it. `None` means that the window is not shown on any known monitor.
+    #[allow(unused_variables)]
+    fn monitor(&mut self, monitor: Option<Monitor>) {}
+
+    /// Called when the window becomes visible or hidden.
+    ///
+    /// A window is hidden while it is minimized, fully covered by other windows, or otherwise
+    /// not shown to the user, as far as the platform can tell. Applications may use this to
+    /// stop animations and other work whose result cannot be seen.
+    #[allow(unused_variables)]
+    fn visibility(&mut self, visible: bool) {}
+
     /// Request the handler to prepare to paint the window contents.  In particular, if there are
     /// any regions that need to be repainted on the next call to `paint`, the handler should
     /// invalidate those regions by calling [`WindowHandle::invalidate_rect`] or
@@ -696,6 +925,12 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn zoom(&mut self, delta: f64) {}
 
+    /// Called when a touchpad gesture begins, changes or ends.
+    ///
+    /// The changes of the scale of pinches are also reported to [`WinHandler::zoom`].
+    #[allow(unused_variables)]
+    fn gesture(&mut self, event: GestureEvent) {}
+
     /// Called on a mouse wheel event.
     ///
     /// The polarity is the amount to be added to the scroll position,
@@ -723,9 +958,67 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn pointer_up(&mut self, event: PointerEvent) {}
 
+    /// Called when a pointer has entered the application window, before it moves inside it.
+    ///
+    /// The event has the position of the pointer, and the buttons which were already held
+    /// where the platform reports them.
+    #[allow(unused_variables)]
+    fn pointer_enter(&mut self, event: PointerEvent) {}
+
     /// Called when a pointer has left the application window.
     fn pointer_leave(&mut self) {}
 
+    /// Called when the pointer moves while it is locked with [`PointerGrab::Lock`].
+    ///
+    /// `delta` is in display points, with the pointer acceleration applied.
+    #[allow(unused_variables)]
+    fn relative_pointer_move(&mut self, delta: Vec2) {}
+
+    /// Called when a drag enters the window.
+    ///
+    /// This is only called if the window was made a drop target with
+    /// [`WindowHandle::set_drop_target`]. Return the action that would be performed if the data
+    /// was dropped at this position, or [`DropAction::None`] to refuse it.
+    ///
+    /// The handler should pick the format that it would read with
+    /// [`DropData::preferred_format`]. On Wayland, the drop is only accepted in a format that the
+    /// handler asked for, with that or with [`DropData::get_format`].
+    ///
+    /// [`DropData::preferred_format`]: crate::DropData::preferred_format
+    /// [`DropData::get_format`]: crate::DropData::get_format
+    #[allow(unused_variables)]
+    fn drag_enter(&mut self, event: &DragEvent) -> DropAction {
+        DropAction::None
+    }
+
+    /// Called when a drag moves over the window.
+    ///
+    /// The return value is the same as for [`WinHandler::drag_enter`].
+    #[allow(unused_variables)]
+    fn drag_over(&mut self, event: &DragEvent) -> DropAction {
+        DropAction::None
+    }
+
+    /// Called when a drag leaves the window, or is cancelled, without being dropped.
+    fn drag_leave(&mut self) {}
+
+    /// Called when data is dropped onto the window.
+    ///
+    /// This is where the data should be read from [`DragEvent::data`]. Return the action that
+    /// was performed, or [`DropAction::None`] if the drop was refused.
+    #[allow(unused_variables)]
+    fn drag_drop(&mut self, event: &DragEvent) -> DropAction {
+        DropAction::None
+    }
+
+    /// Called when a drag started with [`WindowHandle::start_drag`] ends.
+    ///
+    /// `action` is the action performed by the target. It is [`DropAction::None`] if the drag
+    /// was cancelled or the drop was refused. If it is [`DropAction::Move`], the data should
+    /// now be deleted.
+    #[allow(unused_variables)]
+    fn dnd_finished(&mut self, action: DropAction) {}
+
     /// Called on timer event.
     ///
     /// This is called at (approximately) the requested deadline by a